@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::trie_map::TrieMapNode;
+use crate::*;
+
+/// An [Aho–Corasick automaton](https://en.wikipedia.org/wiki/Aho%E2%80%93Corasick_algorithm) that finds every
+/// dictionary word occurring anywhere inside a text in a single pass.
+///
+/// It's built on top of an existing letter trie. Each node of the trie becomes a state with the same child links,
+/// plus a failure link to the state for the longest proper suffix that's also a prefix in the trie, and an output
+/// link to the nearest state along the failure links that ends a word. When the next character of the text has no
+/// child link the automaton follows failure links instead of starting over, so each character of the text is
+/// looked at only once no matter how many words are in the dictionary.
+///
+/// The text is normalized one character at a time with the same normalizer as the trie so by default matching
+/// ignores case. A character that the normalizer rejects, or whitespace, can't be part of a match. A character
+/// that the normalizer removes entirely, such as a combining accent when diacritics are stripped, is skipped so the
+/// letters on either side of it can still match.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let automaton = AhoCorasick::from_file(
+///     dataset.filename(),
+///     dataset.is_sorted(),
+///     &LoadMethod::Continuous,
+/// );
+///
+/// let words: Vec<String> = automaton
+///     .find_all("An azure creature", &MatchKind::WholeWord)
+///     .into_iter()
+///     .map(|m| m.word)
+///     .collect();
+/// assert_eq!(words, vec!["an", "azure", "creature"]);
+///
+/// let m = &automaton.find_all("Crossed", &MatchKind::LeftmostLongest)[0];
+/// assert_eq!((m.start, m.end, m.word.as_str()), (0, 7, "crossed"));
+/// ```
+pub struct AhoCorasick {
+    states: Vec<State>,
+    normalizer: Normalizer,
+    // The depth of the deepest state, which is the length of the longest word.
+    max_depth: usize,
+}
+
+/// How overlapping matches are reported by `AhoCorasick::find_all()`.
+#[derive(Debug, PartialEq)]
+pub enum MatchKind {
+    /// Report every occurrence of every word, including words inside other words, so "crossed" gives "cross" and
+    /// "crossed".
+    Overlapping,
+    /// Report matches that don't overlap, scanning from the left and preferring the longest word at each position,
+    /// so "crossed" gives only "crossed".
+    LeftmostLongest,
+    /// Report only the occurrences that aren't part of a longer run of letters or digits in the text, so "crossed"
+    /// doesn't match "cross" but "a cross." does.
+    WholeWord,
+}
+
+/// One occurrence of a dictionary word in a text.
+#[derive(Debug, PartialEq)]
+pub struct WordMatch {
+    /// The byte offset in the text where the match starts.
+    pub start: usize,
+    /// The byte offset in the text just past the end of the match, so the matched text is `&text[start..end]`.
+    pub end: usize,
+    /// The word as it is in the trie, which may differ from the text in case or accents.
+    pub word: String,
+}
+
+struct State {
+    next: BTreeMap<char, usize>,
+    fail: usize,
+    // The nearest state reached by following failure links that ends a word.
+    output: Option<usize>,
+    depth: usize,
+    word: Option<String>,
+}
+
+const ROOT: usize = 0;
+
+impl AhoCorasick {
+    /// Build the automaton from an existing letter trie. The trie itself is left unchanged and the automaton keeps
+    /// a copy of its normalizer.
+    pub fn from_trie<V>(trie: &LetterTrieMap<V>) -> Self {
+        let mut states = vec![State::new(0, None)];
+
+        // Copy the trie one level at a time so that each state's failure link points to a state that has already
+        // been completed.
+        let mut queue: VecDeque<(&TrieMapNode<char, V>, usize, String)> = VecDeque::new();
+        queue.push_back((trie.root(), ROOT, String::new()));
+        while let Some((node, state_index, prefix)) = queue.pop_front() {
+            for (c, child_node) in node.children().iter() {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(*c);
+                let word = child_node.value().map(|_| child_prefix.clone());
+                let child_index = states.len();
+                states.push(State::new(states[state_index].depth + 1, word));
+                states[state_index].next.insert(*c, child_index);
+
+                let fail = if state_index == ROOT {
+                    ROOT
+                } else {
+                    Self::goto(&states, states[state_index].fail, *c)
+                };
+                states[child_index].fail = fail;
+                states[child_index].output = if states[fail].word.is_some() {
+                    Some(fail)
+                } else {
+                    states[fail].output
+                };
+                queue.push_back((child_node, child_index, child_prefix));
+            }
+        }
+
+        let max_depth = states.iter().map(|state| state.depth).max().unwrap_or(0);
+        Self {
+            states,
+            normalizer: trie.normalizer().clone(),
+            max_depth,
+        }
+    }
+
+    /// Build the automaton from a word file with one word per line, loading it with any LoadMethod as with
+    /// `LetterTrie::from_file()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading.
+    pub fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
+        let trie = LetterTrieSet::from_file(filename, is_sorted, load_method);
+        Self::from_trie(&trie)
+    }
+
+    /// The number of states, which is the same as the number of nodes in the trie it was built from.
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    // Follow failure links from a state until there's a transition for the character, ending at the root if there
+    // is none.
+    fn goto(states: &[State], state_index: usize, c: char) -> usize {
+        let mut state_index = state_index;
+        loop {
+            if let Some(next_index) = states[state_index].next.get(&c) {
+                return *next_index;
+            }
+            if state_index == ROOT {
+                return ROOT;
+            }
+            state_index = states[state_index].fail;
+        }
+    }
+
+    /// Find the dictionary words in a text, in order of where they start and then from shortest to longest.
+    pub fn find_all(&self, text: &str, match_kind: &MatchKind) -> Vec<WordMatch> {
+        let matches = self.find_overlapping(text);
+        match match_kind {
+            MatchKind::Overlapping => matches,
+            MatchKind::LeftmostLongest => {
+                let mut matches = matches;
+                // Sort by start and then by length with the longest first, then keep each match that starts
+                // after the end of the last one kept.
+                matches.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| b.end.cmp(&a.end)));
+                let mut kept: Vec<WordMatch> = vec![];
+                for m in matches {
+                    if kept.last().is_none_or(|last| m.start >= last.end) {
+                        kept.push(m);
+                    }
+                }
+                kept
+            }
+            MatchKind::WholeWord => matches
+                .into_iter()
+                .filter(|m| {
+                    let before = text[..m.start].chars().next_back();
+                    let after = text[m.end..].chars().next();
+                    !before.is_some_and(char::is_alphanumeric)
+                        && !after.is_some_and(char::is_alphanumeric)
+                })
+                .collect(),
+        }
+    }
+
+    fn find_overlapping(&self, text: &str) -> Vec<WordMatch> {
+        let mut matches = vec![];
+        // For each of the last few normalized characters fed to the automaton, the byte offsets of the text
+        // character it came from. One text character may become several normalized characters, as with "ß" to "ss".
+        // No match is longer than the longest word so older entries are dropped.
+        let mut fed: VecDeque<(usize, usize)> = VecDeque::with_capacity(self.max_depth + 1);
+        let mut state_index = ROOT;
+        for (start, c) in text.char_indices() {
+            let end = start + c.len_utf8();
+            let normalized: Option<Vec<char>> = if self.normalizer.is_default() {
+                Some(c.to_lowercase().collect())
+            } else {
+                self.normalizer.to_chars(c.encode_utf8(&mut [0; 4]))
+            };
+            let normalized = match normalized {
+                Some(normalized) if !normalized.is_empty() => normalized,
+                // Whitespace is trimmed away by the normalizer but still separates words.
+                None => {
+                    state_index = ROOT;
+                    continue;
+                }
+                Some(_) if c.is_whitespace() => {
+                    state_index = ROOT;
+                    continue;
+                }
+                Some(_) => continue,
+            };
+            for normalized_c in normalized {
+                if fed.len() >= self.max_depth {
+                    fed.pop_front();
+                }
+                fed.push_back((start, end));
+                state_index = Self::goto(&self.states, state_index, normalized_c);
+                let mut output_index = if self.states[state_index].word.is_some() {
+                    Some(state_index)
+                } else {
+                    self.states[state_index].output
+                };
+                while let Some(index) = output_index {
+                    let state = &self.states[index];
+                    let first_fed = fed.len() - state.depth;
+                    matches.push(WordMatch {
+                        start: fed[first_fed].0,
+                        end,
+                        word: state.word.clone().unwrap(),
+                    });
+                    output_index = state.output;
+                }
+            }
+        }
+        // Matches ending at the same place were found from longest to shortest.
+        matches.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.end.cmp(&b.end)));
+        matches
+    }
+}
+
+impl State {
+    fn new(depth: usize, word: Option<String>) -> Self {
+        Self {
+            next: BTreeMap::new(),
+            fail: ROOT,
+            output: None,
+            depth,
+            word,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_automaton() -> AhoCorasick {
+        let dataset = Dataset::TestSmallUnsorted;
+        AhoCorasick::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        )
+    }
+
+    fn match_words(matches: Vec<WordMatch>) -> Vec<(usize, usize, String)> {
+        matches
+            .into_iter()
+            .map(|m| (m.start, m.end, m.word))
+            .collect()
+    }
+
+    #[test]
+    fn small_state_count() {
+        assert_eq!(small_automaton().state_count(), 28);
+    }
+
+    #[test]
+    fn overlapping() {
+        let automaton = small_automaton();
+        assert_eq!(
+            match_words(automaton.find_all("xCrossedandy", &MatchKind::Overlapping)),
+            vec![
+                (1, 6, "cross".to_owned()),
+                (1, 8, "crossed".to_owned()),
+                (8, 9, "a".to_owned()),
+                (8, 10, "an".to_owned()),
+                (8, 11, "and".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn leftmost_longest() {
+        let automaton = small_automaton();
+        assert_eq!(
+            match_words(automaton.find_all("crossedandazure", &MatchKind::LeftmostLongest)),
+            vec![
+                (0, 7, "crossed".to_owned()),
+                (7, 10, "and".to_owned()),
+                (10, 15, "azure".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn whole_word() {
+        let automaton = small_automaton();
+        assert_eq!(
+            match_words(automaton.find_all("A cross, crossed.", &MatchKind::WholeWord)),
+            vec![
+                (0, 1, "a".to_owned()),
+                (2, 7, "cross".to_owned()),
+                (9, 16, "crossed".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn failure_links() {
+        let mut trie: LetterTrieMap<()> = LetterTrieMap::new();
+        for word in ["he", "she", "his", "hers"].iter() {
+            trie.insert(*word, ());
+        }
+        let automaton = AhoCorasick::from_trie(&trie);
+        assert_eq!(
+            match_words(automaton.find_all("ushers", &MatchKind::Overlapping)),
+            vec![
+                (1, 4, "she".to_owned()),
+                (2, 4, "he".to_owned()),
+                (2, 6, "hers".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalized_text() {
+        let mut trie: LetterTrieMap<()> =
+            LetterTrieMap::with_normalizer(Normalizer::make_ascii_letters());
+        trie.insert("zurich", ());
+        trie.insert("strasse", ());
+        let automaton = AhoCorasick::from_trie(&trie);
+        let text = "Zürich Straße";
+        let matches = automaton.find_all(text, &MatchKind::Overlapping);
+        assert_eq!(
+            match_words(matches),
+            vec![(0, 7, "zurich".to_owned()), (8, 15, "strasse".to_owned()),]
+        );
+        assert_eq!(&text[8..15], "Straße");
+    }
+
+    #[test]
+    fn skipped_combining_marks() {
+        let mut trie: LetterTrieMap<()> =
+            LetterTrieMap::with_normalizer(Normalizer::make_ascii_letters());
+        trie.insert("zurich", ());
+        let automaton = AhoCorasick::from_trie(&trie);
+        // The umlaut is a separate combining character, which normalizes to nothing.
+        let text = "Zu\u{308}rich zu\u{308} rich";
+        assert_eq!(
+            match_words(automaton.find_all(text, &MatchKind::Overlapping)),
+            vec![(0, 8, "zurich".to_owned())]
+        );
+    }
+
+    #[test]
+    fn long_text() {
+        let automaton = small_automaton();
+        let text = "crossed ".repeat(1_000);
+        let matches = automaton.find_all(&text, &MatchKind::WholeWord);
+        assert_eq!(matches.len(), 1_000);
+        assert_eq!(matches[999].start, 999 * 8);
+        assert_eq!(matches[999].end, 999 * 8 + 7);
+    }
+}
@@ -7,29 +7,54 @@ use std::fmt::{self, Debug};
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::mem;
 use std::rc::{Rc, Weak};
 use std::sync::mpsc;
 use std::thread;
 use std::time::Instant;
 
+use crate::trie_validation::{SubtreeCounts, TrieValidator};
 use crate::*;
 
 // The Rc pointing to a node should always have a count of one except in special cases where additional references are
 // used temporarily to simplify operations like iterating. There will also be an extra strong count when a ParentLink
 // is momentarily upgraded.
-type ChildLink = Rc<RefCell<Node>>;
+type ChildLink<K = char> = Rc<RefCell<Node<K>>>;
 // The weak count of the pointer to a node should always equal that node's number of child nodes.
-type ParentLink = Weak<RefCell<Node>>;
+type ParentLink<K = char> = Weak<RefCell<Node<K>>>;
 
 /// The baseline implementation of a [letter trie]: https://www.geeksforgeeks.org/trie-insert-and-search/ with added
 /// references from nodes to their parents to experiment with Rc and RefCell. Other trees use different approaches
 /// for parent and child links but otherwise work the same.
-pub struct BaseLetterTrie {
-    // The root node's character is a single space which doesn't count toward the words represented by the trie.
-    root: ChildLink,
+///
+/// Like TrieMap, the nodes are generic over the type of key element so the same insert, lookup and merge logic
+/// works for bytes or whole words. The letter trie that implements `LetterTrie` is `BaseLetterTrie`, the case
+/// where the key elements are chars.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let trie: BaseTrie<String> = BaseTrie::new();
+/// trie.add_key("new york city");
+/// assert!(trie.contains_word("New York  City"));
+/// assert!(!trie.contains_word("new york"));
+/// ```
+pub struct BaseTrie<K> {
+    // The root node's key element is `K::root_sentinel()`, such as a single space for a char, which doesn't count
+    // toward the words represented by the trie.
+    root: ChildLink<K>,
+    // Applied to every word or prefix that's added or looked up.
+    normalizer: Normalizer,
+    // A companion trie holding each key in reverse.
+    suffix_index: Option<Box<BaseTrie<K>>>,
 }
 
-impl BaseLetterTrie {
+/// A trie with one letter per node.
+pub type BaseLetterTrie = BaseTrie<char>;
+
+impl<K: KeyElement> BaseTrie<K> {
     /// Constructor for the letter trie. The root of each trie is the same regardless of what words will be added to
     /// the trie so there are no parameters.
     ///
@@ -37,23 +62,40 @@ impl BaseLetterTrie {
     /// ```rust
     /// let mut trie = letter_trie::BaseLetterTrie::new();
     /// ```
-    pub fn new() -> BaseLetterTrie {
-        let c = ' ';
+    pub fn new() -> Self {
+        Self::with_normalizer(Normalizer::make_default())
+    }
+
+    /// Constructor for a letter trie that normalizes words with the given normalizer instead of the default
+    /// `trim().to_lowercase()`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::with_normalizer(Normalizer::make_exact());
+    /// ```
+    pub fn with_normalizer(normalizer: Normalizer) -> Self {
+        let c = K::root_sentinel();
         let depth = 0;
         let parent = None;
         let is_word = false;
-        let root = BaseLetterTrie::make_child_node_and_link(c, parent, depth, is_word);
+        let root = Self::make_child_node_and_link(c, parent, depth, is_word);
         debug_assert!(Self::child_link_has_normal_ref_counts(&root));
-        BaseLetterTrie { root }
+        Self {
+            root,
+            normalizer,
+            suffix_index: None,
+        }
     }
 
     // Create an Rc<RefCell<Node>> for a given character.
     fn make_child_node_and_link(
-        c: char,
-        parent: Option<ParentLink>,
+        c: K,
+        parent: Option<ParentLink<K>>,
         depth: usize,
         is_word: bool,
-    ) -> ChildLink {
+    ) -> ChildLink<K> {
         debug_assert!(Self::opt_parent_link_has_normal_ref_counts(&parent));
         let children = BTreeMap::new();
         Rc::new(RefCell::new(Node {
@@ -69,93 +111,376 @@ impl BaseLetterTrie {
         }))
     }
 
-    fn add_word(&self, s: &str) {
-        let s = s.trim();
-        if !s.is_empty() {
+    /// Add a key after splitting it into key elements, which for text means passing it through the trie's
+    /// normalizer. An empty key or one that the normalizer rejects is ignored.
+    pub fn add_key<Q: ToKeyElements<K> + ?Sized>(&self, key: &Q) {
+        if let Some(v) = key.to_key_elements(&self.normalizer) {
             debug_assert!(!self.is_frozen());
-            let v: Vec<char> = s.to_lowercase().chars().collect();
             let v_len = v.len();
             self.add_from_vec_chars(&v, v_len, 0);
         }
     }
 
+    /// Returns true if a key was added as a whole, not just as the start of a longer key.
+    pub fn contains_word<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> bool {
+        let v = match word.to_key_elements(&self.normalizer) {
+            Some(v) if !v.is_empty() => v,
+            _ => return false,
+        };
+        let mut rc = Rc::clone(&self.root);
+        for c in v.iter() {
+            let rc_opt = rc.borrow().children.get(c).map(Rc::clone);
+            match rc_opt {
+                Some(rc_next) => rc = rc_next,
+                None => return false,
+            }
+        }
+        let is_word = rc.borrow().is_word;
+        is_word
+    }
+
     // This is called once for every word, and should be called only on the root.
-    pub fn add_from_vec_chars(&self, v: &[char], v_len: usize, char_index: usize) {
+    pub fn add_from_vec_chars(&self, v: &[K], v_len: usize, char_index: usize) {
+        self.add_from_vec_chars_counted(v, v_len, char_index, None);
+    }
+
+    /// Remove a key that was added as a whole and return true if it was there. Any nodes that are left without a
+    /// word either at the node or below it are removed as well.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let trie = BaseLetterTrie::new();
+    /// trie.add_word("cross");
+    /// trie.add_word("crossed");
+    /// assert!(trie.remove_key("crossed"));
+    /// assert!(!trie.remove_key("crossed"));
+    /// assert!(trie.contains_word("cross"));
+    /// assert_eq!(trie.to_fixed_node().node_count, 6);
+    /// ```
+    pub fn remove_key<Q: ToKeyElements<K> + ?Sized>(&self, key: &Q) -> bool {
+        let v = match key.to_key_elements(&self.normalizer) {
+            Some(v) if !v.is_empty() => v,
+            _ => return false,
+        };
+        debug_assert!(!self.is_frozen());
+        let is_removed = self.root.borrow_mut().remove_one_node(&v, 0);
+        if is_removed {
+            if let Some(suffix_index) = &self.suffix_index {
+                let reversed: Vec<K> = v.iter().rev().cloned().collect();
+                suffix_index.root.borrow_mut().remove_one_node(&reversed, 0);
+            }
+        }
+        is_removed
+    }
+
+    /// Start keeping a companion trie with every key in reverse, as with `TrieMap::enable_suffix_index()`. The
+    /// companion trie is built from the words already in the trie and from then on it's kept in sync by
+    /// `add_key()`, `remove_key()` and `merge()`.
+    pub fn enable_suffix_index(&mut self) {
+        let suffix_index = BaseTrie::new();
+        let mut keys = vec![];
+        self.root.borrow().add_keys(&mut vec![], &mut keys);
+        for v in keys {
+            let reversed: Vec<K> = v.into_iter().rev().collect();
+            suffix_index.add_from_vec_chars(&reversed, reversed.len(), 0);
+        }
+        if self.is_frozen() {
+            suffix_index.root.borrow_mut().freeze();
+        }
+        self.suffix_index = Some(Box::new(suffix_index));
+    }
+
+    /// Stop keeping the companion trie of reversed keys.
+    pub fn disable_suffix_index(&mut self) {
+        self.suffix_index = None;
+    }
+
+    /// Returns true if the trie is keeping a companion trie of reversed keys.
+    pub fn has_suffix_index(&self) -> bool {
+        self.suffix_index.is_some()
+    }
+
+    fn add_to_suffix_index(&self, v: &[K]) {
+        if v.is_empty() {
+            return;
+        }
+        if let Some(suffix_index) = &self.suffix_index {
+            let reversed: Vec<K> = v.iter().rev().cloned().collect();
+            suffix_index.add_from_vec_chars(&reversed, reversed.len(), 0);
+        }
+    }
+
+    /// Find the words that end with a suffix, in alphabetical order. The suffix itself is included if it's a word.
+    /// Without a suffix index this has to check every word in the trie.
+    pub fn words_with_suffix<Q: ToKeyElements<K> + ?Sized>(&self, suffix: &Q) -> Vec<K::Key> {
+        match suffix.to_key_elements(&self.normalizer) {
+            Some(suffix) => self.words_with_prefix_and_suffix_elements(&[], &suffix),
+            None => vec![],
+        }
+    }
+
+    /// Find the words that start with a prefix and end with a suffix, in alphabetical order. The prefix and suffix
+    /// may overlap. With a suffix index, the subtree below the prefix and the one below the reversed suffix are
+    /// compared only if the trie is frozen so that both word counts are cached, otherwise the suffix side is
+    /// walked.
+    pub fn words_with_prefix_and_suffix<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+        suffix: &Q,
+    ) -> Vec<K::Key> {
+        match (
+            prefix.to_key_elements(&self.normalizer),
+            suffix.to_key_elements(&self.normalizer),
+        ) {
+            (Some(prefix), Some(suffix)) => {
+                self.words_with_prefix_and_suffix_elements(&prefix, &suffix)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn words_with_prefix_and_suffix_elements(&self, prefix: &[K], suffix: &[K]) -> Vec<K::Key> {
+        let prefix_rc = match self.find_link(prefix) {
+            Some(rc) => rc,
+            None => return vec![],
+        };
+        let mut keys: Vec<Vec<K>> = vec![];
+        let reversed_suffix: Vec<K> = suffix.iter().rev().cloned().collect();
+        let suffix_rc = self
+            .suffix_index
+            .as_ref()
+            .map(|suffix_index| suffix_index.find_link(&reversed_suffix));
+        let is_suffix_smaller = |suffix_rc: &ChildLink<K>| match (
+            suffix_rc.borrow().word_count,
+            prefix_rc.borrow().word_count,
+        ) {
+            (Some(suffix_count), Some(prefix_count)) => suffix_count < prefix_count,
+            _ => true,
+        };
+        match suffix_rc {
+            Some(None) => return vec![],
+            Some(Some(suffix_rc)) if is_suffix_smaller(&suffix_rc) => {
+                let mut reversed_keys = vec![];
+                suffix_rc
+                    .borrow()
+                    .add_keys(&mut reversed_suffix.clone(), &mut reversed_keys);
+                for reversed in reversed_keys {
+                    let v: Vec<K> = reversed.into_iter().rev().collect();
+                    if v.starts_with(prefix) {
+                        keys.push(v);
+                    }
+                }
+                keys.sort();
+            }
+            _ => {
+                prefix_rc.borrow().add_keys(&mut prefix.to_vec(), &mut keys);
+                keys.retain(|v| v.ends_with(suffix));
+            }
+        }
+        keys.iter().map(|v| K::make_key(v)).collect()
+    }
+
+    // Find the link to the node for a sequence of key elements that have already been normalized, which is the
+    // root for an empty sequence.
+    fn find_link(&self, v: &[K]) -> Option<ChildLink<K>> {
+        let mut rc = Rc::clone(&self.root);
+        for c in v.iter() {
+            let rc_next = rc.borrow().children.get(c).cloned()?;
+            rc = rc_next;
+        }
+        Some(rc)
+    }
+
+    // The same as add_from_vec_chars() while recording the hits, misses and new nodes if there are counts to update.
+    fn add_from_vec_chars_counted(
+        &self,
+        v: &[K],
+        v_len: usize,
+        char_index: usize,
+        counts: Option<&mut InsertCounts>,
+    ) {
         debug_assert!(!self.is_frozen());
-        debug_assert!(self.root.borrow().c == ' ');
+        debug_assert!(self.root.borrow().c == K::root_sentinel());
         if v_len > 0 {
-            BaseLetterTrie::add_from_vec_chars_one_char(&self.root, v, v_len, char_index);
+            self.add_to_suffix_index(&v[char_index..v_len]);
+            Self::add_from_vec_chars_one_char(&self.root, v, v_len, char_index, counts);
         }
     }
 
     // This is called once for every character in every word.
-    fn add_from_vec_chars_one_char(rc: &ChildLink, v: &[char], v_len: usize, char_index: usize) {
-        debug_assert!(Self::child_link_has_normal_ref_counts(&rc));
+    fn add_from_vec_chars_one_char(
+        rc: &ChildLink<K>,
+        v: &[K],
+        v_len: usize,
+        char_index: usize,
+        mut counts: Option<&mut InsertCounts>,
+    ) {
+        debug_assert!(Self::child_link_has_normal_ref_counts(rc));
         if char_index < v_len {
-            let c = v[char_index];
+            let c = v[char_index].clone();
             let is_word = char_index == v_len - 1;
             let mut root = rc.borrow_mut();
             let child_node_opt = root.children.get(&c);
-
-            if USE_CHAR_GET_COUNTER {
-                CharGetCounter::record(child_node_opt.is_some());
+            if let Some(counts) = counts.as_deref_mut() {
+                counts.record(child_node_opt.is_some());
             }
 
             if let Some(child_node_link) = child_node_opt {
-                debug_assert!(Self::child_link_has_normal_ref_counts(&child_node_link));
+                debug_assert!(Self::child_link_has_normal_ref_counts(child_node_link));
                 if is_word {
                     let mut child_node = child_node_link.borrow_mut();
+                    if let Some(counts) = counts.as_deref_mut() {
+                        counts.record_word(child_node.is_word);
+                    }
                     child_node.is_word = true;
                 }
-                BaseLetterTrie::add_from_vec_chars_one_char(
-                    &child_node_link,
+                Self::add_from_vec_chars_one_char(
+                    child_node_link,
                     v,
                     v_len,
                     char_index + 1,
+                    counts,
                 );
             } else {
-                debug_assert!(Self::child_link_has_normal_ref_counts(&rc));
-                let parent: ParentLink = Rc::downgrade(&rc);
+                debug_assert!(Self::child_link_has_normal_ref_counts(rc));
+                let parent: ParentLink<K> = Rc::downgrade(rc);
                 debug_assert!(Self::parent_link_has_normal_ref_counts(&parent));
-                let new_child_link: ChildLink = BaseLetterTrie::make_child_node_and_link(
-                    c,
+                let new_child_link: ChildLink<K> = Self::make_child_node_and_link(
+                    c.clone(),
                     Some(parent),
                     root.depth + 1,
                     is_word,
                 );
-                BaseLetterTrie::add_from_vec_chars_one_char(
+                if let Some(counts) = counts.as_deref_mut() {
+                    counts.record_node();
+                }
+                Self::add_from_vec_chars_one_char(
                     &new_child_link,
                     v,
                     v_len,
                     char_index + 1,
+                    counts,
                 );
                 root.children.insert(c, new_child_link);
             }
         }
     }
 
-    pub fn merge(&self, other: BaseLetterTrie) {
+    pub fn merge(&self, other: Self) {
+        if self.suffix_index.is_some() {
+            let mut keys = vec![];
+            other.root.borrow().add_keys(&mut vec![], &mut keys);
+            for v in keys {
+                self.add_to_suffix_index(&v);
+            }
+        }
         let mut this_node = self.root.borrow_mut();
-        for other_child_node_link in other.root.borrow().children.values() {
+        // Move the children out of `other` rather than cloning the links so each one still has a single owner.
+        let other_children = mem::take(&mut other.root.borrow_mut().children);
+        for other_child_node_link in other_children.into_values() {
             debug_assert!(Self::child_link_has_normal_ref_counts(
                 &other_child_node_link
             ));
-            let mut other_child_node = other_child_node_link.borrow_mut();
-            let parent: ParentLink = Rc::downgrade(&self.root);
-            other_child_node.parent = Some(parent);
-            debug_assert!(Self::opt_parent_link_has_normal_ref_counts(
-                &other_child_node.parent
-            ));
-            let c = other_child_node.c;
-            this_node
-                .children
-                .insert(c, Rc::clone(other_child_node_link));
+            let c = {
+                let mut other_child_node = other_child_node_link.borrow_mut();
+                let parent: ParentLink<K> = Rc::downgrade(&self.root);
+                other_child_node.parent = Some(parent);
+                debug_assert!(Self::opt_parent_link_has_normal_ref_counts(
+                    &other_child_node.parent
+                ));
+                other_child_node.c.clone()
+            };
+            this_node.children.insert(c.clone(), other_child_node_link);
             debug_assert!(Self::child_link_has_normal_ref_counts(
-                &other_child_node_link
+                &this_node.children[&c]
             ));
         }
     }
 
+    fn is_frozen(&self) -> bool {
+        self.root.borrow().is_frozen
+    }
+
+    pub fn freeze(&mut self) {
+        self.root.borrow_mut().freeze();
+        if let Some(suffix_index) = &mut self.suffix_index {
+            suffix_index.freeze();
+        }
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.root.borrow_mut().unfreeze();
+        if let Some(suffix_index) = &mut self.suffix_index {
+            suffix_index.unfreeze();
+        }
+    }
+
+    fn child_link_has_normal_ref_counts(rc: &ChildLink<K>) -> bool {
+        // The Rc pointing to a node will normally have a count of one, either from the BaseLetterTrie to the root
+        // node or from a parent node to a child node.
+        let strong_count = Rc::strong_count(rc);
+
+        // The weak count of the pointer to a node should equal the number of child nodes.
+        // let weak_count = Rc::weak_count(rc);
+
+        // dbg!(strong_count);
+        // dbg!(weak_count);
+
+        strong_count == 1
+
+        // Don't check against the number of child nodes since this requires a borrow and the ParentLink might
+        // already have a mutable borrow against it.
+        // let child_node_count = rc.borrow().children.len();
+        // weak_count == child_node_count
+    }
+
+    fn parent_link_has_normal_ref_counts(weak: &ParentLink<K>) -> bool {
+        // This function can't reuse child_link_has_normal_ref_counts because that would mean upgrading weak
+        // into an Rc, thus changing the counts.
+
+        // The Rc pointing to a node will normally have a count of one, either from the BaseLetterTrie to the root
+        // node or from a parent node to a child node.
+        let strong_count = Weak::strong_count(weak);
+
+        // The weak count of the pointer to a node should equal the number of child nodes.
+        // let weak_count = Weak::weak_count(weak).unwrap();
+
+        // dbg!(strong_count);
+        // dbg!(weak_count);
+
+        strong_count == 1
+
+        // Don't check against the number of child nodes since this requires a borrow and the ParentLink might
+        // already have a mutable borrow against it.
+        // let child_node_count = weak.upgrade().unwrap().borrow().children.len();
+        // weak_count == child_node_count
+    }
+
+    fn opt_parent_link_has_normal_ref_counts(weak_opt: &Option<ParentLink<K>>) -> bool {
+        if let Some(weak) = weak_opt {
+            Self::parent_link_has_normal_ref_counts(weak)
+        } else {
+            true
+        }
+    }
+}
+
+impl BaseLetterTrie {
+    /// Add a word after passing it through the trie's normalizer. A blank word or one that the normalizer rejects
+    /// is ignored.
+    pub fn add_word(&self, s: &str) {
+        if let Some(v) = self.normalizer.load_chars(s) {
+            debug_assert!(!self.is_frozen());
+            let v_len = v.len();
+            self.add_from_vec_chars(&v, v_len, 0);
+        }
+    }
+
+    #[deprecated(
+        note = "explore the trie with the \"ls\" and \"cd\" commands of the REPL or with TrieExplorer"
+    )]
     pub fn print_prefixes(&self, prefix_count: usize) -> usize {
         self.root.borrow().print_prefixes(prefix_count)
     }
@@ -166,6 +491,7 @@ impl BaseLetterTrie {
         v
     }
 
+    #[deprecated(note = "use get_words() or the \"words\" command of the REPL")]
     pub fn print_words(&self, word_count: usize) {
         let v = self.get_words(word_count);
         for word in v {
@@ -173,10 +499,6 @@ impl BaseLetterTrie {
         }
     }
 
-    fn is_frozen(&self) -> bool {
-        self.root.borrow().is_frozen
-    }
-
     pub fn iter_breadth_first(&self) -> BaseLetterTrieIteratorBreadthFirst {
         BaseLetterTrieIteratorBreadthFirst {
             stack: vec![Rc::clone(&self.root)],
@@ -184,24 +506,20 @@ impl BaseLetterTrie {
     }
 
     pub fn iter_prefix(&self, prefix: &str) -> BaseLetterTrieIteratorPrefix {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        // A prefix the normalizer rejects can't be in the trie so the iterator starts out finished.
+        let (prefix, prefix_index) = match self.normalizer.to_chars(prefix) {
+            Some(prefix) => (prefix, 0),
+            None => (vec![], 1),
+        };
         let prefix_len = prefix.len();
         BaseLetterTrieIteratorPrefix {
             prefix,
             prefix_len,
-            prefix_index: 0,
+            prefix_index,
             rc: Rc::clone(&self.root),
         }
     }
 
-    pub fn freeze(&mut self) {
-        self.root.borrow_mut().freeze();
-    }
-
-    pub fn unfreeze(&mut self) {
-        self.root.borrow_mut().unfreeze();
-    }
-
     fn print(&self, detail_level: usize) {
         match detail_level {
             1 => println!("{:?}", self.to_fixed_node()),
@@ -215,22 +533,26 @@ impl BaseLetterTrie {
         filename: &str,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
+        report: &mut BuildReport,
     ) {
-        println!("{}", filename);
+        if opt.object_detail_level >= 1 {
+            println!("{}", filename);
+        }
         let start = Instant::now();
         let content = fs::read_to_string(filename).expect("Error reading file.");
-        print_elapsed_from_start(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
+        report
+            .metrics
+            .record_elapsed(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
 
         let start = Instant::now();
-        let words: Vec<&str> = content
-            .split('\n')
-            .map(|x| x.trim())
-            .filter(|x| !x.is_empty())
+        let words: Vec<Vec<char>> = content
+            .lines()
+            .filter_map(|x| report.load_line(&self.normalizer, x))
             .collect();
         if let Some(exp_word_count) = expected_word_count {
             assert_eq!(words.len(), exp_word_count);
         }
-        print_elapsed_from_start(
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_MAKE_VECTOR,
@@ -242,10 +564,16 @@ impl BaseLetterTrie {
         }
 
         let start = Instant::now();
-        for word in words {
-            self.add_word(word);
+        for vec_char in words {
+            let v_len = vec_char.len();
+            self.add_from_vec_chars_counted(
+                &vec_char,
+                v_len,
+                0,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
         }
-        print_elapsed_from_start(
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_LOAD_FROM_VEC,
@@ -260,14 +588,20 @@ impl BaseLetterTrie {
         filename: &str,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
+        report: &mut BuildReport,
     ) {
         let start = Instant::now();
-        let v = make_vec_char_test(filename, opt, expected_word_count);
+        let v = make_vec_char_test(filename, opt, expected_word_count, &self.normalizer, report);
         for vec_char in v {
             let v_len = vec_char.len();
-            self.add_from_vec_chars(&vec_char, v_len, 0);
+            self.add_from_vec_chars_counted(
+                &vec_char,
+                v_len,
+                0,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
         }
-        print_elapsed_from_start(
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_LOAD_FROM_VEC,
@@ -276,33 +610,47 @@ impl BaseLetterTrie {
         self.print(opt.object_detail_level);
     }
 
-    fn load_continuous(&self, filename: &str, expected_word_count: Option<usize>) {
+    fn load_continuous(
+        &self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
         let file = File::open(filename).unwrap();
         let lines = BufReader::new(file)
             .lines()
-            .map(|x| x.unwrap().trim().to_owned())
-            .filter(|x| !x.is_empty())
-            .collect::<Vec<String>>();
+            .filter_map(|x| report.load_line(&self.normalizer, &x.unwrap()))
+            .collect::<Vec<Vec<char>>>();
         if let Some(exp_word_count) = expected_word_count {
             assert_eq!(lines.len(), exp_word_count);
         }
 
-        for line in lines {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
+        for vec_char in lines {
             let v_len = vec_char.len();
-            self.add_from_vec_chars(&vec_char, v_len, 0);
+            self.add_from_vec_chars_counted(
+                &vec_char,
+                v_len,
+                0,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
         }
     }
 
-    fn load_continuous_parallel_sorted(&self, filename: &str, expected_word_count: Option<usize>) {
+    fn load_continuous_parallel_sorted(
+        &self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
         let (tx, rx) = mpsc::channel();
 
         let file = File::open(filename).unwrap();
         let lines = BufReader::new(file)
             .lines()
-            .map(|x| x.unwrap().trim().to_owned())
-            .filter(|x| !x.is_empty())
-            .collect::<Vec<String>>();
+            .filter_map(|x| report.load_line(&self.normalizer, &x.unwrap()))
+            .collect::<Vec<Vec<char>>>();
         if let Some(exp_word_count) = expected_word_count {
             assert_eq!(lines.len(), exp_word_count);
         }
@@ -310,22 +658,29 @@ impl BaseLetterTrie {
         let mut thread_count = 0;
         let mut prev_c = ' ';
         let mut this_vec: Vec<Vec<char>> = vec![];
-        for line in lines {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
+        for vec_char in lines {
             let this_c = vec_char[0];
             if this_c != prev_c {
-                thread_count +=
-                    Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+                thread_count += Self::create_thread_for_part_of_vec(
+                    this_vec,
+                    opt.count_inserts,
+                    mpsc::Sender::clone(&tx),
+                );
                 this_vec = vec![];
                 prev_c = this_c;
             }
             this_vec.push(vec_char.clone());
         }
 
-        thread_count += Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+        thread_count += Self::create_thread_for_part_of_vec(
+            this_vec,
+            opt.count_inserts,
+            mpsc::Sender::clone(&tx),
+        );
 
-        for (received_index, received) in rx.iter().enumerate() {
+        for (received_index, (received, thread_metrics)) in rx.iter().enumerate() {
             self.merge(received);
+            report.metrics.add_thread(thread_metrics);
             if received_index == thread_count - 1 {
                 break;
             }
@@ -337,14 +692,18 @@ impl BaseLetterTrie {
         filename: &str,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
+        report: &mut BuildReport,
     ) {
-        let mut v = make_vec_char_test(filename, opt, expected_word_count);
+        let mut v =
+            make_vec_char_test(filename, opt, expected_word_count, &self.normalizer, report);
 
-        print_elapsed(
+        let start = Instant::now();
+        v.sort_unstable_by(|a, b| a[0].cmp(&b[0]));
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_SORT_VECTOR,
-            || v.sort_unstable_by(|a, b| a[0].cmp(&b[0])),
+            start,
         );
 
         let (tx, rx) = mpsc::channel();
@@ -355,18 +714,26 @@ impl BaseLetterTrie {
         for vec_char in v {
             let this_c = vec_char[0];
             if this_c != prev_c {
-                thread_count +=
-                    Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+                thread_count += Self::create_thread_for_part_of_vec(
+                    this_vec,
+                    opt.count_inserts,
+                    mpsc::Sender::clone(&tx),
+                );
                 this_vec = vec![];
                 prev_c = this_c;
             }
             this_vec.push(vec_char.clone());
         }
 
-        thread_count += Self::create_thread_for_part_of_vec(this_vec, mpsc::Sender::clone(&tx));
+        thread_count += Self::create_thread_for_part_of_vec(
+            this_vec,
+            opt.count_inserts,
+            mpsc::Sender::clone(&tx),
+        );
 
-        for (received_index, received) in rx.iter().enumerate() {
+        for (received_index, (received, thread_metrics)) in rx.iter().enumerate() {
             self.merge(received);
+            report.metrics.add_thread(thread_metrics);
             if received_index == thread_count - 1 {
                 break;
             }
@@ -374,15 +741,37 @@ impl BaseLetterTrie {
     }
 
     // Returns the number of threads spawned, which will be 1 if there are items in the vector, otherwise 0.
-    fn create_thread_for_part_of_vec(v: Vec<Vec<char>>, tx: mpsc::Sender<BaseLetterTrie>) -> usize {
+    fn create_thread_for_part_of_vec(
+        v: Vec<Vec<char>>,
+        count_inserts: bool,
+        tx: mpsc::Sender<(BaseLetterTrie, ThreadMetrics)>,
+    ) -> usize {
         if !v.is_empty() {
             thread::spawn(move || {
+                let start = Instant::now();
+                let first_char = v[0][0];
+                let word_count = v.len();
+                let mut counts = InsertCounts::default();
                 let t = BaseLetterTrie::new();
+                if count_inserts {
+                    counts.record_node();
+                }
                 for vec_char in v {
                     let v_len = vec_char.len();
-                    t.add_from_vec_chars(&vec_char, v_len, 0);
+                    t.add_from_vec_chars_counted(
+                        &vec_char,
+                        v_len,
+                        0,
+                        count_inserts.then_some(&mut counts),
+                    );
                 }
-                tx.send(t).unwrap();
+                let thread_metrics = ThreadMetrics {
+                    first_char,
+                    word_count,
+                    counts,
+                    elapsed: start.elapsed(),
+                };
+                tx.send((t, thread_metrics)).unwrap();
             });
             1
         } else {
@@ -391,13 +780,13 @@ impl BaseLetterTrie {
     }
 
     pub fn find(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = self.normalizer.to_chars(prefix)?;
         let prefix_len = prefix.len();
         self.root.borrow().find_child(prefix, prefix_len, 0)
     }
 
     pub fn find_loop(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = self.normalizer.to_chars(prefix)?;
         let prefix_len = prefix.len();
         let mut prefix_index = 0;
         let mut rc = Rc::clone(&self.root);
@@ -413,25 +802,62 @@ impl BaseLetterTrie {
                     };
                 }
                 let c = prefix[prefix_index];
-                let rc_opt = rc.borrow().children.get(&c).map(|x| Rc::clone(x));
-                if let Some(rc_next) = rc_opt {
+                let rc_next = rc.borrow().children.get(&c).cloned()?;
+                rc = rc_next;
+                prefix_index += 1;
+            }
+        }
+    }
+
+    /// Find every word that's a prefix of a string, from shortest to longest. The string itself is included if
+    /// it's a word.
+    ///
+    /// This is the same walk as `is_word_loop()` except that it keeps going after the first word it passes.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    /// assert_eq!(trie.all_prefix_words("Andy"), vec!["a", "an", "and"]);
+    /// ```
+    pub fn all_prefix_words(&self, s: &str) -> Vec<String> {
+        let s: Vec<char> = match self.normalizer.to_chars(s) {
+            Some(s) => s,
+            None => return vec![],
+        };
+        let mut words = vec![];
+        let mut rc = Rc::clone(&self.root);
+        for (index, c) in s.iter().enumerate() {
+            let rc_opt = rc.borrow().children.get(c).map(Rc::clone);
+            match rc_opt {
+                Some(rc_next) => {
+                    if rc_next.borrow().is_word {
+                        words.push(s[..=index].iter().collect());
+                    }
                     rc = rc_next;
-                    prefix_index += 1;
-                } else {
-                    return None;
                 }
+                None => break,
             }
         }
+        words
     }
 
     pub fn is_word_recursive(&self, prefix: &str) -> bool {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = match self.normalizer.to_chars(prefix) {
+            Some(prefix) => prefix,
+            None => return false,
+        };
         let prefix_len = prefix.len();
         self.root.borrow().is_word_child(prefix, prefix_len, 0)
     }
 
     pub fn is_word_loop(&self, prefix: &str) -> bool {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = match self.normalizer.to_chars(prefix) {
+            Some(prefix) => prefix,
+            None => return false,
+        };
         let prefix_len = prefix.len();
         let mut prefix_index = 0;
         let mut rc = Rc::clone(&self.root);
@@ -443,7 +869,7 @@ impl BaseLetterTrie {
                     return rc.borrow().is_word;
                 }
                 let c = prefix[prefix_index];
-                let rc_opt = rc.borrow().children.get(&c).map(|x| Rc::clone(x));
+                let rc_opt = rc.borrow().children.get(&c).cloned();
                 if let Some(rc_next) = rc_opt {
                     rc = rc_next;
                     prefix_index += 1;
@@ -454,107 +880,208 @@ impl BaseLetterTrie {
         }
     }
 
-    fn child_link_has_normal_ref_counts(rc: &ChildLink) -> bool {
-        // The Rc pointing to a node will normally have a count of one, either from the BaseLetterTrie to the root
-        // node or from a parent node to a child node.
+    // Check a node and the ones below it for LetterTrie::validate(), returning the counts for its subtree worked out
+    // without the cached values. `parent` is the parent's link and the key this node is stored under, or None for
+    // the root.
+    fn validate_link(
+        rc: &ChildLink,
+        parent: Option<(&ChildLink, char)>,
+        prefix: &mut String,
+        v: &mut TrieValidator,
+    ) -> SubtreeCounts {
+        // Read the counts before anything below takes another reference to this node.
         let strong_count = Rc::strong_count(rc);
-
-        // The weak count of the pointer to a node should equal the number of child nodes.
-        // let weak_count = Rc::weak_count(rc);
-
-        // dbg!(strong_count);
-        // dbg!(weak_count);
-
-        strong_count == 1
-
-        // Don't check against the number of child nodes since this requires a borrow and the ParentLink might
-        // already have a mutable borrow against it.
-        // let child_node_count = rc.borrow().children.len();
-        // weak_count == child_node_count
-    }
-
-    fn parent_link_has_normal_ref_counts(weak: &ParentLink) -> bool {
-        // This function can't reuse child_link_has_normal_ref_counts because that would mean upgrading weak
-        // into an Rc, thus changing the counts.
-
-        // The Rc pointing to a node will normally have a count of one, either from the BaseLetterTrie to the root
-        // node or from a parent node to a child node.
-        let strong_count = Weak::strong_count(weak);
-
-        // The weak count of the pointer to a node should equal the number of child nodes.
-        // let weak_count = Weak::weak_count(weak).unwrap();
-
-        // dbg!(strong_count);
-        // dbg!(weak_count);
-
-        strong_count == 1
-
-        // Don't check against the number of child nodes since this requires a borrow and the ParentLink might
-        // already have a mutable borrow against it.
-        // let child_node_count = weak.upgrade().unwrap().borrow().children.len();
-        // weak_count == child_node_count
-    }
-
-    fn opt_parent_link_has_normal_ref_counts(weak_opt: &Option<ParentLink>) -> bool {
-        if let Some(weak) = weak_opt {
-            Self::parent_link_has_normal_ref_counts(&weak)
-        } else {
-            true
+        let weak_count = Rc::weak_count(rc);
+        let node = rc.borrow();
+        let child_count = node.children.len();
+        if strong_count != 1 || weak_count != child_count {
+            v.add(
+                prefix,
+                TrieViolationKind::RefCounts {
+                    strong_count,
+                    weak_count,
+                    child_count,
+                },
+            );
+        }
+        let parent_key_depth = parent.map(|(parent_rc, key)| (key, parent_rc.borrow().depth));
+        v.check_node(
+            prefix,
+            parent_key_depth,
+            node.c,
+            node.depth,
+            node.is_word,
+            child_count,
+        );
+        let is_parent_link_ok = match (parent, &node.parent) {
+            (None, None) => true,
+            (Some((parent_rc, _)), Some(parent_weak)) => parent_weak
+                .upgrade()
+                .is_some_and(|rc| Rc::ptr_eq(&rc, parent_rc)),
+            _ => false,
+        };
+        if !is_parent_link_ok {
+            v.add(prefix, TrieViolationKind::ParentLink);
+        }
+        let mut counts = SubtreeCounts::make_node(node.is_word);
+        for (key, child_rc) in node.children.iter() {
+            if node.is_frozen && !child_rc.borrow().is_frozen {
+                v.add(prefix, TrieViolationKind::UnfrozenChild { key: *key });
+            }
+            prefix.push(*key);
+            let child_counts = Self::validate_link(child_rc, Some((rc, *key)), prefix, v);
+            prefix.pop();
+            counts.add_child(&child_counts);
+        }
+        if node.is_frozen {
+            v.check_cached_counts(
+                prefix,
+                node.node_count,
+                node.word_count,
+                node.height,
+                &counts,
+            );
         }
+        counts
     }
 }
 
 impl LetterTrie for BaseLetterTrie {
     fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
         let opt = DisplayDetailOptions::make_no_display();
-        Self::from_file_test(filename, is_sorted, load_method, &opt, None)
+        Self::from_file_test(filename, is_sorted, load_method, &opt, None).0
     }
 
-    fn from_file_test(
+    fn from_file_report(
         filename: &str,
         is_sorted: bool,
         load_method: &LoadMethod,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) -> Self {
-        let t = Self::new();
-        print_elapsed(
+        normalizer: &Normalizer,
+    ) -> (Self, BuildReport) {
+        let t = Self::with_normalizer(normalizer.clone());
+        let mut report = BuildReport::default();
+        report.metrics.is_counted = opt.count_inserts;
+        let start = Instant::now();
+        match load_method {
+            LoadMethod::ReadVecFill => {
+                t.load_read_vec_fill(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::VecFill => {
+                t.load_vec_fill(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::Continuous => {
+                t.load_continuous(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::ContinuousParallel => {
+                if is_sorted {
+                    t.load_continuous_parallel_sorted(
+                        filename,
+                        opt,
+                        expected_word_count,
+                        &mut report,
+                    );
+                } else {
+                    t.load_parallel_unsorted(filename, opt, expected_word_count, &mut report);
+                }
+            }
+        };
+        report.metrics.record_elapsed(
             opt.print_overall_time,
             &opt.label,
             LABEL_STEP_OVERALL,
-            || {
-                match load_method {
-                    LoadMethod::ReadVecFill => {
-                        t.load_read_vec_fill(filename, opt, expected_word_count);
-                    }
-                    LoadMethod::VecFill => {
-                        t.load_vec_fill(filename, opt, expected_word_count);
-                    }
-                    LoadMethod::Continuous => {
-                        t.load_continuous(filename, expected_word_count);
-                    }
-                    LoadMethod::ContinuousParallel => {
-                        if is_sorted {
-                            t.load_continuous_parallel_sorted(filename, expected_word_count);
-                        } else {
-                            t.load_parallel_unsorted(filename, opt, expected_word_count);
-                        }
-                    }
-                };
-            },
+            start,
         );
-        t
+        (t, report)
     }
 
     fn find(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = self.normalizer.to_chars(prefix)?;
         let prefix_len = prefix.len();
         self.root.borrow().find_child(prefix, prefix_len, 0)
     }
 
+    fn all_prefix_words(&self, s: &str) -> Vec<String> {
+        BaseLetterTrie::all_prefix_words(self, s)
+    }
+
+    fn enable_suffix_index(&mut self) {
+        BaseTrie::enable_suffix_index(self);
+    }
+
+    fn disable_suffix_index(&mut self) {
+        BaseTrie::disable_suffix_index(self);
+    }
+
+    fn has_suffix_index(&self) -> bool {
+        BaseTrie::has_suffix_index(self)
+    }
+
+    fn words_with_suffix(&self, suffix: &str) -> Vec<String> {
+        BaseTrie::words_with_suffix(self, suffix)
+    }
+
+    fn words_with_prefix_and_suffix(&self, prefix: &str, suffix: &str) -> Vec<String> {
+        BaseTrie::words_with_prefix_and_suffix(self, prefix, suffix)
+    }
+
     fn to_fixed_node(&self) -> FixedNode {
         self.root.borrow().to_fixed_node()
     }
+
+    fn to_dot_with_options(&self, root_prefix: &str, opt: &DotOptions) -> Option<String> {
+        let prefix: Vec<char> = self.normalizer.to_chars(root_prefix)?;
+        let mut rc = Rc::clone(&self.root);
+        for c in prefix.iter() {
+            let child_rc = rc.borrow().children.get(c).map(Rc::clone)?;
+            rc = child_rc;
+        }
+        let mut w = DotWriter::new(opt);
+        rc.borrow()
+            .write_graph(&mut w, &mut prefix.iter().collect(), 0);
+        Some(w.finish())
+    }
+
+    fn to_d3_hierarchy(&self, root_prefix: &str, opt: &D3Options) -> Option<serde_json::Value> {
+        let prefix: Vec<char> = self.normalizer.to_chars(root_prefix)?;
+        let mut rc = Rc::clone(&self.root);
+        for c in prefix.iter() {
+            let child_rc = rc.borrow().children.get(c).map(Rc::clone)?;
+            rc = child_rc;
+        }
+        let mut w = D3Writer::new(opt);
+        rc.borrow()
+            .write_graph(&mut w, &mut prefix.iter().collect(), 0);
+        Some(w.finish())
+    }
+
+    fn stats(&self) -> TrieStats {
+        let mut w = StatsWriter::new();
+        self.root
+            .borrow()
+            .write_graph(&mut w, &mut String::new(), 0);
+        w.finish()
+    }
+
+    fn heap_size(&self) -> HeapSize {
+        let mut h = HeapSize::default();
+        h.add_normalizer(&self.normalizer);
+        if let Some(suffix_index) = &self.suffix_index {
+            let mut suffix_h = HeapSize::default();
+            suffix_h.node_bytes += mem::size_of::<BaseLetterTrie>();
+            suffix_index.root.borrow().add_heap_size(&mut suffix_h);
+            h.add_bytes(&suffix_h);
+        }
+        self.root.borrow().add_heap_size(&mut h);
+        h
+    }
+
+    fn validate(&self) -> Vec<TrieViolation> {
+        let mut v = TrieValidator::default();
+        Self::validate_link(&self.root, None, &mut String::new(), &mut v);
+        v.finish()
+    }
 }
 
 impl Debug for BaseLetterTrie {
@@ -579,8 +1106,8 @@ impl Iterator for BaseLetterTrieIteratorBreadthFirst {
             let this_rc = self.stack.remove(0);
             let this_node = this_rc.borrow();
             let fixed_char_node = this_node.to_fixed_node();
-            for (_, child_node_rc) in this_node.children.iter() {
-                self.stack.push(Rc::clone(&child_node_rc));
+            for child_node_rc in this_node.children.values() {
+                self.stack.push(Rc::clone(child_node_rc));
             }
             Some(fixed_char_node)
         }
@@ -608,7 +1135,7 @@ impl Iterator for BaseLetterTrieIteratorPrefix {
                 Some(fixed_char_node)
             } else {
                 let c = self.prefix[self.prefix_index];
-                let rc_opt = self.rc.borrow().children.get(&c).map(|x| Rc::clone(x));
+                let rc_opt = self.rc.borrow().children.get(&c).cloned();
                 if let Some(rc_next) = rc_opt {
                     self.rc = rc_next;
                     self.prefix_index += 1;
@@ -628,23 +1155,23 @@ impl Debug for BaseLetterTrieIteratorPrefix {
             write!(
                 f,
                 "BaseLetterTrieIteratorPrefix:\n\tprefix_len = {}\n\tprefix_index = {}\n\trc = {}",
-                self.prefix_len, self.prefix_index, &rc_string
+                self.prefix_len, self.prefix_index, rc_string
             )
         } else {
             write!(
                 f,
                 "BaseLetterTrieIteratorPrefix: prefix_len = {}, prefix_index = {}, rc = {}",
-                self.prefix_len, self.prefix_index, &rc_string
+                self.prefix_len, self.prefix_index, rc_string
             )
         }
     }
 }
 
-struct Node {
-    c: char,
+struct Node<K = char> {
+    c: K,
     depth: usize,
-    parent: Option<ParentLink>,
-    children: BTreeMap<char, ChildLink>,
+    parent: Option<ParentLink<K>>,
+    children: BTreeMap<K, ChildLink<K>>,
     is_word: bool,
     is_frozen: bool,
     node_count: Option<usize>,
@@ -652,7 +1179,7 @@ struct Node {
     height: Option<usize>,
 }
 
-impl Node {
+impl<K: KeyElement> Node<K> {
     pub fn node_count(&self) -> usize {
         if self.is_frozen {
             self.node_count.unwrap()
@@ -725,6 +1252,47 @@ impl Node {
         }
     }
 
+    // Add the key elements of every word at this node or below it, where `prefix` holds the key elements down to
+    // this node.
+    fn add_keys(&self, prefix: &mut Vec<K>, keys: &mut Vec<Vec<K>>) {
+        if self.is_word {
+            keys.push(prefix.clone());
+        }
+        for (c, child_rc) in self.children.iter() {
+            prefix.push(c.clone());
+            child_rc.borrow().add_keys(prefix, keys);
+            prefix.pop();
+        }
+    }
+
+    // Clear the word at the end of a sequence of key elements below this node, then drop the nodes along the way
+    // that no longer lead to a word. Returns true if there was a word to clear.
+    fn remove_one_node(&mut self, v: &[K], char_index: usize) -> bool {
+        let c = &v[char_index];
+        let child_rc = match self.children.get(c) {
+            Some(child_rc) => Rc::clone(child_rc),
+            None => return false,
+        };
+        let (is_removed, is_dead_end) = {
+            let mut child_node = child_rc.borrow_mut();
+            let is_removed = if char_index == v.len() - 1 {
+                mem::replace(&mut child_node.is_word, false)
+            } else {
+                child_node.remove_one_node(v, char_index + 1)
+            };
+            (
+                is_removed,
+                !child_node.is_word && child_node.children.is_empty(),
+            )
+        };
+        if is_dead_end {
+            self.children.remove(c);
+        }
+        is_removed
+    }
+}
+
+impl Node {
     fn find_child(
         &self,
         prefix: Vec<char>,
@@ -778,6 +1346,7 @@ impl Node {
             node_count: self.node_count(),
             word_count: self.word_count(),
             height: self.height(),
+            surface_forms: vec![],
         }
     }
 
@@ -802,6 +1371,44 @@ impl Node {
         )
     }
 
+    // Each node is in its own Rc<RefCell<Node>> allocation and the BTreeMap holds only the pointers.
+    fn add_heap_size(&self, h: &mut HeapSize) {
+        h.node_count += 1;
+        if self.is_word {
+            h.word_count += 1;
+        }
+        h.add_rc_ref_cell::<Node>();
+        h.add_children::<char, ChildLink>(self.children.len(), false);
+        for child_rc in self.children.values() {
+            child_rc.borrow().add_heap_size(h);
+        }
+    }
+
+    // Add this node and the ones below it to a graph, returning the ID of this node if the writer included it.
+    pub(crate) fn write_graph<W: GraphWriter>(
+        &self,
+        w: &mut W,
+        prefix: &mut String,
+        depth: usize,
+    ) -> Option<usize> {
+        let node = if w.needs_counts() {
+            self.to_fixed_node()
+        } else {
+            shallow_fixed_node(self.c, self.depth, self.is_word, self.children.len())
+        };
+        let id = w.add_node(prefix, &node, depth)?;
+        let shown_count = w.children_to_show(depth, self.children.len());
+        for (c, child_rc) in self.children.iter().take(shown_count) {
+            prefix.push(*c);
+            let child_id = child_rc.borrow().write_graph(w, prefix, depth + 1);
+            prefix.pop();
+            if let Some(child_id) = child_id {
+                w.add_edge(id, child_id, *c);
+            }
+        }
+        Some(id)
+    }
+
     pub fn describe_deep(&self, s: &mut String, depth: usize) {
         s.push_str(&format!(
             "{}\n",
@@ -855,7 +1462,7 @@ impl Node {
             v.push(self.prefix());
         }
         if !self.children.is_empty() {
-            for (_, child_node_rc) in self.children.iter() {
+            for child_node_rc in self.children.values() {
                 child_node_rc.borrow().get_words(v, word_count);
             }
         }
@@ -884,18 +1491,185 @@ mod tests {
     fn small_root() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
         assert_small_root(&t.to_fixed_node());
     }
 
+    #[test]
+    fn generic_keys() {
+        let t: BaseTrie<u8> = BaseTrie::new();
+        t.add_key(&[10, 0, 0][..]);
+        t.add_key("ab");
+        let other: BaseTrie<u8> = BaseTrie::new();
+        other.add_key(&[192, 168][..]);
+        t.merge(other);
+        assert!(t.contains_word(&[10, 0, 0][..]));
+        assert!(t.contains_word(&[192, 168][..]));
+        assert!(t.contains_word("ab"));
+        assert!(!t.contains_word(&[10, 0][..]));
+        assert!(!t.contains_word(&[][..]));
+        let root = t.root.borrow();
+        assert_eq!(root.c, 0);
+        assert_eq!(root.node_count(), 8);
+        assert_eq!(root.word_count(), 3);
+    }
+
+    #[test]
+    fn validate() {
+        let dataset = Dataset::TestMediumSorted;
+        for load_method in LoadMethod::ALL.iter() {
+            let mut t =
+                BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), load_method);
+            assert_eq!(t.validate(), vec![]);
+            t.freeze();
+            assert_eq!(t.validate(), vec![]);
+        }
+
+        let t = BaseLetterTrie::new();
+        t.add_word("ab");
+        {
+            let root = t.root.borrow();
+            let mut a = root.children[&'a'].borrow_mut();
+            a.c = 'x';
+            let mut b = a.children[&'b'].borrow_mut();
+            b.depth = 5;
+            b.is_word = false;
+            b.parent = Some(Rc::downgrade(&t.root));
+        }
+        let extra_link = Rc::clone(&t.root.borrow().children[&'a']);
+        let violations: Vec<(String, TrieViolationKind)> = t
+            .validate()
+            .into_iter()
+            .map(|violation| (violation.prefix, violation.kind))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    "".to_owned(),
+                    TrieViolationKind::RefCounts {
+                        strong_count: 1,
+                        weak_count: 2,
+                        child_count: 1
+                    }
+                ),
+                (
+                    "a".to_owned(),
+                    TrieViolationKind::RefCounts {
+                        strong_count: 2,
+                        weak_count: 0,
+                        child_count: 1
+                    }
+                ),
+                (
+                    "a".to_owned(),
+                    TrieViolationKind::KeyMismatch { key: 'a', c: 'x' }
+                ),
+                (
+                    "ab".to_owned(),
+                    TrieViolationKind::Depth {
+                        expected: 2,
+                        found: 5
+                    }
+                ),
+                ("ab".to_owned(), TrieViolationKind::DeadEnd),
+                ("ab".to_owned(), TrieViolationKind::ParentLink),
+            ]
+        );
+        drop(extra_link);
+
+        let mut t = BaseLetterTrie::new();
+        t.add_word("ab");
+        t.freeze();
+        t.root.borrow_mut().node_count = Some(99);
+        t.root.borrow().children[&'a'].borrow_mut().is_frozen = false;
+        let violations: Vec<(String, TrieViolationKind)> = t
+            .validate()
+            .into_iter()
+            .map(|violation| (violation.prefix, violation.kind))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                ("".to_owned(), TrieViolationKind::UnfrozenChild { key: 'a' }),
+                (
+                    "".to_owned(),
+                    TrieViolationKind::CachedCount {
+                        name: "node_count",
+                        cached: Some(99),
+                        actual: 3
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn normalizer() {
+        let t = BaseLetterTrie::with_normalizer(Normalizer::make_exact());
+        t.add_word("Polish");
+        t.add_word("polish");
+        assert_eq!(t.to_fixed_node().word_count, 2);
+        assert!(t.is_word_loop("Polish"));
+        assert!(!t.is_word_recursive("POLISH"));
+
+        let t = BaseLetterTrie::from_file_normalized(
+            "words_to_normalize.txt",
+            false,
+            &LoadMethod::VecFill,
+            &DisplayDetailOptions::make_no_display(),
+            Some(6),
+            &Normalizer::make_ascii_letters(),
+        );
+        assert!(t.is_word_loop("Zürich"));
+        assert!(t.is_word_recursive("strasse"));
+        assert!(t.find_loop("Crème").is_some());
+        assert_eq!(t.find("route 66"), None);
+    }
+
+    #[test]
+    fn suffix_index_stays_in_sync() {
+        let mut t = BaseLetterTrie::new();
+        t.add_word("geology");
+        t.enable_suffix_index();
+        t.add_word("biology");
+        t.add_word("logo");
+        assert_eq!(t.words_with_suffix("logy"), vec!["biology", "geology"]);
+        assert!(t.remove_key("geology"));
+        assert!(!t.remove_key("geology"));
+        assert_eq!(t.words_with_suffix("logy"), vec!["biology"]);
+        assert!(t.validate().is_empty());
+
+        let other = BaseLetterTrie::new();
+        other.add_word("ecology");
+        other.add_word("aba");
+        t.merge(other);
+        assert_eq!(t.words_with_suffix("ology"), vec!["biology", "ecology"]);
+        assert_eq!(t.words_with_prefix_and_suffix("ab", "ba"), vec!["aba"]);
+        assert_eq!(t.words_with_prefix_and_suffix("lo", "go"), vec!["logo"]);
+
+        // Once both tries are frozen the subtree with fewer words is walked.
+        t.freeze();
+        assert!(t.suffix_index.as_ref().unwrap().is_frozen());
+        assert_eq!(t.words_with_prefix_and_suffix("b", "logy"), vec!["biology"]);
+        assert_eq!(t.words_with_prefix_and_suffix("", "a"), vec!["aba"]);
+        t.unfreeze();
+
+        let with_index_bytes = t.heap_size().total_bytes();
+        t.disable_suffix_index();
+        assert!(!t.has_suffix_index());
+        assert!(t.heap_size().total_bytes() < with_index_bytes);
+        assert_eq!(t.words_with_suffix("ology"), vec!["biology", "ecology"]);
+    }
+
     #[test]
     fn small_prefix_cross() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -910,6 +1684,7 @@ mod tests {
                 node_count: 3,
                 word_count: 2,
                 height: 3,
+                surface_forms: vec![],
             })
         );
     }
@@ -918,7 +1693,7 @@ mod tests {
     fn small_prefix_creatu() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -933,6 +1708,7 @@ mod tests {
                 node_count: 3,
                 word_count: 1,
                 height: 3,
+                surface_forms: vec![],
             })
         );
     }
@@ -941,7 +1717,7 @@ mod tests {
     fn small_prefix_an() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -956,6 +1732,7 @@ mod tests {
                 node_count: 2,
                 word_count: 2,
                 height: 2,
+                surface_forms: vec![],
             })
         );
     }
@@ -964,7 +1741,7 @@ mod tests {
     fn small_prefix_c() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -979,6 +1756,7 @@ mod tests {
                 node_count: 20,
                 word_count: 6,
                 height: 8,
+                surface_forms: vec![],
             })
         );
     }
@@ -987,7 +1765,7 @@ mod tests {
     fn small_prefix_not_found() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -998,7 +1776,7 @@ mod tests {
     fn large_read_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::ReadVecFill,
         );
@@ -1009,7 +1787,7 @@ mod tests {
     fn large_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::VecFill,
         );
@@ -1020,7 +1798,7 @@ mod tests {
     fn large_continuous_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -1031,7 +1809,7 @@ mod tests {
     fn large_continuous_parallel_root() {
         let dataset = Dataset::TestLargeSorted;
         let t = BaseLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::ContinuousParallel,
         );
@@ -1043,7 +1821,7 @@ mod tests {
         let t = large_tree();
         let words = good_words();
         for word in words {
-            assert_eq!(true, t.is_word_recursive(&word));
+            assert!(t.is_word_recursive(&word));
         }
     }
 
@@ -1052,7 +1830,7 @@ mod tests {
         let t = large_tree();
         let words = good_words();
         for word in words {
-            assert_eq!(true, t.is_word_loop(&word));
+            assert!(t.is_word_loop(&word));
         }
     }
 
@@ -1061,7 +1839,7 @@ mod tests {
         let t = large_tree();
         let words = non_words();
         for word in words {
-            assert_eq!(false, t.is_word_recursive(&word));
+            assert!(!t.is_word_recursive(&word));
         }
     }
 
@@ -1070,7 +1848,7 @@ mod tests {
         let t = large_tree();
         let words = non_words();
         for word in words {
-            assert_eq!(false, t.is_word_loop(&word));
+            assert!(!t.is_word_loop(&word));
         }
     }
 
@@ -1080,7 +1858,7 @@ mod tests {
         let hash_set = large_dataset_words_hash_set();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, hash_set.contains(&word));
+                assert!(hash_set.contains(&word));
             }
         });
     }
@@ -1091,7 +1869,7 @@ mod tests {
         let t = large_tree();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, t.is_word_recursive(&word));
+                assert!(t.is_word_recursive(&word));
             }
         });
     }
@@ -1102,7 +1880,7 @@ mod tests {
         let t = large_tree();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, t.is_word_loop(&word));
+                assert!(t.is_word_loop(&word));
             }
         });
     }
@@ -1112,7 +1890,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             BaseLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::ReadVecFill,
             );
@@ -1124,7 +1902,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             BaseLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::VecFill,
             );
@@ -1136,7 +1914,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             BaseLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::Continuous,
             );
@@ -1148,7 +1926,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             BaseLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::ContinuousParallel,
             );
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+/// Counts of what happened at each node visited while adding words to a trie.
+/// - hit = Starting from a given node, we found a child node corresponding to the next letter of the word.
+/// - miss = We didn't find such a child node and thus created one.
+///
+/// These results can influence how we go about speeding up the build. In the large word list with 400,000 words
+/// leading to 1,081,648 nodes we get a hit about 74% of the time.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InsertCounts {
+    pub hit_count: usize,
+    pub miss_count: usize,
+    /// The nodes created while adding words. This is one more than the misses for each thread of a parallel load
+    /// since each thread starts its own trie with its own root.
+    pub node_count: usize,
+    /// The words that were already in the trie when they were added.
+    pub duplicate_count: usize,
+}
+
+impl InsertCounts {
+    /// The fraction of the nodes visited that were already there, or zero if nothing was recorded.
+    pub fn hit_pct(&self) -> f64 {
+        let total_count = self.hit_count + self.miss_count;
+        if total_count == 0 {
+            0.0
+        } else {
+            self.hit_count as f64 / total_count as f64
+        }
+    }
+
+    // Record a single hit or miss.
+    pub(crate) fn record(&mut self, is_hit: bool) {
+        if is_hit {
+            self.hit_count += 1;
+        } else {
+            self.miss_count += 1;
+        }
+    }
+
+    pub(crate) fn record_node(&mut self) {
+        self.node_count += 1;
+    }
+
+    // Record reaching the node at the end of a word, which makes the word a duplicate if the node was already a word.
+    pub(crate) fn record_word(&mut self, was_word: bool) {
+        if was_word {
+            self.duplicate_count += 1;
+        }
+    }
+
+    fn add(&mut self, other: &InsertCounts) {
+        self.hit_count += other.hit_count;
+        self.miss_count += other.miss_count;
+        self.node_count += other.node_count;
+        self.duplicate_count += other.duplicate_count;
+    }
+}
+
+/// The time taken by one step of a load, such as `LABEL_STEP_READ_FILE`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepTime {
+    pub label: &'static str,
+    pub elapsed: Duration,
+}
+
+/// What one thread of a parallel load did. Each thread builds a trie from the words starting with one letter.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThreadMetrics {
+    pub first_char: char,
+    pub word_count: usize,
+    pub counts: InsertCounts,
+    /// The time the thread spent adding its words, not counting the wait to be merged.
+    pub elapsed: Duration,
+}
+
+/// Metrics gathered while building one trie, found in the `BuildReport` from `LetterTrie::from_file_test()`. Since
+/// they belong to the build rather than being kept in one place for the whole process, tries built at the same time
+/// on different threads each get their own.
+///
+/// The metrics can be printed with `{}`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallSorted;
+/// let mut opt = DisplayDetailOptions::make_no_display();
+/// opt.count_inserts = true;
+/// let (trie, report) = BaseLetterTrie::from_file_test(
+///     dataset.filename(),
+///     dataset.is_sorted(),
+///     &LoadMethod::ContinuousParallel,
+///     &opt,
+///     None,
+/// );
+/// let metrics = report.metrics;
+///
+/// // Every node other than the root was a miss the first time a word reached it.
+/// assert_eq!(metrics.counts.miss_count + 1, trie.to_fixed_node().node_count);
+/// // The words start with "a" or "c" so there's one thread for each letter.
+/// assert_eq!(metrics.threads.len(), 2);
+/// assert!(metrics.step_time(LABEL_STEP_OVERALL).is_some());
+/// println!("{}", metrics);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildMetrics {
+    /// True if the hits, misses, new nodes and duplicates were counted, which is when
+    /// `DisplayDetailOptions::count_inserts` is set. Otherwise every count is zero.
+    pub is_counted: bool,
+    /// The counts for the whole build, including those from every thread.
+    pub counts: InsertCounts,
+    /// The steps of the load in the order they finished. `LABEL_STEP_OVERALL` comes last and covers the others.
+    pub steps: Vec<StepTime>,
+    /// For a parallel load, one entry for each thread in the order they were merged, otherwise empty.
+    pub threads: Vec<ThreadMetrics>,
+}
+
+impl BuildMetrics {
+    /// The time taken by a step, or None if the load method doesn't have that step.
+    pub fn step_time(&self, step_label: &str) -> Option<Duration> {
+        self.steps
+            .iter()
+            .find(|step| step.label == step_label)
+            .map(|step| step.elapsed)
+    }
+
+    // Record the time taken by a step and print it as print_elapsed_from_start() does.
+    pub(crate) fn record_elapsed(
+        &mut self,
+        display: bool,
+        case_label: &str,
+        step_label: &'static str,
+        start: Instant,
+    ) {
+        self.steps.push(StepTime {
+            label: step_label,
+            elapsed: start.elapsed(),
+        });
+        print_elapsed_from_start(display, case_label, step_label, start);
+    }
+
+    pub(crate) fn add_thread(&mut self, thread: ThreadMetrics) {
+        self.counts.add(&thread.counts);
+        self.threads.push(thread);
+    }
+}
+
+impl fmt::Display for BuildMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_counted {
+            writeln!(
+                f,
+                "hit count = {}; miss count = {}; hit pct = {:.3}; nodes created = {}; duplicates = {}",
+                format_count(self.counts.hit_count),
+                format_count(self.counts.miss_count),
+                self.counts.hit_pct(),
+                format_count(self.counts.node_count),
+                format_count(self.counts.duplicate_count)
+            )?;
+        }
+        for step in self.steps.iter() {
+            writeln!(f, "{} = {:?}", step.label, step.elapsed)?;
+        }
+        for thread in self.threads.iter() {
+            if self.is_counted {
+                writeln!(
+                    f,
+                    "thread '{}': {} words; hit pct = {:.3}; nodes created = {}; {:?}",
+                    thread.first_char,
+                    format_count(thread.word_count),
+                    thread.counts.hit_pct(),
+                    format_count(thread.counts.node_count),
+                    thread.elapsed
+                )?;
+            } else {
+                writeln!(
+                    f,
+                    "thread '{}': {} words; {:?}",
+                    thread.first_char,
+                    format_count(thread.word_count),
+                    thread.elapsed
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build<T: LetterTrie>(dataset: &Dataset, load_method: &LoadMethod) -> (T, BuildMetrics) {
+        let mut opt = DisplayDetailOptions::make_no_display();
+        opt.count_inserts = true;
+        let (t, report) = T::from_file_test(
+            dataset.filename(),
+            dataset.is_sorted(),
+            load_method,
+            &opt,
+            Some(dataset.word_count()),
+        );
+        (t, report.metrics)
+    }
+
+    #[test]
+    fn same_counts_for_every_method() {
+        let dataset = Dataset::TestMediumSorted;
+        for load_method in LoadMethod::ALL.iter() {
+            let (base, base_metrics) = build::<BaseLetterTrie>(&dataset, load_method);
+            let (_, map_metrics) = build::<LetterTrieSet>(&dataset, load_method);
+            let node_count = base.to_fixed_node().node_count;
+            for metrics in [&base_metrics, &map_metrics].iter() {
+                assert_eq!(metrics.counts.miss_count, node_count - 1);
+                assert_eq!(
+                    metrics.counts.node_count,
+                    node_count - 1 + metrics.threads.len()
+                );
+                assert_eq!(metrics.steps.last().unwrap().label, LABEL_STEP_OVERALL);
+            }
+            assert_eq!(base_metrics.counts, map_metrics.counts);
+        }
+    }
+
+    #[test]
+    fn threads() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let (_, metrics) = build::<BaseLetterTrie>(&dataset, &LoadMethod::ContinuousParallel);
+        assert!(metrics.step_time(LABEL_STEP_SORT_VECTOR).is_some());
+        assert!(metrics.step_time(LABEL_STEP_READ_FILE).is_none());
+        let word_count: usize = metrics.threads.iter().map(|t| t.word_count).sum();
+        assert_eq!(word_count, dataset.word_count());
+        let mut first_chars: Vec<char> = metrics.threads.iter().map(|t| t.first_char).collect();
+        first_chars.sort_unstable();
+        first_chars.dedup();
+        assert_eq!(first_chars.len(), metrics.threads.len());
+
+        let (_, metrics) = build::<NoParentLetterTrie>(&dataset, &LoadMethod::Continuous);
+        assert!(metrics.threads.is_empty());
+        assert!(metrics.counts.hit_pct() > 0.5);
+    }
+
+    #[test]
+    fn counts_only_when_asked() {
+        let dataset = Dataset::TestSmallSorted;
+        for load_method in LoadMethod::ALL.iter() {
+            let (_, report) = NoParentLetterTrie::from_file_test(
+                dataset.filename(),
+                dataset.is_sorted(),
+                load_method,
+                &DisplayDetailOptions::make_no_display(),
+                None,
+            );
+            assert_eq!(report.metrics.counts, InsertCounts::default());
+            assert!(report.metrics.step_time(LABEL_STEP_OVERALL).is_some());
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use serde_json::{json, Value};
+
+use crate::normalizer::LoadedLine;
+use crate::*;
+
+// The steps that get their own column in the CSV form of a report, in the order of the columns.
+const CSV_STEP_LABELS: [&str; 6] = [
+    LABEL_STEP_OVERALL,
+    LABEL_STEP_READ_FILE,
+    LABEL_STEP_MAKE_VECTOR,
+    LABEL_STEP_READ_AND_VECTOR,
+    LABEL_STEP_SORT_VECTOR,
+    LABEL_STEP_LOAD_FROM_VEC,
+];
+
+/// The column names for `BuildReport::to_csv_row()`, separated by commas. The step times are in milliseconds and
+/// are blank for steps that the load method doesn't have. The duplicates, hits, misses and nodes created are blank
+/// if they weren't counted.
+pub const BUILD_REPORT_CSV_HEADER: &str = "lines,words,duplicates,blank_lines,rejected_lines,threads,\
+     hits,misses,nodes_created,overall_ms,read_file_ms,make_vector_ms,read_and_vector_ms,sort_vector_ms,\
+     load_from_vec_ms";
+
+/// What happened while loading a word file into a trie, from `LetterTrie::from_file_test()`.
+///
+/// Each line of the file is counted as a word, a blank line or a line the normalizer rejected. A word that's
+/// already in the trie doesn't change the trie, and is counted as a duplicate in `metrics.counts` if
+/// `DisplayDetailOptions::count_inserts` is set. Otherwise `metrics.is_counted` is false and the counts come out
+/// as null in `to_json()` and as blank fields in `to_csv_row()`.
+///
+/// The report can be turned into JSON with `to_json()` or into one line of a CSV file with `to_csv_row()`, so the
+/// time taken by each step can be tracked from one run to the next.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let (trie, report) = BaseLetterTrie::from_file_test(
+///     dataset.filename(),
+///     dataset.is_sorted(),
+///     &LoadMethod::VecFill,
+///     &DisplayDetailOptions::make_no_display(),
+///     Some(dataset.word_count()),
+/// );
+///
+/// assert_eq!(report.word_count, 10);
+/// assert!(!report.metrics.is_counted);
+/// assert!(report.to_json()["duplicate_count"].is_null());
+/// assert_eq!(report.thread_count(), 1);
+/// assert!(report.metrics.step_time(LABEL_STEP_READ_AND_VECTOR).is_some());
+/// assert_eq!(report.to_json()["word_count"], 10);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildReport {
+    /// Every line read from the file, including blank and rejected lines.
+    pub line_count: usize,
+    /// The lines that held a word after normalization, including duplicates.
+    pub word_count: usize,
+    /// The lines that were empty or only whitespace.
+    pub blank_line_count: usize,
+    /// The lines that the normalizer turned away, such as those with characters outside its whitelist.
+    pub rejected_line_count: usize,
+    /// The rejected words and the characters that weren't allowed, if the normalizer's `invalid_word_action` is
+    /// `InvalidWordAction::Report`. Otherwise this is empty.
+    pub invalid_words: Vec<InvalidWord>,
+    /// The node hits and misses, duplicates, step times and per-thread breakdown. See `BuildMetrics`.
+    pub metrics: BuildMetrics,
+}
+
+impl BuildReport {
+    /// The number of threads that built parts of the trie. A load that isn't parallel has one.
+    pub fn thread_count(&self) -> usize {
+        self.metrics.threads.len().max(1)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let steps: Vec<Value> = self
+            .metrics
+            .steps
+            .iter()
+            .map(|step| json!({ "label": step.label, "ms": duration_ms(step.elapsed) }))
+            .collect();
+        let threads: Vec<Value> = self
+            .metrics
+            .threads
+            .iter()
+            .map(|thread| {
+                json!({
+                    "first_char": thread.first_char.to_string(),
+                    "word_count": thread.word_count,
+                    "hit_count": self.count_json(thread.counts.hit_count),
+                    "miss_count": self.count_json(thread.counts.miss_count),
+                    "node_count": self.count_json(thread.counts.node_count),
+                    "duplicate_count": self.count_json(thread.counts.duplicate_count),
+                    "ms": duration_ms(thread.elapsed),
+                })
+            })
+            .collect();
+        let invalid_words: Vec<Value> = self
+            .invalid_words
+            .iter()
+            .map(|invalid_word| {
+                json!({
+                    "word": invalid_word.word,
+                    "invalid_chars": invalid_word.invalid_chars.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!({
+            "line_count": self.line_count,
+            "word_count": self.word_count,
+            "duplicate_count": self.count_json(self.metrics.counts.duplicate_count),
+            "blank_line_count": self.blank_line_count,
+            "rejected_line_count": self.rejected_line_count,
+            "thread_count": self.thread_count(),
+            "hit_count": self.count_json(self.metrics.counts.hit_count),
+            "miss_count": self.count_json(self.metrics.counts.miss_count),
+            "node_count": self.count_json(self.metrics.counts.node_count),
+            "steps": steps,
+            "threads": threads,
+            "invalid_words": invalid_words,
+        })
+    }
+
+    /// The report as one line of a CSV file with the columns in `BUILD_REPORT_CSV_HEADER`.
+    pub fn to_csv_row(&self) -> String {
+        let counts = &self.metrics.counts;
+        let is_counted = self.metrics.is_counted;
+        let mut fields: Vec<String> = [
+            (self.line_count, true),
+            (self.word_count, true),
+            (counts.duplicate_count, is_counted),
+            (self.blank_line_count, true),
+            (self.rejected_line_count, true),
+            (self.thread_count(), true),
+            (counts.hit_count, is_counted),
+            (counts.miss_count, is_counted),
+            (counts.node_count, is_counted),
+        ]
+        .iter()
+        .map(|(count, is_known)| {
+            if *is_known {
+                count.to_string()
+            } else {
+                "".to_owned()
+            }
+        })
+        .collect();
+        for step_label in CSV_STEP_LABELS.iter() {
+            fields.push(match self.metrics.step_time(step_label) {
+                Some(elapsed) => format!("{:.3}", duration_ms(elapsed)),
+                None => "".to_owned(),
+            });
+        }
+        fields.join(",")
+    }
+
+    // One of the insert counts for to_json(), which is null if the counts weren't collected.
+    fn count_json(&self, count: usize) -> Value {
+        if self.metrics.is_counted {
+            json!(count)
+        } else {
+            Value::Null
+        }
+    }
+
+    // Normalize one line of a word file as Normalizer::load_chars() does, counting what kind of line it was.
+    pub(crate) fn load_line(&mut self, normalizer: &Normalizer, line: &str) -> Option<Vec<char>> {
+        self.line_count += 1;
+        match normalizer.load_line(line) {
+            LoadedLine::Word(v) => {
+                self.word_count += 1;
+                Some(v)
+            }
+            LoadedLine::Blank => {
+                self.blank_line_count += 1;
+                None
+            }
+            LoadedLine::Rejected(invalid_word) => {
+                self.rejected_line_count += 1;
+                if normalizer.invalid_word_action == InvalidWordAction::Report {
+                    self.invalid_words.push(invalid_word);
+                }
+                None
+            }
+        }
+    }
+}
+
+fn duration_ms(elapsed: std::time::Duration) -> f64 {
+    elapsed.as_secs_f64() * 1_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn build<T: LetterTrie>(filename: &str, load_method: &LoadMethod) -> BuildReport {
+        let mut opt = DisplayDetailOptions::make_no_display();
+        opt.count_inserts = true;
+        T::from_file_report(
+            filename,
+            false,
+            load_method,
+            &opt,
+            None,
+            &Normalizer::make_ascii_letters(),
+        )
+        .1
+    }
+
+    #[test]
+    fn line_counts() {
+        let filename = env::temp_dir().join("letter_trie_build_report_test.txt");
+        fs::write(
+            &filename,
+            "apple\n\nApple\nbanana\n  \nbanana split\nbandana\napple\n",
+        )
+        .unwrap();
+        let filename = filename.to_str().unwrap();
+        for load_method in LoadMethod::ALL.iter() {
+            let reports = [
+                build::<BaseLetterTrie>(filename, load_method),
+                build::<NoParentLetterTrie>(filename, load_method),
+                build::<LetterTrieSet>(filename, load_method),
+            ];
+            for report in reports.iter() {
+                assert_eq!(report.line_count, 8);
+                assert_eq!(report.word_count, 5);
+                assert_eq!(report.metrics.counts.duplicate_count, 2);
+                assert_eq!(report.blank_line_count, 2);
+                assert_eq!(report.rejected_line_count, 1);
+                assert_eq!(
+                    report.invalid_words,
+                    vec![InvalidWord {
+                        word: "banana split".to_owned(),
+                        invalid_chars: vec![' '],
+                    }]
+                );
+            }
+        }
+        fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn csv_row() {
+        let dataset = Dataset::TestSmallSorted;
+        let (_, report) = LetterTrieSet::from_file_test(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+            &DisplayDetailOptions::make_no_display(),
+            None,
+        );
+        let row = report.to_csv_row();
+        let fields: Vec<&str> = row.split(',').collect();
+        assert_eq!(fields.len(), BUILD_REPORT_CSV_HEADER.split(',').count());
+        // The duplicates, hits, misses and nodes created weren't counted.
+        assert_eq!(&fields[..9], &["13", "10", "", "3", "0", "2", "", "", ""]);
+        // There's an overall time but the file isn't read as a single string.
+        assert!(!fields[9].is_empty());
+        assert!(fields[10].is_empty());
+        assert_eq!(report.to_json()["threads"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn counts_only_when_asked() {
+        fn reports<T: LetterTrie>() -> Vec<BuildReport> {
+            let dataset = Dataset::TestSmallUnsorted;
+            let mut opt = DisplayDetailOptions::make_no_display();
+            [false, true]
+                .iter()
+                .map(|count_inserts| {
+                    opt.count_inserts = *count_inserts;
+                    T::from_file_test(
+                        dataset.filename(),
+                        dataset.is_sorted(),
+                        &LoadMethod::ContinuousParallel,
+                        &opt,
+                        None,
+                    )
+                    .1
+                })
+                .collect()
+        }
+
+        for reports in [
+            reports::<BaseLetterTrie>(),
+            reports::<NoParentLetterTrie>(),
+            reports::<LetterTrieSet>(),
+        ]
+        .iter()
+        {
+            let (off, on) = (&reports[0], &reports[1]);
+            assert!(!off.metrics.is_counted);
+            assert!(on.metrics.is_counted);
+
+            let (off_json, on_json) = (off.to_json(), on.to_json());
+            for key in ["duplicate_count", "hit_count", "miss_count", "node_count"].iter() {
+                assert!(off_json[key].is_null());
+                assert!(on_json[key].is_u64());
+                for thread in off_json["threads"].as_array().unwrap().iter() {
+                    assert!(thread[key].is_null());
+                }
+                for thread in on_json["threads"].as_array().unwrap().iter() {
+                    assert!(thread[key].is_u64());
+                }
+            }
+            assert_eq!(on_json["duplicate_count"], 0);
+            assert_eq!(off_json["word_count"], 10);
+
+            let off_row = off.to_csv_row();
+            let off_fields: Vec<&str> = off_row.split(',').collect();
+            let on_row = on.to_csv_row();
+            let on_fields: Vec<&str> = on_row.split(',').collect();
+            for index in [2, 6, 7, 8].iter() {
+                assert!(off_fields[*index].is_empty());
+                assert!(!on_fields[*index].is_empty());
+            }
+            assert_eq!(off_fields[..2], on_fields[..2]);
+            assert!(!off.metrics.to_string().contains("hit count"));
+            assert!(on.metrics.to_string().contains("hit count"));
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use rand::Rng;
+use std::iter;
+use std::mem;
+
+use crate::trie_map::TrieMapNode;
+use crate::*;
+
+const START: char = '[';
+const END: char = ']';
+
+/// A Markov model for generating words, like SequenceModel, but stored in a trie of contexts instead of a map of
+/// prefix strings for each depth.
+///
+/// Each context (the last one to `max_depth` characters of a partially-formed word, with "[" marking the start of
+/// the word) is a path through the trie taken in reverse, from the most recent character back. The node at the end
+/// of the path holds a count of each character that followed that context in the example words, with "]" marking
+/// the end of a word. Because every shorter context is a suffix of a longer one, the contexts share nodes and
+/// choosing the next character is a single walk down from the root that stops at the longest context that was
+/// seen, which is the same backing off that SequenceModel does with a separate map lookup at each depth.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+///
+/// let example_words: Vec<String> = words_from_file("english_words_3_000.txt");
+/// let model = ContextTrie::new(&example_words, 3);
+///
+/// let mut rng = StdRng::seed_from_u64(3);
+/// let word = model.generate_word(&mut rng);
+/// assert!(word.chars().all(|c| c.is_ascii_lowercase()));
+///
+/// // There's one node for each distinct context plus the root.
+/// let context_count: usize = SequenceModel::new(&example_words, 3)
+///     .depth_stats(0)
+///     .iter()
+///     .map(|stats| stats.prefix_count)
+///     .sum();
+/// assert_eq!(model.node_count(), context_count + 1);
+/// ```
+pub struct ContextTrie {
+    max_depth: usize,
+    contexts: LetterTrieMap<NextCounts>,
+}
+
+// The characters that followed one context and how many times each one did.
+#[derive(Default)]
+struct NextCounts {
+    counts: Vec<(char, usize)>,
+    total: usize,
+}
+
+impl NextCounts {
+    fn add(&mut self, c: char) {
+        match self.counts.iter_mut().find(|(next_c, _)| *next_c == c) {
+            Some((_, count)) => *count += 1,
+            None => self.counts.push((c, 1)),
+        }
+        self.total += 1;
+    }
+
+    fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> char {
+        let mut n = rng.gen_range(0, self.total);
+        for (c, count) in self.counts.iter() {
+            if n < *count {
+                return *c;
+            }
+            n -= count;
+        }
+        unreachable!()
+    }
+}
+
+impl ContextTrie {
+    /// Build the model from a list of real words, where `max_depth` is the highest number of letters to use when
+    /// deciding what letter comes next. As with SequenceModel, words with anything other than the letters a to z
+    /// are skipped.
+    pub fn new(example_words: &[String], max_depth: usize) -> Self {
+        let mut contexts: LetterTrieMap<NextCounts> = LetterTrieMap::new();
+        for example in example_words.iter().map(|x| x.trim().to_lowercase()) {
+            if example.is_empty() || !example.chars().all(|c| c.is_ascii_lowercase()) {
+                continue;
+            }
+            let word: Vec<char> = iter::once(START)
+                .chain(example.chars())
+                .chain(iter::once(END))
+                .collect();
+            for i in 1..word.len() {
+                // The contexts ending just before position i, reversed so the most recent character comes first.
+                let reversed: Vec<char> = word[i.saturating_sub(max_depth)..i]
+                    .iter()
+                    .rev()
+                    .cloned()
+                    .collect();
+                for depth in 1..=reversed.len() {
+                    contexts.entry(&reversed[..depth]).or_default().add(word[i]);
+                }
+            }
+        }
+        contexts.freeze();
+        Self {
+            max_depth,
+            contexts,
+        }
+    }
+
+    /// The highest number of letters used to decide what letter comes next.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// The number of nodes in the trie, which is the number of distinct contexts plus one for the root.
+    pub fn node_count(&self) -> usize {
+        self.contexts.node_count()
+    }
+
+    /// The number of distinct pairs of a context and the character after it.
+    pub fn transition_count(&self) -> usize {
+        self.contexts
+            .iter()
+            .map(|(_, next_counts)| next_counts.counts.len())
+            .sum()
+    }
+
+    /// A rough estimate of the memory used by the model in bytes, counting the size of each node and each
+    /// transition but not the spare capacity inside the maps and vectors. Comparable to
+    /// `SequenceModel::estimated_bytes()`.
+    pub fn estimated_bytes(&self) -> usize {
+        self.node_count() * mem::size_of::<TrieMapNode<char, NextCounts>>()
+            + self.transition_count() * mem::size_of::<(char, usize)>()
+    }
+
+    /// Generate one word from start to end. The word may be one of the example words.
+    pub fn generate_word<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        let mut word = vec![START];
+        loop {
+            // Walk down the most recent characters to the longest context that was seen.
+            let mut node = self.contexts.root();
+            for c in word.iter().rev().take(self.max_depth) {
+                match node.children().get(c) {
+                    Some(child_node) => node = child_node,
+                    None => break,
+                }
+            }
+            let next_c = match node.value() {
+                Some(next_counts) => next_counts.pick(rng),
+                None => END,
+            };
+            if next_c == END {
+                return word[1..].iter().collect();
+            }
+            word.push(next_c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+    use test::Bencher;
+
+    const FILENAME_ENGLISH_3_000: &str = "english_words_3_000.txt";
+    const BENCH_WORD_COUNT: usize = 1_000;
+
+    #[test]
+    fn matches_sequence_model_size() {
+        let example_words = words_from_file(FILENAME_ENGLISH_3_000);
+        for max_depth in 1..=4 {
+            let trie = ContextTrie::new(&example_words, max_depth);
+            let stats = SequenceModel::new(&example_words, max_depth).depth_stats(0);
+            let prefix_count: usize = stats.iter().map(|stats| stats.prefix_count).sum();
+            let transition_count: usize = stats.iter().map(|stats| stats.transition_count).sum();
+            assert_eq!(trie.node_count(), prefix_count + 1);
+            assert_eq!(trie.transition_count(), transition_count);
+        }
+    }
+
+    #[test]
+    fn generates_only_possible_words() {
+        let example_words = vec!["cat".to_owned(), "cot".to_owned(), "C4T".to_owned()];
+        let trie = ContextTrie::new(&example_words, 2);
+        let mut rng = StdRng::seed_from_u64(7);
+        let words: HashSet<String> = (0..100).map(|_| trie.generate_word(&mut rng)).collect();
+        let expected: HashSet<String> = ["cat", "cot"].iter().map(|x| x.to_string()).collect();
+        assert_eq!(words, expected);
+    }
+
+    #[bench]
+    fn bench_generate_context_trie(b: &mut Bencher) {
+        let trie = ContextTrie::new(&words_from_file(FILENAME_ENGLISH_3_000), 3);
+        let mut rng = StdRng::seed_from_u64(1);
+        b.iter(|| {
+            for _ in 0..BENCH_WORD_COUNT {
+                trie.generate_word(&mut rng);
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_generate_sequence_model(b: &mut Bencher) {
+        let model = SequenceModel::new(&words_from_file(FILENAME_ENGLISH_3_000), 3);
+        let mut rng = StdRng::seed_from_u64(1);
+        b.iter(|| {
+            for _ in 0..BENCH_WORD_COUNT {
+                model.generate_word(&mut rng);
+            }
+        });
+    }
+}
@@ -0,0 +1,303 @@
+use serde_json::{json, Map, Value};
+
+use crate::*;
+
+/// Options for `LetterTrie::to_d3_hierarchy()`, which describes a trie in the nested form used by D3's
+/// `d3.hierarchy()`.
+pub struct D3Options {
+    /// The number of levels to include below the root of the hierarchy. A value of 0 gives only the root. It can't
+    /// be more than `DEBUG_TRIE_MAX_DEPTH`.
+    pub max_depth: usize,
+    /// Leave out any subtree with fewer words than this below the root. A value of 0 or 1 keeps everything.
+    pub min_word_count: usize,
+}
+
+impl D3Options {
+    /// Create a set of options that include as much of the trie as the debugging limits allow.
+    pub fn make_default() -> Self {
+        Self::make(DEBUG_TRIE_MAX_DEPTH, 0)
+    }
+
+    /// Create a set of options with the given depth limit and smallest subtree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// // Three levels, leaving out the branches that lead to only one or two words.
+    /// let opt = D3Options::make(3, 3);
+    /// ```
+    pub fn make(max_depth: usize, min_word_count: usize) -> Self {
+        Self {
+            max_depth,
+            min_word_count,
+        }
+    }
+}
+
+// Builds the hierarchy as a tree of JSON objects. Each node's object is held here until add_edge() moves it into
+// its parent's list of children.
+pub(crate) struct D3Writer {
+    nodes: Vec<Option<Map<String, Value>>>,
+    max_depth: usize,
+    min_word_count: usize,
+}
+
+impl D3Writer {
+    pub(crate) fn new(opt: &D3Options) -> Self {
+        Self {
+            nodes: vec![],
+            max_depth: opt.max_depth.min(DEBUG_TRIE_MAX_DEPTH),
+            min_word_count: opt.min_word_count,
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Value {
+        Value::Object(self.nodes[0].take().unwrap())
+    }
+}
+
+impl GraphWriter for D3Writer {
+    fn children_to_show(&self, depth: usize, child_count: usize) -> usize {
+        if depth < self.max_depth {
+            child_count.min(DEBUG_TRIE_MAX_CHILDREN)
+        } else {
+            0
+        }
+    }
+
+    // The root of the hierarchy is named for its whole prefix and every other node for its letter.
+    fn add_node(&mut self, prefix: &str, node: &FixedNode, depth: usize) -> Option<usize> {
+        if depth > 0 && node.word_count < self.min_word_count {
+            return None;
+        }
+        let name = if depth == 0 {
+            prefix.to_owned()
+        } else {
+            node.c.to_string()
+        };
+        let mut object = Map::new();
+        object.insert("name".to_owned(), json!(name));
+        object.insert("is_word".to_owned(), json!(node.is_word));
+        object.insert("word_count".to_owned(), json!(node.word_count));
+        object.insert("height".to_owned(), json!(node.height));
+        self.nodes.push(Some(object));
+        Some(self.nodes.len() - 1)
+    }
+
+    // Leaves have no "children" field, which is what d3.hierarchy() expects.
+    fn add_edge(&mut self, parent_id: usize, child_id: usize, _c: char) {
+        let child = Value::Object(self.nodes[child_id].take().unwrap());
+        let parent = self.nodes[parent_id].as_mut().unwrap();
+        match parent
+            .entry("children")
+            .or_insert_with(|| Value::Array(vec![]))
+        {
+            Value::Array(children) => children.push(child),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Create a self-contained HTML page that shows a hierarchy from `to_d3_hierarchy()` as a tree that can be explored
+/// offline. The data is embedded in the page and it's drawn with a few lines of plain JavaScript rather than with
+/// D3 itself, so nothing has to be downloaded.
+///
+/// As in the original interactive version, clicking a node makes it the root of the tree. Clicking the root goes
+/// back up one level. Nodes that complete a word are filled in, and hovering over a node shows its prefix, word
+/// count and height.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let trie = LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+///
+/// let hierarchy = trie.to_d3_hierarchy("", &D3Options::make_default()).unwrap();
+/// let page = d3_html_page(&hierarchy, "Small trie");
+/// assert!(page.contains("<title>Small trie</title>"));
+/// ```
+pub fn d3_html_page(hierarchy: &Value, title: &str) -> String {
+    // Keep "</script>" inside a string in the data from ending the script early.
+    let data = hierarchy.to_string().replace("</", "<\\/");
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    D3_HTML_TEMPLATE
+        .replace("{title}", &title)
+        .replace("{data}", &data)
+}
+
+const D3_HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+  body { font-family: Helvetica, Arial, sans-serif; margin: 16px; }
+  #path { margin-bottom: 8px; color: #555; }
+  .link { fill: none; stroke: #bbb; stroke-width: 1.5px; }
+  .node circle { stroke: steelblue; stroke-width: 1.5px; fill: #fff; cursor: pointer; }
+  .node.word circle { fill: lightsteelblue; }
+  .node text { font-size: 12px; }
+</style>
+</head>
+<body>
+<h3>{title}</h3>
+<div id="path"></div>
+<svg id="tree"></svg>
+<script>
+const data = {data};
+const SVG_NS = "http://www.w3.org/2000/svg";
+const ROW_HEIGHT = 18;
+const COLUMN_WIDTH = 60;
+const MARGIN = 30;
+// The nodes from the top of the data down to the one being shown as the root.
+const path = [{ node: data, prefix: data.name }];
+
+function element(name, attributes, parent) {
+  const e = document.createElementNS(SVG_NS, name);
+  for (const key in attributes) {
+    e.setAttribute(key, attributes[key]);
+  }
+  parent.appendChild(e);
+  return e;
+}
+
+// Give each node a column by depth and a row, with leaves in order and each parent centered on its children.
+function layout(node, prefix, depth, rows, placed) {
+  const item = { node: node, prefix: prefix, x: depth * COLUMN_WIDTH, y: 0, children: [] };
+  placed.push(item);
+  if (node.children && node.children.length > 0) {
+    for (const child of node.children) {
+      item.children.push(layout(child, prefix + child.name, depth + 1, rows, placed));
+    }
+    item.y = (item.children[0].y + item.children[item.children.length - 1].y) / 2;
+  } else {
+    item.y = rows.count * ROW_HEIGHT;
+    rows.count += 1;
+  }
+  return item;
+}
+
+function draw() {
+  const svg = document.getElementById("tree");
+  while (svg.firstChild) {
+    svg.removeChild(svg.firstChild);
+  }
+  const top = path[path.length - 1];
+  document.getElementById("path").textContent =
+    "Showing \"" + top.prefix + "\". Click a node to make it the root and click the root to go back up.";
+  const rows = { count: 0 };
+  const placed = [];
+  const root = layout(top.node, top.prefix, 0, rows, placed);
+  let width = 0;
+  for (const item of placed) {
+    width = Math.max(width, item.x);
+  }
+  svg.setAttribute("width", width + 2 * MARGIN + 200);
+  svg.setAttribute("height", Math.max(rows.count, 1) * ROW_HEIGHT + 2 * MARGIN);
+  const g = element("g", { transform: "translate(" + MARGIN + "," + MARGIN + ")" }, svg);
+  for (const item of placed) {
+    for (const child of item.children) {
+      const midX = (item.x + child.x) / 2;
+      element("path", {
+        class: "link",
+        d: "M" + item.x + "," + item.y + "C" + midX + "," + item.y + " " + midX + "," + child.y + " " +
+          child.x + "," + child.y,
+      }, g);
+    }
+  }
+  for (const item of placed) {
+    const nodeG = element("g", {
+      class: item.node.is_word ? "node word" : "node",
+      transform: "translate(" + item.x + "," + item.y + ")",
+    }, g);
+    element("circle", { r: 5 }, nodeG);
+    const label = element("text", { x: 8, dy: "0.35em" }, nodeG);
+    label.textContent = item === root ? (item.prefix || "(root)") : item.node.name;
+    const tooltip = element("title", {}, nodeG);
+    tooltip.textContent = "\"" + item.prefix + "\": " + item.node.word_count + " words, height " +
+      item.node.height;
+    nodeG.addEventListener("click", function () {
+      if (item === root) {
+        if (path.length > 1) {
+          path.pop();
+        }
+      } else {
+        path.push({ node: item.node, prefix: item.prefix });
+      }
+      draw();
+    });
+  }
+}
+
+draw();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hierarchy() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let base = BaseLetterTrie::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let no_parent = NoParentLetterTrie::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let map = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+
+        let h = map
+            .to_d3_hierarchy("an", &D3Options::make_default())
+            .unwrap();
+        assert_eq!(
+            h,
+            json!({
+                "name": "an", "is_word": true, "word_count": 2, "height": 2,
+                "children": [{ "name": "d", "is_word": true, "word_count": 1, "height": 1 }]
+            })
+        );
+
+        let opt = D3Options::make(2, 2);
+        let h = map.to_d3_hierarchy("", &opt).unwrap();
+        assert_eq!(base.to_d3_hierarchy("", &opt).unwrap(), h);
+        assert_eq!(no_parent.to_d3_hierarchy("", &opt).unwrap(), h);
+        assert_eq!(h["name"], "");
+        assert_eq!(h["word_count"], WORD_COUNT_SMALL);
+        // Below "a", "an" leads to two words and "az" to only one.
+        let a = &h["children"][0];
+        assert_eq!(a["name"], "a");
+        assert_eq!(a["children"].as_array().unwrap().len(), 1);
+        assert_eq!(a["children"][0]["name"], "n");
+        assert!(a["children"][0].get("children").is_none());
+
+        assert!(map.to_d3_hierarchy("x", &opt).is_none());
+        assert!(base.to_d3_hierarchy("x", &opt).is_none());
+        assert!(no_parent.to_d3_hierarchy("x", &opt).is_none());
+    }
+
+    #[test]
+    fn html_page() {
+        let page = d3_html_page(&json!({ "name": "</script>" }), "A & B");
+        assert!(page.contains("<title>A &amp; B</title>"));
+        assert!(page.contains(r#"const data = {"name":"<\/script>"};"#));
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::*;
+
+/// The environment variable that sets the directory where generated datasets are cached.
+pub const DATA_DIR_ENV_VAR: &str = "LETTER_TRIE_DATA_DIR";
+/// The directory where generated datasets are cached if `LETTER_TRIE_DATA_DIR` isn't set, relative to the
+/// directory the program or test runs in.
+pub const DEFAULT_DATA_DIR: &str = "datasets";
+
+const GENERATOR_SOURCE_FILENAME: &str = "english_words_3_000.txt";
+const GENERATOR_MAX_DEPTH: usize = 3;
+const GENERATOR_SEED: u64 = 400_000;
+// The node count, height and number of first letters of a trie built from the large dataset, as checked by
+// `assert_large_root()`.
+pub(crate) const LARGE_NODE_COUNT: usize = 1_081_648;
+const LARGE_MAX_WORD_LEN: usize = 16;
+const LARGE_MISSING_FIRST_LETTER: char = 'x';
+
+lazy_static! {
+    /// The registry used by `Dataset::filename()`, `good_words()` and `non_words()`, caching generated files in
+    /// the directory named by the `LETTER_TRIE_DATA_DIR` environment variable or in "datasets".
+    pub static ref DATASET_REGISTRY: DatasetRegistry = DatasetRegistry::from_env();
+}
+
+/// How a dataset file comes to exist.
+#[derive(Debug, PartialEq)]
+pub enum DatasetRecipe {
+    /// A file that's part of the repository, found relative to the directory the program or test runs in.
+    CheckedIn,
+    /// The 400,000 words of the large dataset in the order they were added, or sorted. They start with the good
+    /// words and the words of the medium dataset. After those come distinct words from a WordGenerator built from
+    /// "english_words_3_000.txt" with a depth of 3 and a fixed seed, leaving out the non-words, words longer than
+    /// 16 letters and words starting with "x".
+    Generated { is_sorted: bool },
+    /// The first 1,000 words of the unsorted large dataset, so each one is known to be in the large dataset. The
+    /// file is checked in and the large dataset is built to start with it, so it only has to be generated again
+    /// if it's deleted.
+    GoodWords,
+    /// Each of the good words with "q" appended, none of which are in the large dataset. Like the good words,
+    /// the file is checked in.
+    NonWords,
+}
+
+/// A word file used for testing, with what's needed to produce it and to confirm that it's correct.
+#[derive(Debug)]
+pub struct DatasetSpec {
+    pub filename: &'static str,
+    /// The number of non-blank lines.
+    pub word_count: usize,
+    /// The 64-bit FNV-1a hash of the whole file with "\r\n" line endings read as "\n", as from `text_checksum()`.
+    pub checksum: u64,
+    pub recipe: DatasetRecipe,
+}
+
+/// Every dataset file the crate knows how to find or produce.
+pub const DATASET_SPECS: [DatasetSpec; 9] = [
+    DatasetSpec {
+        filename: FILENAME_SMALL_SORTED,
+        word_count: WORD_COUNT_SMALL,
+        checksum: 0xe581_2c68_1450_4467,
+        recipe: DatasetRecipe::CheckedIn,
+    },
+    DatasetSpec {
+        filename: FILENAME_SMALL_UNSORTED,
+        word_count: WORD_COUNT_SMALL,
+        checksum: 0x5f16_a515_e005_a01f,
+        recipe: DatasetRecipe::CheckedIn,
+    },
+    DatasetSpec {
+        filename: GENERATOR_SOURCE_FILENAME,
+        word_count: 3_000,
+        checksum: 0x1f57_2c53_15a1_98d3,
+        recipe: DatasetRecipe::CheckedIn,
+    },
+    DatasetSpec {
+        filename: FILENAME_MEDIUM_SORTED,
+        word_count: WORD_COUNT_MEDIUM,
+        checksum: 0x4f1d_952f_4478_4fa4,
+        recipe: DatasetRecipe::CheckedIn,
+    },
+    DatasetSpec {
+        filename: FILENAME_MEDIUM_UNSORTED,
+        word_count: WORD_COUNT_MEDIUM,
+        checksum: 0xfdfd_7e4d_d543_6fc2,
+        recipe: DatasetRecipe::CheckedIn,
+    },
+    DatasetSpec {
+        filename: FILENAME_LARGE_SORTED,
+        word_count: WORD_COUNT_LARGE,
+        checksum: 0xd1f0_b23c_7a75_f607,
+        recipe: DatasetRecipe::Generated { is_sorted: true },
+    },
+    DatasetSpec {
+        filename: FILENAME_LARGE_UNSORTED,
+        word_count: WORD_COUNT_LARGE,
+        checksum: 0x0a8b_47b7_0bc8_a29d,
+        recipe: DatasetRecipe::Generated { is_sorted: false },
+    },
+    DatasetSpec {
+        filename: FILENAME_GOOD_WORDS,
+        word_count: WORD_COUNT_GOOD,
+        checksum: 0x5e00_cd55_a92d_6d45,
+        recipe: DatasetRecipe::GoodWords,
+    },
+    DatasetSpec {
+        filename: FILENAME_NON_WORDS,
+        word_count: WORD_COUNT_NON,
+        checksum: 0xebd9_9b9c_9f47_45fb,
+        recipe: DatasetRecipe::NonWords,
+    },
+];
+
+/// Finds dataset files, generating any that are missing into a cache directory and confirming the word count and
+/// checksum of each file the first time it's asked for.
+///
+/// Generated files come from a seeded WordGenerator so they're the same on every machine, which means a fresh
+/// checkout can run the large tests and benchmarks without downloading anything. Generating the large dataset
+/// takes a few seconds in a release build and longer in a debug build, and it only happens once per cache
+/// directory.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let filename: &str = DATASET_REGISTRY.path("english_words_10_sorted.txt");
+/// assert_eq!(words_from_file(filename).len(), 10);
+/// ```
+pub struct DatasetRegistry {
+    directory: String,
+    // The path of each dataset, computed up front so that `path()` can hand out references to them.
+    paths: BTreeMap<&'static str, String>,
+    // The filenames that have been confirmed to be correct. This also serves as a lock so that only one thread
+    // at a time generates files.
+    verified: Mutex<BTreeSet<&'static str>>,
+}
+
+impl DatasetRegistry {
+    /// Create a registry that caches generated files in a given directory, which is created if needed.
+    pub fn new(directory: &str) -> Self {
+        let paths = DATASET_SPECS
+            .iter()
+            .map(|spec| {
+                let path = match spec.recipe {
+                    DatasetRecipe::Generated { .. } => Path::new(directory)
+                        .join(spec.filename)
+                        .to_str()
+                        .unwrap()
+                        .to_owned(),
+                    _ => spec.filename.to_owned(),
+                };
+                (spec.filename, path)
+            })
+            .collect();
+        Self {
+            directory: directory.to_owned(),
+            paths,
+            verified: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Create a registry that caches generated files in the directory named by the `LETTER_TRIE_DATA_DIR`
+    /// environment variable, or in "datasets" if it's not set.
+    pub fn from_env() -> Self {
+        let directory = env::var(DATA_DIR_ENV_VAR).unwrap_or_else(|_| DEFAULT_DATA_DIR.to_owned());
+        Self::new(&directory)
+    }
+
+    /// The directory where generated files are cached.
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// Get the path to a dataset file, generating it first if it's missing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filename isn't one of the files in `DATASET_SPECS`, if a checked-in file is missing, if a
+    /// file can't be written, or if a file has the wrong number of words or the wrong checksum. A generated file
+    /// that fails the check can be deleted so that it's generated again.
+    pub fn path(&self, filename: &str) -> &str {
+        let spec = dataset_spec(filename);
+        let mut verified = self
+            .verified
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !verified.contains(spec.filename) {
+            let path = &self.paths[spec.filename];
+            if !Path::new(path).exists() {
+                assert_ne!(
+                    spec.recipe,
+                    DatasetRecipe::CheckedIn,
+                    "Missing file \"{}\".",
+                    path
+                );
+                self.generate_all();
+            }
+            if let Err(message) = self.verify(spec) {
+                panic!("{}", message);
+            }
+            verified.insert(spec.filename);
+        }
+        &self.paths[spec.filename]
+    }
+
+    /// Check that a dataset file has the expected number of words and checksum.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the file is missing or doesn't match.
+    pub fn verify(&self, spec: &DatasetSpec) -> Result<(), String> {
+        let path = &self.paths[spec.filename];
+        let content = fs::read(path).map_err(|e| format!("Error reading \"{}\": {}", path, e))?;
+        let word_count = String::from_utf8_lossy(&content)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count();
+        if word_count != spec.word_count {
+            return Err(format!(
+                "\"{}\" has {} words but should have {}.",
+                path, word_count, spec.word_count
+            ));
+        }
+        let checksum = text_checksum(&content);
+        if checksum != spec.checksum {
+            return Err(format!(
+                "\"{}\" has checksum {:#018x} but should have {:#018x}.",
+                path, checksum, spec.checksum
+            ));
+        }
+        Ok(())
+    }
+
+    // Generate every file that isn't checked in and doesn't exist yet. The large datasets are built around the
+    // good words and the good words can be recovered from the large datasets, so they're made together.
+    fn generate_all(&self) {
+        fs::create_dir_all(&self.directory).expect("Error creating the dataset directory.");
+        // This runs while `path()` holds the lock so the other files are checked directly rather than through
+        // `path()`.
+        let verified_words = |filename: &str| {
+            let spec = dataset_spec(filename);
+            if let Err(message) = self.verify(spec) {
+                panic!("{}", message);
+            }
+            words_from_file(&self.paths[spec.filename])
+        };
+        let large_unsorted_path = &self.paths[FILENAME_LARGE_UNSORTED];
+        let good_words = if Path::new(&self.paths[FILENAME_GOOD_WORDS]).exists() {
+            verified_words(FILENAME_GOOD_WORDS)
+        } else {
+            assert!(
+                Path::new(large_unsorted_path).exists(),
+                "\"{}\" is needed to generate the large datasets.",
+                FILENAME_GOOD_WORDS
+            );
+            verified_words(FILENAME_LARGE_UNSORTED)[..WORD_COUNT_GOOD].to_vec()
+        };
+        let non_words: Vec<String> = good_words.iter().map(|word| format!("{}q", word)).collect();
+        let large_words = if Path::new(large_unsorted_path).exists() {
+            verified_words(FILENAME_LARGE_UNSORTED)
+        } else {
+            make_large_words(
+                &good_words,
+                &non_words,
+                &verified_words(FILENAME_MEDIUM_UNSORTED),
+                &verified_words(GENERATOR_SOURCE_FILENAME),
+            )
+        };
+
+        for spec in DATASET_SPECS.iter() {
+            let lines = match spec.recipe {
+                DatasetRecipe::CheckedIn => continue,
+                DatasetRecipe::Generated { is_sorted } => {
+                    let mut lines = large_words.clone();
+                    if is_sorted {
+                        lines.sort_unstable();
+                    }
+                    lines
+                }
+                DatasetRecipe::GoodWords => good_words.clone(),
+                DatasetRecipe::NonWords => non_words.clone(),
+            };
+            let path = &self.paths[spec.filename];
+            if !Path::new(path).exists() {
+                // Write to a temporary file first so that an interrupted run doesn't leave a partial file behind.
+                let temp_path = format!("{}.tmp", path);
+                let mut file = File::create(&temp_path).expect("Error creating file.");
+                for line in lines.iter() {
+                    writeln!(file, "{}", line).expect("Error writing a line.");
+                }
+                fs::rename(&temp_path, path).expect("Error renaming file.");
+            }
+        }
+    }
+}
+
+// Put together the words of the large dataset as described for DatasetRecipe::Generated.
+fn make_large_words(
+    good_words: &[String],
+    non_words: &[String],
+    medium_words: &[String],
+    source_words: &[String],
+) -> Vec<String> {
+    let non_words: HashSet<&String> = non_words.iter().collect();
+    let mut words = Vec::with_capacity(WORD_COUNT_LARGE);
+    let mut word_set = HashSet::with_capacity(WORD_COUNT_LARGE);
+    for word in good_words.iter().chain(medium_words.iter()) {
+        if word_set.insert(word.clone()) {
+            words.push(word.clone());
+        }
+    }
+    let generator = WordGenerator::new(source_words, GENERATOR_MAX_DEPTH)
+        .seed(GENERATOR_SEED)
+        .max_length(LARGE_MAX_WORD_LEN)
+        .silent(true);
+    let mut candidates = generator
+        .iter()
+        .filter(|word| !word.starts_with(LARGE_MISSING_FIRST_LETTER) && !non_words.contains(word));
+    while words.len() < WORD_COUNT_LARGE {
+        let word = candidates.next().expect("Ran out of generated words.");
+        if word_set.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// Get the spec for a dataset file.
+///
+/// # Panics
+///
+/// Panics if the filename isn't one of the files in `DATASET_SPECS`.
+pub fn dataset_spec(filename: &str) -> &'static DatasetSpec {
+    DATASET_SPECS
+        .iter()
+        .find(|spec| spec.filename == filename)
+        .unwrap_or_else(|| panic!("Unknown dataset \"{}\".", filename))
+}
+
+/// The 64-bit FNV-1a hash of some bytes, used as a checksum because it's simple and, unlike the standard library's
+/// hasher, guaranteed to stay the same.
+pub fn fnv1a_checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// The checksum of a text file, which is `fnv1a_checksum()` of its bytes with each "\r\n" changed to "\n". This
+/// way a checkout that converts line endings, as Git does on Windows with `core.autocrlf`, still matches.
+pub fn text_checksum(bytes: &[u8]) -> u64 {
+    let normalized: Vec<u8> = bytes
+        .iter()
+        .enumerate()
+        .filter(|(i, byte)| !(**byte == b'\r' && bytes.get(i + 1) == Some(&b'\n')))
+        .map(|(_, byte)| *byte)
+        .collect();
+    fnv1a_checksum(&normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum() {
+        assert_eq!(fnv1a_checksum(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_checksum(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(text_checksum(b"a\r\nb\n"), fnv1a_checksum(b"a\nb\n"));
+        assert_eq!(text_checksum(b"a\rb"), fnv1a_checksum(b"a\rb"));
+    }
+
+    #[test]
+    fn all_datasets() {
+        for spec in DATASET_SPECS.iter() {
+            let path = DATASET_REGISTRY.path(spec.filename);
+            assert_eq!(DATASET_REGISTRY.verify(spec), Ok(()));
+            assert_eq!(words_from_file(path).len(), spec.word_count);
+        }
+        let large_words = large_dataset_words_hash_set();
+        assert_eq!(large_words.len(), WORD_COUNT_LARGE);
+        assert!(good_words().iter().all(|word| large_words.contains(word)));
+        assert!(non_words().iter().all(|word| !large_words.contains(word)));
+    }
+
+    #[test]
+    fn cached_file_is_verified() {
+        // Copy a generated file into a separate directory so the registry uses it instead of generating it.
+        let directory = env::temp_dir().join("letter_trie_dataset_registry_test");
+        fs::create_dir_all(&directory).unwrap();
+        let registry = DatasetRegistry::new(directory.to_str().unwrap());
+        let spec = dataset_spec(FILENAME_LARGE_UNSORTED);
+        let path = directory.join(spec.filename);
+        fs::copy(DATASET_REGISTRY.path(spec.filename), &path).unwrap();
+        assert_eq!(registry.path(spec.filename), path.to_str().unwrap());
+        assert_eq!(registry.verify(spec), Ok(()));
+
+        // Line endings converted as in a Windows checkout don't change the checksum.
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace('\n', "\r\n")).unwrap();
+        assert_eq!(registry.verify(spec), Ok(()));
+
+        // Replace the first word.
+        fs::write(
+            &path,
+            content.replacen(|c: char| c.is_ascii_lowercase(), "q", 1),
+        )
+        .unwrap();
+        assert!(registry.verify(spec).unwrap_err().contains("checksum"));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,261 @@
+use crate::*;
+
+/// Options for `LetterTrie::to_dot_with_options()`, which describes a trie as a Graphviz graph.
+pub struct DotOptions {
+    /// The number of levels to include below the root of the graph. A value of 0 shows only the root. It can't be
+    /// more than `DEBUG_TRIE_MAX_DEPTH`.
+    pub max_depth: usize,
+    /// The most children to show for any one node, taken in alphabetical order. The rest are summarized in a
+    /// single "+n more" node. It can't be more than `DEBUG_TRIE_MAX_CHILDREN`.
+    pub max_children: usize,
+    /// If true, each node shows the number of words at or below it and its height from `FixedNode`.
+    pub show_counts: bool,
+}
+
+impl DotOptions {
+    /// Create a set of options that include as much of the trie as the debugging limits allow, without counts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let opt = DotOptions::make_default();
+    /// assert_eq!(opt.max_depth, DEBUG_TRIE_MAX_DEPTH);
+    /// ```
+    pub fn make_default() -> Self {
+        Self::make_limits(DEBUG_TRIE_MAX_DEPTH, DEBUG_TRIE_MAX_CHILDREN)
+    }
+
+    /// Create a set of options with the given limits and without counts.
+    pub fn make_limits(max_depth: usize, max_children: usize) -> Self {
+        Self {
+            max_depth,
+            max_children,
+            show_counts: false,
+        }
+    }
+
+    /// Create a set of options with the given limits where each node also shows its word count and height.
+    pub fn make_with_counts(max_depth: usize, max_children: usize) -> Self {
+        Self {
+            max_depth,
+            max_children,
+            show_counts: true,
+        }
+    }
+}
+
+// Receives the nodes of a trie as one of the trie implementations walks them, so that each kind of export only has
+// to be written once. A node is passed to add_node() before its children and add_edge() is called once a child and
+// everything below it have been added.
+pub(crate) trait GraphWriter {
+    // The number of children to visit below a node at this depth, counting from zero at the root of the graph. The
+    // children are visited in alphabetical order.
+    fn children_to_show(&self, depth: usize, child_count: usize) -> usize;
+
+    // Add a node and return its ID, or return None to leave it and everything below it out of the graph.
+    fn add_node(&mut self, prefix: &str, node: &FixedNode, depth: usize) -> Option<usize>;
+
+    fn add_edge(&mut self, parent_id: usize, child_id: usize, c: char);
+
+    // If false the FixedNode passed to add_node() doesn't have the node count, word count and height, which would
+    // otherwise take a walk through everything below each node unless the trie is frozen.
+    fn needs_counts(&self) -> bool {
+        true
+    }
+}
+
+// A FixedNode with only the fields that can be filled in without looking below the node, for writers that don't need
+// the counts. The prefix is left out as well since the writers are given it separately.
+pub(crate) fn shallow_fixed_node(
+    c: char,
+    depth: usize,
+    is_word: bool,
+    child_count: usize,
+) -> FixedNode {
+    FixedNode {
+        c,
+        prefix: String::new(),
+        depth,
+        is_word,
+        child_count,
+        node_count: 0,
+        word_count: 0,
+        height: 0,
+        surface_forms: vec![],
+    }
+}
+
+// Builds the text of a DOT graph.
+pub(crate) struct DotWriter {
+    s: String,
+    next_id: usize,
+    max_depth: usize,
+    max_children: usize,
+    show_counts: bool,
+}
+
+impl DotWriter {
+    pub(crate) fn new(opt: &DotOptions) -> Self {
+        let mut s = String::new();
+        s.push_str("digraph trie {\n");
+        s.push_str("    node [shape=box, style=rounded, fontname=\"Helvetica\"];\n");
+        s.push_str("    edge [fontname=\"Helvetica\", fontsize=10];\n");
+        Self {
+            s,
+            next_id: 0,
+            max_depth: opt.max_depth.min(DEBUG_TRIE_MAX_DEPTH),
+            max_children: opt.max_children.min(DEBUG_TRIE_MAX_CHILDREN),
+            show_counts: opt.show_counts,
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.s.push_str("}\n");
+        self.s
+    }
+
+    fn new_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+impl GraphWriter for DotWriter {
+    fn children_to_show(&self, depth: usize, child_count: usize) -> usize {
+        if depth < self.max_depth {
+            child_count.min(self.max_children)
+        } else {
+            0
+        }
+    }
+
+    // Each node is labeled with its prefix. If some of its children won't be visited because of the limits, they're
+    // represented by a single extra node.
+    fn add_node(&mut self, prefix: &str, node: &FixedNode, depth: usize) -> Option<usize> {
+        let id = self.new_id();
+        let mut label = if prefix.is_empty() {
+            "(root)".to_owned()
+        } else {
+            prefix.to_owned()
+        };
+        if self.show_counts {
+            label.push_str(&format!(
+                "\\n{} words, height {}",
+                format_count(node.word_count),
+                node.height
+            ));
+        }
+        let style = if node.is_word {
+            ", style=\"rounded,filled,bold\", fillcolor=\"lightblue\""
+        } else {
+            ""
+        };
+        self.s.push_str(&format!(
+            "    n{} [label=\"{}\"{}];\n",
+            id,
+            escape_label(&label),
+            style
+        ));
+
+        let hidden_count = node.child_count - self.children_to_show(depth, node.child_count);
+        if hidden_count > 0 {
+            let more_id = self.new_id();
+            self.s.push_str(&format!(
+                "    n{} [label=\"+{} more\", shape=plaintext];\n",
+                more_id,
+                format_count(hidden_count)
+            ));
+            self.s
+                .push_str(&format!("    n{} -> n{} [style=dashed];\n", id, more_id));
+        }
+        Some(id)
+    }
+
+    fn add_edge(&mut self, parent_id: usize, child_id: usize, c: char) {
+        self.s.push_str(&format!(
+            "    n{} -> n{} [label=\"{}\"];\n",
+            parent_id,
+            child_id,
+            escape_label(&c.to_string())
+        ));
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_tries() -> (BaseLetterTrie, NoParentLetterTrie, LetterTrieSet) {
+        let dataset = Dataset::TestSmallUnsorted;
+        (
+            BaseLetterTrie::from_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &LoadMethod::Continuous,
+            ),
+            NoParentLetterTrie::from_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &LoadMethod::Continuous,
+            ),
+            LetterTrieSet::from_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &LoadMethod::Continuous,
+            ),
+        )
+    }
+
+    #[test]
+    fn same_graph_for_each_type() {
+        let (base, no_parent, map) = small_tries();
+        let opt = DotOptions::make_with_counts(3, 2);
+        let dot = map.to_dot_with_options("cr", &opt).unwrap();
+        assert_eq!(base.to_dot_with_options("cr", &opt).unwrap(), dot);
+        assert_eq!(no_parent.to_dot_with_options("cr", &opt).unwrap(), dot);
+        let dot = map.to_dot("", 2, 1).unwrap();
+        assert_eq!(base.to_dot("", 2, 1).unwrap(), dot);
+        assert_eq!(no_parent.to_dot("", 2, 1).unwrap(), dot);
+        assert!(map.to_dot("x", 2, 2).is_none());
+        assert!(base.to_dot("x", 2, 2).is_none());
+        assert!(no_parent.to_dot("x", 2, 2).is_none());
+    }
+
+    #[test]
+    fn graph() {
+        let (_, _, map) = small_tries();
+        let dot = map
+            .to_dot("an", DEBUG_TRIE_MAX_DEPTH, DEBUG_TRIE_MAX_CHILDREN)
+            .unwrap();
+        assert_eq!(
+            dot,
+            "digraph trie {
+    node [shape=box, style=rounded, fontname=\"Helvetica\"];
+    edge [fontname=\"Helvetica\", fontsize=10];
+    n0 [label=\"an\", style=\"rounded,filled,bold\", fillcolor=\"lightblue\"];
+    n1 [label=\"and\", style=\"rounded,filled,bold\", fillcolor=\"lightblue\"];
+    n0 -> n1 [label=\"d\"];
+}
+"
+        );
+
+        let dot = map
+            .to_dot_with_options("cro", &DotOptions::make_with_counts(2, 1))
+            .unwrap();
+        assert!(dot.contains("n0 [label=\"cro\\n2 words, height 5\"];"));
+        assert!(dot.contains("n2 -> n3 [style=dashed];"));
+        assert!(dot.contains("n3 [label=\"+1 more\", shape=plaintext];"));
+
+        // The whole trie starts from an unlabeled root.
+        let dot = map.to_dot("", 0, 0).unwrap();
+        assert!(dot.contains("n0 [label=\"(root)\"];"));
+        assert!(dot.contains("n1 [label=\"+2 more\", shape=plaintext];"));
+    }
+}
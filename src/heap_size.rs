@@ -0,0 +1,186 @@
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+use crate::*;
+
+// The most entries in one node of the standard library's BTreeMap.
+const BTREE_CAPACITY: usize = 11;
+// The entries per node, counting the one moved up to the parent, that a BTreeMap settles at as it grows by splitting
+// full nodes. A node that's just been split has 5 or 6 entries and one that's about to split has 11.
+const BTREE_AVERAGE_FILL: usize = 7;
+
+/// An estimate of the memory a trie uses on the heap, from `LetterTrie::heap_size()`. The struct that holds the
+/// root isn't counted unless it's on the heap itself, as it is for `BaseLetterTrie`.
+///
+/// The sizes of the node structs and `Rc` allocations are exact. Those of `BTreeMap` nodes are estimated from the
+/// layout of the standard library's B-tree and the way its nodes fill as they split, since the actual number of
+/// nodes depends on the order of the insertions. Memory the allocator adds to each allocation isn't included.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let base = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+/// let no_parent = NoParentLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+///
+/// // The Rc and RefCell around each of BaseLetterTrie's nodes take space that NoParentLetterTrie doesn't need.
+/// assert!(base.heap_size().rc_bytes > 0);
+/// assert_eq!(no_parent.heap_size().rc_bytes, 0);
+/// println!("{:.1} bytes per word", base.heap_size().bytes_per_word());
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct HeapSize {
+    /// The node structs themselves, whether they're in their own allocation or stored inline in a `BTreeMap`.
+    pub node_bytes: usize,
+    /// The `BTreeMap` nodes that hold the links to the children, not counting space taken by the node structs
+    /// stored inline in them. This includes the unused slots in each `BTreeMap` node.
+    pub map_bytes: usize,
+    /// The reference counts around each node and the borrow flag and padding added by `RefCell`.
+    pub rc_bytes: usize,
+    /// Strings and other buffers such as surface forms and the normalizer's settings.
+    pub string_bytes: usize,
+    pub node_count: usize,
+    pub word_count: usize,
+}
+
+impl HeapSize {
+    pub fn total_bytes(&self) -> usize {
+        self.node_bytes + self.map_bytes + self.rc_bytes + self.string_bytes
+    }
+
+    /// The total bytes divided by the number of words, or 0.0 for a trie with no words.
+    pub fn bytes_per_word(&self) -> f64 {
+        bytes_per(self.total_bytes(), self.word_count)
+    }
+
+    /// The total bytes divided by the number of nodes, or 0.0 if no nodes were counted.
+    pub fn bytes_per_node(&self) -> f64 {
+        bytes_per(self.total_bytes(), self.node_count)
+    }
+
+    // Add the bytes, nodes and words from a companion trie such as a suffix index, which don't add to the words
+    // or nodes of the trie itself.
+    pub(crate) fn add_bytes(&mut self, other: &HeapSize) {
+        self.node_bytes += other.node_bytes;
+        self.map_bytes += other.map_bytes;
+        self.rc_bytes += other.rc_bytes;
+        self.string_bytes += other.string_bytes;
+    }
+
+    // Add a node's BTreeMap of children. If the children are stored inline in the map, their structs are counted
+    // as node bytes rather than map bytes.
+    pub(crate) fn add_children<K, V>(&mut self, len: usize, is_inline: bool) {
+        let map_bytes = btree_map_heap_size::<K, V>(len);
+        if is_inline {
+            let inline_node_bytes = len * mem::size_of::<V>();
+            self.node_bytes += inline_node_bytes;
+            self.map_bytes += map_bytes - inline_node_bytes;
+        } else {
+            self.map_bytes += map_bytes;
+        }
+    }
+
+    // Add a node that has its own Rc<RefCell<T>> allocation.
+    pub(crate) fn add_rc_ref_cell<T>(&mut self) {
+        let rc_box_bytes = 2 * mem::size_of::<usize>() + mem::size_of::<RefCell<T>>();
+        debug_assert_eq!(mem::size_of::<Rc<RefCell<T>>>(), mem::size_of::<usize>());
+        self.node_bytes += mem::size_of::<T>();
+        self.rc_bytes += rc_box_bytes - mem::size_of::<T>();
+    }
+
+    pub(crate) fn add_normalizer(&mut self, normalizer: &Normalizer) {
+        self.string_bytes += btree_map_heap_size::<char, String>(normalizer.mappings.len());
+        for mapping in normalizer.mappings.values() {
+            self.string_bytes += mapping.capacity();
+        }
+        if let Some(whitelist) = &normalizer.whitelist {
+            self.string_bytes += btree_set_heap_size::<char>(whitelist.len());
+        }
+    }
+}
+
+// Round up to a multiple of the alignment.
+fn round_up(size: usize, align: usize) -> usize {
+    size.div_ceil(align) * align
+}
+
+/// An estimate of the heap memory used by the nodes of a `BTreeMap` with this many entries, not counting anything
+/// the keys or values point to.
+pub fn btree_map_heap_size<K, V>(len: usize) -> usize {
+    if len == 0 {
+        // An empty BTreeMap doesn't allocate.
+        return 0;
+    }
+    let pointer_bytes = mem::size_of::<usize>();
+    let align = mem::align_of::<K>()
+        .max(mem::align_of::<V>())
+        .max(pointer_bytes);
+    // A leaf has a pointer to its parent, two u16 fields for its index in the parent and its length, and room for
+    // the keys and values. An internal node adds pointers to its children.
+    let leaf_bytes = round_up(
+        pointer_bytes
+            + 2 * mem::size_of::<u16>()
+            + BTREE_CAPACITY * (mem::size_of::<K>() + mem::size_of::<V>()),
+        align,
+    );
+    let internal_bytes = leaf_bytes + (BTREE_CAPACITY + 1) * pointer_bytes;
+
+    let leaf_count = if len <= BTREE_CAPACITY {
+        1
+    } else {
+        (len + BTREE_AVERAGE_FILL) / BTREE_AVERAGE_FILL
+    };
+    let mut internal_count = 0;
+    let mut level_count = leaf_count;
+    while level_count > 1 {
+        level_count = level_count.div_ceil(BTREE_AVERAGE_FILL);
+        internal_count += level_count;
+    }
+    leaf_count * leaf_bytes + internal_count * internal_bytes
+}
+
+/// An estimate of the heap memory used by the nodes of a `BTreeSet` with this many entries.
+pub fn btree_set_heap_size<K>(len: usize) -> usize {
+    btree_map_heap_size::<K, ()>(len)
+}
+
+fn bytes_per(bytes: usize, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        bytes as f64 / count as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn btree_map() {
+        assert_eq!(btree_map_heap_size::<char, u64>(0), 0);
+        let leaf_bytes = btree_map_heap_size::<char, u64>(1);
+        assert_eq!(leaf_bytes, btree_map_heap_size::<char, u64>(BTREE_CAPACITY));
+        assert_eq!(leaf_bytes, 8 + 2 + 2 + 11 * (4 + 8));
+        // One split gives two leaves and an internal node above them.
+        assert_eq!(
+            btree_map_heap_size::<char, u64>(12),
+            3 * leaf_bytes + 12 * 8
+        );
+    }
+
+    #[test]
+    fn bytes_per_empty_trie() {
+        let h = LetterTrieSet::new().heap_size();
+        assert_eq!(h.word_count, 0);
+        assert_eq!(h.bytes_per_word(), 0.0);
+        assert_eq!(HeapSize::default().bytes_per_node(), 0.0);
+
+        let h = BaseLetterTrie::new().heap_size();
+        assert!(h.bytes_per_node() > 0.0);
+        assert_eq!(h.bytes_per_word(), 0.0);
+    }
+}
@@ -1,5 +1,6 @@
 // Enables the use of Weak::strong_count() and Weak::weak_count().
 #![feature(weak_counts)]
+#![allow(stable_features)]
 #![allow(clippy::new_without_default)]
 #![feature(test)]
 
@@ -13,22 +14,68 @@ use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::sync::Mutex;
+use std::str::FromStr;
 use std::time::Instant;
 
+pub mod aho_corasick;
+pub use aho_corasick::{AhoCorasick, MatchKind, WordMatch};
 pub mod base_letter_trie;
-pub use base_letter_trie::BaseLetterTrie;
+pub use base_letter_trie::{BaseLetterTrie, BaseTrie};
+pub mod build_metrics;
+pub use build_metrics::{BuildMetrics, InsertCounts, StepTime, ThreadMetrics};
+pub mod build_report;
+pub use build_report::{BuildReport, BUILD_REPORT_CSV_HEADER};
+pub mod context_trie;
+pub use context_trie::ContextTrie;
+pub mod dataset_registry;
+pub use dataset_registry::{
+    DatasetRecipe, DatasetRegistry, DatasetSpec, DATASET_REGISTRY, DATASET_SPECS,
+};
+pub mod d3;
+use d3::D3Writer;
+pub use d3::{d3_html_page, D3Options};
+pub mod dot;
+pub use dot::DotOptions;
+use dot::{shallow_fixed_node, DotWriter, GraphWriter};
+pub mod heap_size;
+pub use heap_size::{btree_map_heap_size, btree_set_heap_size, HeapSize};
+pub mod lint;
+pub use lint::{lint_word_file, LintIssue, LintKind, LintPolicy, LintReport, DEFAULT_MAX_WORD_LEN};
+pub mod trie_map;
+pub use trie_map::{
+    ByteTrieMap, Frequency, KeyElement, LetterTrieMap, LetterTrieSet, ToKeyElements, TokenTrieMap,
+    TrieMap,
+};
 pub mod no_parent_letter_trie;
-pub use no_parent_letter_trie::NoParentLetterTrie;
+pub use no_parent_letter_trie::{NoParentLetterTrie, NoParentTrie};
+pub mod normalizer;
+pub use normalizer::{InvalidWord, InvalidWordAction, Normalizer, UnicodeForm, DEFAULT_NORMALIZER};
 pub mod util;
 pub use util::*;
+pub mod repl;
+pub use repl::{ReplResponse, TrieExplorer, REPL_COMMANDS};
+pub mod sampling;
+pub mod saved_trie;
+pub mod segmenter;
+pub use segmenter::{Segment, SegmentStrategy, Segmenter};
+pub mod sequence_model;
+pub use sequence_model::{DepthStats, SequenceModel, Transition};
+pub mod substring_index;
+pub use substring_index::SubstringIndex;
 pub mod text_util;
 pub use text_util::*;
+pub mod trie_stats;
+use trie_stats::StatsWriter;
+pub use trie_stats::{TrieStats, TRIE_STATS_TOP_COUNT};
+pub mod trie_validation;
+pub use trie_validation::{TrieViolation, TrieViolationKind};
+pub mod word_games;
+pub use word_games::{WILDCARD_ANY, WILDCARD_ONE};
 
-const USE_CHAR_GET_COUNTER: bool = false;
-
-const DEBUG_TRIE_MAX_DEPTH: usize = 1000;
-const DEBUG_TRIE_MAX_CHILDREN: usize = 1000;
+/// The most levels of a trie shown by debugging output such as `describe_deep()` and `to_dot()`.
+pub const DEBUG_TRIE_MAX_DEPTH: usize = 1000;
+/// The most children of any one node shown by debugging output such as `describe_deep()` and `to_dot()`.
+pub const DEBUG_TRIE_MAX_CHILDREN: usize = 1000;
 
 const WORD_COUNT_SMALL: usize = 10;
 const WORD_COUNT_MEDIUM: usize = 20_000;
@@ -45,12 +92,13 @@ const FILENAME_LARGE_UNSORTED: &str = "fake_words_400_000_unsorted.txt";
 const FILENAME_GOOD_WORDS: &str = "test_good_words.txt";
 const FILENAME_NON_WORDS: &str = "test_non_words.txt";
 
-const LABEL_STEP_OVERALL: &str = "overall load";
-const LABEL_STEP_READ_FILE: &str = "read file";
-const LABEL_STEP_MAKE_VECTOR: &str = "make_vector";
-const LABEL_STEP_SORT_VECTOR: &str = "sort_vector";
-const LABEL_STEP_LOAD_FROM_VEC: &str = "load from vector";
-const LABEL_STEP_READ_AND_VECTOR: &str = "make vector from file";
+// The steps of a load, as printed with the elapsed times and found in `BuildMetrics::steps`.
+pub const LABEL_STEP_OVERALL: &str = "overall load";
+pub const LABEL_STEP_READ_FILE: &str = "read file";
+pub const LABEL_STEP_MAKE_VECTOR: &str = "make_vector";
+pub const LABEL_STEP_SORT_VECTOR: &str = "sort_vector";
+pub const LABEL_STEP_LOAD_FROM_VEC: &str = "load from vector";
+pub const LABEL_STEP_READ_AND_VECTOR: &str = "make vector from file";
 
 /// A letter trie (https://www.geeksforgeeks.org/trie-insert-and-search) with implementations that use different
 /// approaches for parent and child links but otherwise work the same.
@@ -84,12 +132,16 @@ pub trait LetterTrie {
     /// - `is_sorted` is incorrectly set to `true`.
     /// - The load method uses an optimization that relies on the words being sorted by their first letter. Currently the only such load method is `LoadMethod::ContinuousParallel`.
     ///
+    /// None of these problems are reported. Use `from_file_validated()` to check the file first.
+    ///
     /// # Panics
     ///
     /// Panics if the file does not exist or can't be opened for reading.
     fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self;
 
-    /// Create a trie from words in a text file, optionally displaying elapsed time for each step.
+    /// Create a trie from words in a text file, optionally displaying elapsed time for each step, and return a
+    /// report on the load with the time taken by each step and the counts of words, duplicates, blank lines and
+    /// rejected lines. See `BuildReport`.
     ///
     /// The text file may contain up to one word per line. The words may be upper- or lowercase and
     /// blank lines and whitespace before or after the words will be ignored. Duplicate words will also be
@@ -113,14 +165,291 @@ pub trait LetterTrie {
         load_method: &LoadMethod,
         opt: &DisplayDetailOptions,
         expected_word_count: Option<usize>,
-    ) -> Self;
+    ) -> (Self, BuildReport)
+    where
+        Self: Sized,
+    {
+        Self::from_file_report(
+            filename,
+            is_sorted,
+            load_method,
+            opt,
+            expected_word_count,
+            &DEFAULT_NORMALIZER,
+        )
+    }
+
+    /// Create a trie from words in a text file using the given normalizer in place of the default
+    /// `trim().to_lowercase()`, optionally displaying elapsed time for each step.
+    ///
+    /// The trie keeps a copy of the normalizer and applies it to every word or prefix that's added or looked up
+    /// later. Lines that the normalizer rejects are left out of the trie and don't count toward
+    /// `expected_word_count`.
+    ///
+    /// # Errors
+    ///
+    /// As with `from_file_test()` this may crash or produce an incorrect trie if `is_sorted` is incorrectly set to
+    /// `true`. Here the words must be sorted by their first letter after normalization.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = BaseLetterTrie::from_file_normalized(
+    ///     &dataset.filename(),
+    ///     dataset.is_sorted(),
+    ///     &LoadMethod::Continuous,
+    ///     &DisplayDetailOptions::make_no_display(),
+    ///     None,
+    ///     &Normalizer::make_exact(),
+    /// );
+    ///
+    /// // Without case folding, lookups are case-sensitive.
+    /// assert!(trie.find("azure").is_some());
+    /// assert!(trie.find("Azure").is_none());
+    /// ```
+    fn from_file_normalized(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        normalizer: &Normalizer,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_file_report(
+            filename,
+            is_sorted,
+            load_method,
+            opt,
+            expected_word_count,
+            normalizer,
+        )
+        .0
+    }
+
+    /// Create a trie as with `from_file_normalized()` and also return the report from `from_file_test()`.
+    ///
+    /// The hit, miss, node and duplicate counts in `BuildReport::metrics` are only collected when
+    /// `DisplayDetailOptions::count_inserts` is set, since counting adds work at every node visited. Without it the
+    /// build takes as long as with the other `from_file` functions.
+    fn from_file_report(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        normalizer: &Normalizer,
+    ) -> (Self, BuildReport)
+    where
+        Self: Sized;
+
+    /// Check a word file with `lint_word_file()` and, unless the problems found include a kind that the policy
+    /// fails on, create a trie from it as with `from_file_report()`. This reports the duplicates, lines with more
+    /// than one word and unsorted words that the other `from_file` functions pass over in silence.
+    ///
+    /// The lint report comes back either way, with the trie if the build went ahead or as the error if it didn't.
+    /// Since the file is read once for the check and again for the build, this takes longer than the other
+    /// `from_file` functions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallSorted;
+    /// let (trie, _build_report, lint_report) = LetterTrieSet::from_file_validated(
+    ///     dataset.filename(),
+    ///     dataset.is_sorted(),
+    ///     &LoadMethod::ContinuousParallel,
+    ///     &DisplayDetailOptions::make_no_display(),
+    ///     &DEFAULT_NORMALIZER,
+    ///     &LintPolicy::make_strict(),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(lint_report.is_clean());
+    /// assert!(trie.find("creature").is_some());
+    /// ```
+    fn from_file_validated(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        normalizer: &Normalizer,
+        policy: &LintPolicy,
+    ) -> Result<(Self, BuildReport, LintReport), LintReport>
+    where
+        Self: Sized,
+    {
+        let lint_report = lint_word_file(filename, is_sorted, normalizer, policy);
+        if lint_report.fails(policy) {
+            return Err(lint_report);
+        }
+        let (t, build_report) =
+            Self::from_file_report(filename, is_sorted, load_method, opt, None, normalizer);
+        Ok((t, build_report, lint_report))
+    }
 
     /// Given a word or a partial word, find the corresponding node in the trie if it exists.
     fn find(&self, prefix: &str) -> Option<FixedNode>;
 
+    /// Find every word that's a prefix of a string, from shortest to longest. The string itself is included if
+    /// it's a word, and the string is normalized the same way as the words in the trie.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = NoParentLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    /// assert_eq!(trie.all_prefix_words("Andy"), vec!["a", "an", "and"]);
+    /// assert_eq!(trie.longest_prefix_word("crossing"), Some("cross".to_owned()));
+    /// ```
+    fn all_prefix_words(&self, s: &str) -> Vec<String>;
+
+    /// Find the longest word that's a prefix of a string, which may be the string itself.
+    fn longest_prefix_word(&self, s: &str) -> Option<String> {
+        self.all_prefix_words(s).pop()
+    }
+
+    /// Start keeping a companion trie with every word in reverse so that `words_with_suffix()` and
+    /// `words_with_prefix_and_suffix()` don't have to check every word. The companion trie is kept in sync as words
+    /// are added, removed or merged in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let mut trie = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    /// trie.enable_suffix_index();
+    /// assert!(trie.has_suffix_index());
+    /// trie.add_word("tossed");
+    /// assert_eq!(LetterTrie::words_with_suffix(&trie, "ossed"), vec!["crossed", "tossed"]);
+    /// assert_eq!(
+    ///     LetterTrie::words_with_prefix_and_suffix(&trie, "cr", "e"),
+    ///     vec!["crease", "creative", "creature"]
+    /// );
+    /// ```
+    fn enable_suffix_index(&mut self);
+
+    /// Stop keeping the companion trie of reversed words.
+    fn disable_suffix_index(&mut self);
+
+    /// Returns true if the trie is keeping a companion trie of reversed words.
+    fn has_suffix_index(&self) -> bool;
+
+    /// Find the words that end with a suffix, in alphabetical order. The suffix itself is included if it's a word.
+    fn words_with_suffix(&self, suffix: &str) -> Vec<String>;
+
+    /// Find the words that start with a prefix and end with a suffix, in alphabetical order. The prefix and suffix
+    /// may overlap.
+    fn words_with_prefix_and_suffix(&self, prefix: &str, suffix: &str) -> Vec<String>;
+
     /// For testing or debugging, create a FixedNode from the root node of a trie.
     fn to_fixed_node(&self) -> FixedNode;
 
+    /// Describe the trie as a Graphviz graph in the DOT language, starting from the node for `root_prefix` or from
+    /// the root if it's empty. Returns None if no word starts with `root_prefix`.
+    ///
+    /// Each node is labeled with its prefix and each edge with the letter it adds. Nodes that complete a word are
+    /// filled and bold. Below the root of the graph at most `max_depth` levels are shown, with at most
+    /// `max_children` children for each node, and the limits can't go past `DEBUG_TRIE_MAX_DEPTH` and
+    /// `DEBUG_TRIE_MAX_CHILDREN`. Children that are left out are summarized in a "+n more" node. To render the
+    /// graph, save it to a file and run something like `dot -Tpng trie.dot -o trie.png`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    ///
+    /// let dot = trie.to_dot("cre", 2, 5).unwrap();
+    /// assert!(dot.starts_with("digraph trie {"));
+    /// assert!(dot.contains("[label=\"crea\"]"));
+    /// assert!(trie.to_dot("xyz", 2, 5).is_none());
+    /// ```
+    fn to_dot(&self, root_prefix: &str, max_depth: usize, max_children: usize) -> Option<String> {
+        self.to_dot_with_options(
+            root_prefix,
+            &DotOptions::make_limits(max_depth, max_children),
+        )
+    }
+
+    /// Describe the trie as a Graphviz graph as with `to_dot()`, with the limits and annotations given by the
+    /// options. With `DotOptions::make_with_counts()` each node also shows its word count and height.
+    fn to_dot_with_options(&self, root_prefix: &str, opt: &DotOptions) -> Option<String>;
+
+    /// Describe the trie, starting from the node for `root_prefix` or from the root if it's empty, as nested JSON
+    /// objects with the fields `name`, `is_word`, `word_count`, `height` and, for nodes that have them, `children`.
+    /// This is the form that D3's `d3.hierarchy()` takes, and `d3_html_page()` makes a page to view it. Returns None
+    /// if no word starts with `root_prefix`.
+    ///
+    /// The root is named for its prefix and every other node for its letter. The options limit the depth and leave
+    /// out small subtrees so that a large trie can be shown.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = NoParentLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    ///
+    /// let hierarchy = trie.to_d3_hierarchy("cro", &D3Options::make(1, 0)).unwrap();
+    /// assert_eq!(hierarchy["name"], "cro");
+    /// assert_eq!(hierarchy["word_count"], 2);
+    /// assert_eq!(hierarchy["children"][0]["name"], "s");
+    /// ```
+    fn to_d3_hierarchy(&self, root_prefix: &str, opt: &D3Options) -> Option<serde_json::Value>;
+
+    /// Gather a report on the shape of the whole trie in a single pass through its nodes. See `TrieStats`.
+    fn stats(&self) -> TrieStats;
+
+    /// Estimate the memory the trie uses on the heap, broken down into node structs, `BTreeMap` nodes, `Rc` and
+    /// `RefCell` overhead and strings. See `HeapSize`.
+    fn heap_size(&self) -> HeapSize;
+
+    /// Check the whole trie against the rules that its nodes should always keep and return every violation found,
+    /// or an empty list if there are none. See `TrieViolationKind` for the rules. This visits every node so it's
+    /// meant for tests and debugging, for instance after each change in a test that adds and removes words.
+    ///
+    /// The reference counts in a `BaseLetterTrie` are only as expected when no iterator over the trie is alive,
+    /// since an iterator holds its own reference to a node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallSorted;
+    /// let mut trie = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    /// assert_eq!(trie.validate(), vec![]);
+    ///
+    /// trie.freeze();
+    /// for violation in trie.validate() {
+    ///     println!("{}", violation);
+    /// }
+    /// ```
+    fn validate(&self) -> Vec<TrieViolation>;
+
     /// Print one line of information about the root node of a trie.
     ///
     /// This includes things like the number of nodes and words in the trie and the maximum height.
@@ -140,7 +469,7 @@ pub trait LetterTrie {
 ///
 /// Whether the words are sorted in the collection may affect the speed of loading the trie depending on the
 /// chosen LoadMethod but the resulting trie will be identical either way.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum Dataset {
     /// Small file with nine sorted English words leading to a trie with 26 nodes and a maximum height of 9.
     TestSmallSorted,
@@ -157,7 +486,8 @@ pub enum Dataset {
 }
 
 impl Dataset {
-    /// Get the path to a file with a set of words for testing.
+    /// Get the path to a file with a set of words for testing. The small and medium files are checked in, while the
+    /// large files are generated the first time they're needed and cached (see DatasetRegistry).
     ///
     /// # Examples
     ///
@@ -169,14 +499,14 @@ impl Dataset {
     /// let filename: &str = Dataset::TestMediumSorted.filename();
     /// ```
     pub fn filename(&self) -> &str {
-        match self {
+        DATASET_REGISTRY.path(match self {
             Dataset::TestSmallSorted => FILENAME_SMALL_SORTED,
             Dataset::TestSmallUnsorted => FILENAME_SMALL_UNSORTED,
             Dataset::TestMediumSorted => FILENAME_MEDIUM_SORTED,
             Dataset::TestMediumUnsorted => FILENAME_MEDIUM_UNSORTED,
             Dataset::TestLargeSorted => FILENAME_LARGE_SORTED,
             Dataset::TestLargeUnsorted => FILENAME_LARGE_UNSORTED,
-        }
+        })
     }
 
     /// Returns true if the dataset is supposed to be already in alphabetical order at least by the first character
@@ -210,19 +540,80 @@ impl Dataset {
             Dataset::TestLargeSorted | Dataset::TestLargeUnsorted => WORD_COUNT_LARGE,
         }
     }
+
+    /// Every dataset from smallest to largest.
+    pub const ALL: [Dataset; 6] = [
+        Dataset::TestSmallSorted,
+        Dataset::TestSmallUnsorted,
+        Dataset::TestMediumSorted,
+        Dataset::TestMediumUnsorted,
+        Dataset::TestLargeSorted,
+        Dataset::TestLargeUnsorted,
+    ];
+
+    /// The short name used on the command line, such as "medium-sorted".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dataset::TestSmallSorted => "small-sorted",
+            Dataset::TestSmallUnsorted => "small-unsorted",
+            Dataset::TestMediumSorted => "medium-sorted",
+            Dataset::TestMediumUnsorted => "medium-unsorted",
+            Dataset::TestLargeSorted => "large-sorted",
+            Dataset::TestLargeUnsorted => "large-unsorted",
+        }
+    }
+}
+
+impl FromStr for Dataset {
+    type Err = String;
+
+    /// Parse the short name returned by `name()`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &Self::ALL, Self::name, "dataset")
+    }
 }
 
 /// The choice of implementation of LetterTrie.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum LetterTrieType {
     /// The baseline implementation using Rc<RefCell<Node>> for child links and Weak<RefCell<Node>> for parent links.
     Base,
     /// A stripped-down implementation with no parent links and with direct ownership of child nodes.
     NoParent,
+    /// A map from each word to a value with direct ownership of child nodes, used here with no values as
+    /// LetterTrieMap<()>.
+    Map,
+}
+
+impl LetterTrieType {
+    /// Every implementation.
+    pub const ALL: [LetterTrieType; 3] = [
+        LetterTrieType::Base,
+        LetterTrieType::NoParent,
+        LetterTrieType::Map,
+    ];
+
+    /// The short name used on the command line, such as "no-parent".
+    pub fn name(&self) -> &'static str {
+        match self {
+            LetterTrieType::Base => "base",
+            LetterTrieType::NoParent => "no-parent",
+            LetterTrieType::Map => "map",
+        }
+    }
+}
+
+impl FromStr for LetterTrieType {
+    type Err = String;
+
+    /// Parse the short name returned by `name()`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &Self::ALL, Self::name, "trie type")
+    }
 }
 
 /// The method the LetterTrie will use to load words from a text file.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LoadMethod {
     /// Read the whole file into memory, create a vector of words, then fill the trie.
     ReadVecFill,
@@ -238,6 +629,64 @@ pub enum LoadMethod {
     ContinuousParallel,
 }
 
+impl LoadMethod {
+    /// Every load method.
+    pub const ALL: [LoadMethod; 4] = [
+        LoadMethod::ReadVecFill,
+        LoadMethod::VecFill,
+        LoadMethod::Continuous,
+        LoadMethod::ContinuousParallel,
+    ];
+
+    /// The short name used on the command line, such as "continuous-parallel".
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let load_method: LoadMethod = "Continuous-Parallel".parse().unwrap();
+    /// assert_eq!(load_method, LoadMethod::ContinuousParallel);
+    /// assert_eq!(load_method.name(), "continuous-parallel");
+    /// assert!("fast".parse::<LoadMethod>().is_err());
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoadMethod::ReadVecFill => "read-vec-fill",
+            LoadMethod::VecFill => "vec-fill",
+            LoadMethod::Continuous => "continuous",
+            LoadMethod::ContinuousParallel => "continuous-parallel",
+        }
+    }
+}
+
+impl FromStr for LoadMethod {
+    type Err = String;
+
+    /// Parse the short name returned by `name()`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_name(s, &Self::ALL, Self::name, "load method")
+    }
+}
+
+// Find the choice with a given short name, ignoring case.
+fn parse_name<T: Copy>(
+    s: &str,
+    all: &[T],
+    name: fn(&T) -> &'static str,
+    kind: &str,
+) -> Result<T, String> {
+    match all.iter().find(|x| name(x).eq_ignore_ascii_case(s.trim())) {
+        Some(x) => Ok(*x),
+        None => Err(format!(
+            "Unknown {} \"{}\". Expected one of: {}.",
+            kind,
+            s,
+            all.iter().map(name).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
 /// Options for the amount of detail to display while building a trie.
 pub struct DisplayDetailOptions {
     /// If true, print the elapsed time for the whole trie build including reading the file.
@@ -252,6 +701,10 @@ pub struct DisplayDetailOptions {
     /// The label to be displayed with any debugging information. One easy way to create this string is with a
     /// call to `DisplayDetailOptions::get_test_label()`.
     pub label: String,
+    /// If true, count the hits, misses, new nodes and duplicates while adding words and return them in
+    /// `BuildReport::metrics`. This adds work at every node visited, so it's off unless asked for, in which case
+    /// `BuildMetrics::is_counted` is false and the counts are left at zero.
+    pub count_inserts: bool,
 }
 
 impl DisplayDetailOptions {
@@ -270,6 +723,7 @@ impl DisplayDetailOptions {
             print_step_time: false,
             object_detail_level: 0,
             label: "".to_owned(),
+            count_inserts: false,
         }
     }
 
@@ -288,7 +742,7 @@ impl DisplayDetailOptions {
     ///     &load_method,
     ///     &letter_trie_type);
     ///
-    /// let mut trie: BaseLetterTrie = BaseLetterTrie::from_file_test(
+    /// let (trie, report) = BaseLetterTrie::from_file_test(
     ///     &dataset.filename(),
     ///     dataset.is_sorted(),
     ///     &load_method,
@@ -304,7 +758,8 @@ impl DisplayDetailOptions {
             print_overall_time: true,
             print_step_time: false,
             object_detail_level: 0,
-            label: Self::get_test_label(&dataset, &load_method, &letter_trie_type),
+            label: Self::get_test_label(dataset, load_method, letter_trie_type),
+            count_inserts: false,
         }
     }
 
@@ -325,7 +780,7 @@ impl DisplayDetailOptions {
     ///     &load_method,
     ///     &letter_trie_type);
     ///
-    /// let mut trie: BaseLetterTrie = BaseLetterTrie::from_file_test(
+    /// let (trie, report) = BaseLetterTrie::from_file_test(
     ///     &dataset.filename(),
     ///     dataset.is_sorted(),
     ///     &load_method,
@@ -344,7 +799,8 @@ impl DisplayDetailOptions {
                 Dataset::TestSmallSorted | Dataset::TestSmallUnsorted => 2,
                 _ => 1,
             },
-            label: Self::get_test_label(&dataset, &load_method, &letter_trie_type),
+            label: Self::get_test_label(dataset, load_method, letter_trie_type),
+            count_inserts: false,
         }
     }
 
@@ -412,9 +868,10 @@ impl DisplayDetailOptions {
 ///         depth: 0,
 ///         is_word: false,
 ///         child_count: 25,
-///         node_count: 1_083_388,
+///         node_count: 1_081_648,
 ///         word_count: 400_000,
 ///         height: 17,
+///         surface_forms: vec![],
 ///     }
 /// );
 /// ```
@@ -468,80 +925,9 @@ pub struct FixedNode {
     pub node_count: usize,
     pub word_count: usize,
     pub height: usize,
-}
-
-//
-lazy_static! {
-    static ref CHAR_GET_COUNTER: Mutex<CharGetCounter> = Mutex::new(CharGetCounter {
-        hit_count: 0,
-        miss_count: 0
-    });
-}
-
-/// A counter to keep track of the node hits and misses while building a trie from a list of words.
-/// - hit = Starting from a given node, we found a child node corresponding to the next letter of the word.
-/// - miss = We didn't find such a child node and thus created one.
-///
-/// These results can influence how we go about speeding up the build. In the large word list with 584,983 words
-/// leading to 1,143,413 nodes we get a hit about 82% of the time.
-#[derive(Debug)]
-pub struct CharGetCounter {
-    hit_count: usize,
-    miss_count: usize,
-}
-
-impl CharGetCounter {
-    /// Set the counters to zero at the start of a trie build.
-    pub fn reset() {
-        let mut counter = CHAR_GET_COUNTER.lock().unwrap();
-        counter.hit_count = 0;
-        counter.miss_count = 0;
-    }
-
-    /// Record a single hit or miss.
-    pub fn record(is_hit: bool) {
-        let mut counter = CHAR_GET_COUNTER.lock().unwrap();
-        if is_hit {
-            counter.hit_count += 1;
-        } else {
-            counter.miss_count += 1;
-        }
-    }
-
-    /// View the results.
-    pub fn print() {
-        let counter = CHAR_GET_COUNTER.lock().unwrap();
-        let total_count = counter.hit_count + counter.miss_count;
-        if total_count == 0 {
-            println!("CharGetCounter: nothing recorded");
-        } else {
-            let hit_pct = counter.hit_count as f64 / total_count as f64;
-            println!(
-                "CharGetCounter: hit count = {}; miss count = {}, hit pct = {}",
-                format_count(counter.hit_count),
-                format_count(counter.miss_count),
-                hit_pct
-            );
-        }
-    }
-
-    /// View the results only if we have some results.
-    ///
-    /// This allows us to turn counting on or off for a particular build process without the calling code
-    /// having to know whether it's enabled.
-    pub fn print_optional() {
-        let total_count: usize;
-        {
-            // Lock the counter and get the total count in a separate scope so that the counter is unlocked
-            // before we call Self::print(). If we didn't do this, we'd still have a lock on CHAR_GET_COUNTER
-            // when calling Self::print(). That function would try to get a lock and wait forever.
-            let counter = CHAR_GET_COUNTER.lock().unwrap();
-            total_count = counter.hit_count + counter.miss_count;
-        }
-        if total_count > 0 {
-            Self::print();
-        }
-    }
+    /// The original spellings of the word at this node when the trie preserves surface forms (see
+    /// `Normalizer::preserve_surface_forms`), otherwise empty.
+    pub surface_forms: Vec<String>,
 }
 
 /// For testing, create a vector of 1,000 words that are known to be in the large word list.
@@ -552,11 +938,16 @@ impl CharGetCounter {
 /// - LetterTrie::find() which should return Some(FixedNode).
 /// - LetterTrie::is_word() which should return true.
 ///
+/// The file is generated along with the large dataset if it's missing.
+///
 /// # Panics
 ///
-/// Panics if the file does not exist or can't be opened for reading.
+/// Panics if the file can't be generated or read.
 pub fn good_words() -> Vec<String> {
-    words_from_file_test(FILENAME_GOOD_WORDS, Some(WORD_COUNT_GOOD))
+    words_from_file_test(
+        DATASET_REGISTRY.path(FILENAME_GOOD_WORDS),
+        Some(WORD_COUNT_GOOD),
+    )
 }
 
 /// For testing, create a vector of 1,000 words that are known NOT to be in the large word list.
@@ -567,16 +958,21 @@ pub fn good_words() -> Vec<String> {
 /// - LetterTrie::find() which should return None.
 /// - LetterTrie::is_word() which should return false.
 ///
+/// The file is generated along with the large dataset if it's missing.
+///
 /// # Panics
 ///
-/// Panics if the file does not exist or can't be opened for reading.
+/// Panics if the file can't be generated or read.
 pub fn non_words() -> Vec<String> {
-    words_from_file_test(FILENAME_NON_WORDS, Some(WORD_COUNT_NON))
+    words_from_file_test(
+        DATASET_REGISTRY.path(FILENAME_NON_WORDS),
+        Some(WORD_COUNT_NON),
+    )
 }
 
 /// For testing, create a HashSet containing all of the words in the large dataset.
 ///
-/// This is the list of 584,983 non-English words corresponding to Dataset::TestLargeSorted or
+/// This is the list of 400,000 non-English words corresponding to Dataset::TestLargeSorted or
 /// Dataset::TestLargeUnsorted.
 ///
 /// We can use this to create a baseline benchmark fer finding our 1,000 known good words and our 1,000 known
@@ -591,7 +987,7 @@ pub fn non_words() -> Vec<String> {
 ///
 /// # Panics
 ///
-/// Panics if the file for the Dataset::TestLargeSorted dataset does not exist or can't be opened for reading.
+/// Panics if the file for the Dataset::TestLargeSorted dataset can't be generated or read.
 pub fn large_dataset_words_hash_set() -> HashSet<String> {
     let mut hash_set = HashSet::new();
     for word in words_from_file_test(Dataset::TestLargeSorted.filename(), Some(WORD_COUNT_LARGE)) {
@@ -604,24 +1000,26 @@ pub fn large_dataset_words_hash_set() -> HashSet<String> {
 }
 
 /// Given a filename, create a Vec<Vec<char>> which is the most convenient starting point for building a trie
-/// from a list of words. This assumes that there is at most one word per line in the file.
+/// from a list of words. This assumes that there is at most one word per line in the file. Each word is passed
+/// through the normalizer and any word that it rejects is left out. The lines and the time taken are counted in
+/// `report`.
 pub fn make_vec_char_test(
     filename: &str,
     opt: &DisplayDetailOptions,
     expected_word_count: Option<usize>,
+    normalizer: &Normalizer,
+    report: &mut BuildReport,
 ) -> Vec<Vec<char>> {
     let start = Instant::now();
     let file = File::open(filename).unwrap();
     let mut v: Vec<Vec<char>> = vec![];
     for line in BufReader::new(file).lines() {
         let line = line.unwrap();
-        let line = line.trim();
-        if !line.is_empty() {
-            let vec_char: Vec<char> = line.to_lowercase().chars().collect();
+        if let Some(vec_char) = report.load_line(normalizer, &line) {
             v.push(vec_char);
         }
     }
-    print_elapsed_from_start(
+    report.metrics.record_elapsed(
         opt.print_step_time,
         &opt.label,
         LABEL_STEP_READ_AND_VECTOR,
@@ -669,6 +1067,7 @@ pub fn assert_small_root(node: &FixedNode) {
             node_count: 28,
             word_count: 10,
             height: 9,
+            surface_forms: vec![],
         }
     );
 }
@@ -700,9 +1099,10 @@ pub fn assert_large_root(node: &FixedNode) {
             depth: 0,
             is_word: false,
             child_count: 25,
-            node_count: 1_083_388,
+            node_count: dataset_registry::LARGE_NODE_COUNT,
             word_count: 400_000,
             height: 17,
+            surface_forms: vec![],
         }
     );
 }
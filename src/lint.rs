@@ -0,0 +1,402 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use serde_json::{json, Value};
+
+use crate::*;
+
+/// The longest word that `LintPolicy::make_default()` allows, in characters after normalization.
+pub const DEFAULT_MAX_WORD_LEN: usize = 32;
+
+/// A kind of problem that `lint_word_file()` looks for in a word list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintKind {
+    /// The word is the same after normalization as one on an earlier line. Only the first one is added.
+    Duplicate,
+    /// The line has whitespace between its characters so it's probably more than one word. The trie gets a single
+    /// word with the whitespace in it.
+    Whitespace,
+    /// The word has a character that's not a letter, such as a digit, a hyphen or an apostrophe.
+    NonLetter,
+    /// The word is longer than the policy's `max_word_len`.
+    Overlong,
+    /// The word starts with a letter that comes before the first letter of the word above it, although the list
+    /// was said to be sorted. `LoadMethod::ContinuousParallel` builds an incorrect trie from such a list.
+    Unsorted,
+}
+
+impl LintKind {
+    pub const ALL: [LintKind; 5] = [
+        LintKind::Duplicate,
+        LintKind::Whitespace,
+        LintKind::NonLetter,
+        LintKind::Overlong,
+        LintKind::Unsorted,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintKind::Duplicate => "duplicate",
+            LintKind::Whitespace => "whitespace",
+            LintKind::NonLetter => "non-letter",
+            LintKind::Overlong => "overlong",
+            LintKind::Unsorted => "unsorted",
+        }
+    }
+}
+
+/// One problem found on one line of a word list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintIssue {
+    /// The line number in the file, starting from one.
+    pub line_number: usize,
+    pub kind: LintKind,
+    /// The line with the whitespace at either end trimmed.
+    pub line: String,
+    /// For a duplicate, the line where the word first appeared. For an unsorted word, the line of the word above
+    /// it.
+    pub other_line_number: Option<usize>,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} \"{}\"",
+            self.line_number,
+            self.kind.name(),
+            self.line
+        )?;
+        match (self.kind, self.other_line_number) {
+            (LintKind::Duplicate, Some(other)) => write!(f, " (first on line {})", other),
+            (LintKind::Unsorted, Some(other)) => write!(f, " (after line {})", other),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What `lint_word_file()` checks and which kinds of problem make `LetterTrie::from_file_validated()` fail.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintPolicy {
+    pub max_word_len: usize,
+    pub fail_on: BTreeSet<LintKind>,
+}
+
+impl LintPolicy {
+    /// Fail on the problems that the `LetterTrie::from_file()` documentation says lead to an incorrect trie, which
+    /// are lines with more than one word and a list that's said to be sorted but isn't. The others are reported.
+    pub fn make_default() -> Self {
+        Self {
+            max_word_len: DEFAULT_MAX_WORD_LEN,
+            fail_on: [LintKind::Whitespace, LintKind::Unsorted]
+                .iter()
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Fail on every kind of problem.
+    pub fn make_strict() -> Self {
+        Self {
+            max_word_len: DEFAULT_MAX_WORD_LEN,
+            fail_on: LintKind::ALL.iter().cloned().collect(),
+        }
+    }
+
+    /// Report problems but never fail.
+    pub fn make_report_only() -> Self {
+        Self {
+            max_word_len: DEFAULT_MAX_WORD_LEN,
+            fail_on: BTreeSet::new(),
+        }
+    }
+}
+
+/// The problems found in a word list by `lint_word_file()`, in the order of the lines they're on.
+///
+/// The report can be printed with `{}` or turned into JSON with `to_json()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let filename = std::env::temp_dir().join("letter_trie_lint_doc.txt");
+/// std::fs::write(&filename, "apple\nbanana split\napple\n").unwrap();
+/// let filename = filename.to_str().unwrap();
+///
+/// let report = lint_word_file(filename, false, &DEFAULT_NORMALIZER, &LintPolicy::make_default());
+/// assert_eq!(report.count(LintKind::Duplicate), 1);
+/// assert_eq!(report.issues[0].line_number, 2);
+/// // A line with two words fails the default policy.
+/// assert!(report.fails(&LintPolicy::make_default()));
+/// assert!(!report.fails(&LintPolicy::make_report_only()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LintReport {
+    pub line_count: usize,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn count(&self, kind: LintKind) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.kind == kind)
+            .count()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns true if any of the problems is of a kind that the policy fails on.
+    pub fn fails(&self, policy: &LintPolicy) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| policy.fail_on.contains(&issue.kind))
+    }
+
+    pub fn to_json(&self) -> Value {
+        let counts: serde_json::Map<String, Value> = LintKind::ALL
+            .iter()
+            .map(|kind| (kind.name().to_owned(), json!(self.count(*kind))))
+            .collect();
+        let issues: Vec<Value> = self
+            .issues
+            .iter()
+            .map(|issue| {
+                json!({
+                    "line_number": issue.line_number,
+                    "kind": issue.kind.name(),
+                    "line": issue.line,
+                    "other_line_number": issue.other_line_number,
+                })
+            })
+            .collect();
+        json!({
+            "line_count": self.line_count,
+            "counts": counts,
+            "issues": issues,
+        })
+    }
+}
+
+impl fmt::Display for LintReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in self.issues.iter() {
+            writeln!(f, "{}", issue)?;
+        }
+        let counts: Vec<String> = LintKind::ALL
+            .iter()
+            .map(|kind| format!("{}: {}", kind.name(), format_count(self.count(*kind))))
+            .collect();
+        writeln!(
+            f,
+            "{} lines; {}",
+            format_count(self.line_count),
+            counts.join("; ")
+        )
+    }
+}
+
+/// Check a word list for duplicate words, lines with more than one word, characters that aren't letters, words
+/// longer than the policy allows and, if `is_sorted` is true, words that are out of order by their first letter.
+/// The words are compared after they're passed through the normalizer, as they would be when loading a trie, and
+/// blank lines are skipped.
+///
+/// # Panics
+///
+/// Panics if the file does not exist or can't be opened for reading.
+pub fn lint_word_file(
+    filename: &str,
+    is_sorted: bool,
+    normalizer: &Normalizer,
+    policy: &LintPolicy,
+) -> LintReport {
+    let mut report = LintReport::default();
+    // The line where each word first appeared.
+    let mut first_line_numbers: HashMap<String, usize> = HashMap::new();
+    // The first letter and line number of the last word, for checking the order.
+    let mut prev_word: Option<(char, usize)> = None;
+    let file = File::open(filename).unwrap();
+    for (line_index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.unwrap();
+        let line_number = line_index + 1;
+        report.line_count += 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        // A word that the normalizer rejects won't be in the trie, but it's still checked for the other problems.
+        let (word, is_valid) = match normalizer.normalize(trimmed) {
+            Ok(word) => (word, true),
+            Err(invalid_word) => (invalid_word.word, false),
+        };
+        let mut add_issue = |kind: LintKind, other_line_number: Option<usize>| {
+            report.issues.push(LintIssue {
+                line_number,
+                kind,
+                line: trimmed.to_owned(),
+                other_line_number,
+            });
+        };
+        if is_valid {
+            match first_line_numbers.get(&word) {
+                Some(first_line_number) => {
+                    add_issue(LintKind::Duplicate, Some(*first_line_number));
+                }
+                None => {
+                    first_line_numbers.insert(word.clone(), line_number);
+                }
+            }
+        }
+        if trimmed.chars().any(char::is_whitespace) {
+            add_issue(LintKind::Whitespace, None);
+        }
+        if word
+            .chars()
+            .any(|c| !c.is_alphabetic() && !c.is_whitespace())
+        {
+            add_issue(LintKind::NonLetter, None);
+        }
+        if word.chars().count() > policy.max_word_len {
+            add_issue(LintKind::Overlong, None);
+        }
+        // A line such as a lone combining mark can normalize to nothing, leaving no first letter to compare.
+        if is_sorted && is_valid && !word.is_empty() {
+            let first_char = word.chars().next().unwrap();
+            if let Some((prev_first_char, prev_line_number)) = prev_word {
+                if first_char < prev_first_char {
+                    add_issue(LintKind::Unsorted, Some(prev_line_number));
+                }
+            }
+            prev_word = Some((first_char, line_number));
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn lint(name: &str, content: &str, is_sorted: bool) -> LintReport {
+        let filename = env::temp_dir().join(format!("letter_trie_lint_test_{}.txt", name));
+        fs::write(&filename, content).unwrap();
+        let report = lint_word_file(
+            filename.to_str().unwrap(),
+            is_sorted,
+            &DEFAULT_NORMALIZER,
+            &LintPolicy::make_default(),
+        );
+        fs::remove_file(filename).unwrap();
+        report
+    }
+
+    #[test]
+    fn clean_datasets() {
+        for dataset in [Dataset::TestSmallSorted, Dataset::TestMediumSorted].iter() {
+            let report = lint_word_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &DEFAULT_NORMALIZER,
+                &LintPolicy::make_strict(),
+            );
+            assert!(report.is_clean(), "{}", report);
+        }
+    }
+
+    #[test]
+    fn each_kind() {
+        let report = lint(
+            "each_kind",
+            "Apple\n\nbanana split\napple\ncan't\nzebra\ncoconut\nsupercalifragilisticexpialidocious-ish\n",
+            true,
+        );
+        assert_eq!(report.line_count, 8);
+        let found: Vec<(usize, LintKind, Option<usize>)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.line_number, issue.kind, issue.other_line_number))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (3, LintKind::Whitespace, None),
+                (4, LintKind::Duplicate, Some(1)),
+                (4, LintKind::Unsorted, Some(3)),
+                (5, LintKind::NonLetter, None),
+                (7, LintKind::Unsorted, Some(6)),
+                (8, LintKind::NonLetter, None),
+                (8, LintKind::Overlong, None),
+            ]
+        );
+        assert_eq!(report.issues[1].line, "apple");
+        assert!(report.fails(&LintPolicy::make_default()));
+    }
+
+    #[test]
+    fn unsorted_only_when_claimed() {
+        let content = "banana\napple\n";
+        assert_eq!(
+            lint("unsorted_claimed", content, true).count(LintKind::Unsorted),
+            1
+        );
+        assert!(lint("unsorted_not_claimed", content, false).is_clean());
+    }
+
+    #[test]
+    fn empty_after_normalizing() {
+        let filename = env::temp_dir().join("letter_trie_lint_test_empty_after_normalizing.txt");
+        fs::write(&filename, "apple\n\u{301}\nbanana\n").unwrap();
+        let mut normalizer = Normalizer::make_default();
+        normalizer.strip_diacritics = true;
+        let report = lint_word_file(
+            filename.to_str().unwrap(),
+            true,
+            &normalizer,
+            &LintPolicy::make_default(),
+        );
+        fs::remove_file(filename).unwrap();
+        assert_eq!(report.line_count, 3);
+        assert_eq!(report.count(LintKind::Unsorted), 0);
+    }
+
+    #[test]
+    fn validated_load() {
+        let filename = env::temp_dir().join("letter_trie_lint_test_validated_load.txt");
+        fs::write(&filename, "apple\nbanana\napple\n").unwrap();
+        let filename = filename.to_str().unwrap();
+        let mut opt = DisplayDetailOptions::make_no_display();
+        opt.count_inserts = true;
+
+        let (t, build_report, lint_report) = BaseLetterTrie::from_file_validated(
+            filename,
+            false,
+            &LoadMethod::Continuous,
+            &opt,
+            &DEFAULT_NORMALIZER,
+            &LintPolicy::make_default(),
+        )
+        .unwrap();
+        assert_eq!(t.to_fixed_node().word_count, 2);
+        assert_eq!(build_report.metrics.counts.duplicate_count, 1);
+        assert_eq!(lint_report.count(LintKind::Duplicate), 1);
+
+        let result = LetterTrieSet::from_file_validated(
+            filename,
+            false,
+            &LoadMethod::Continuous,
+            &opt,
+            &DEFAULT_NORMALIZER,
+            &LintPolicy::make_strict(),
+        );
+        assert_eq!(result.err().unwrap().issues.len(), 1);
+        fs::remove_file(filename).unwrap();
+    }
+}
@@ -1,357 +1,1229 @@
-#![allow(dead_code)]
-#![allow(unused_variables)]
-#![allow(unused_imports)]
-#![allow(unused_assignments)]
-// #![allow(unused_mut)]
-
+use clap::{value_t, values_t, App, AppSettings, Arg, ArgMatches, SubCommand};
 use letter_trie::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use std::collections::BTreeMap;
-use std::fs;
-use std::fs::File;
+use rand::SeedableRng;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{self, File};
 use std::io::Write;
-use std::iter::FromIterator;
-use std::mem;
+use std::path::Path;
+use std::process;
+use std::rc::Rc;
+use std::time::Instant;
 
-extern crate typename;
+const DEFAULT_LIMIT: &str = "50";
+const DEFAULT_HISTORY_FILENAME: &str = ".letter_trie_history";
+const FILENAME_ENGLISH_3_000: &str = "english_words_3_000.txt";
 
-const USE_CHAR_GET_COUNTER: bool = false;
+// How to print the results of a command.
+#[derive(Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
 
-const WORD_COUNT_SMALL: usize = 10;
-const WORD_COUNT_MEDIUM: usize = 20_000;
-const WORD_COUNT_LARGE: usize = 400_000;
-const WORD_COUNT_GOOD: usize = 1_000;
-const WORD_COUNT_NON: usize = 1_000;
+// How to print the results of the bench command, which can also be a CSV table.
+#[derive(Clone, Copy, PartialEq)]
+enum BenchFormat {
+    Text,
+    Json,
+    Csv,
+}
 
-const FILENAME_MEDIUM_SORTED: &str = "fake_words_20_000_sorted.txt";
-const FILENAME_MEDIUM_UNSORTED: &str = "fake_words_20_000_unsorted.txt";
-const FILENAME_LARGE_SORTED: &str = "fake_words_400_000_sorted.txt";
-const FILENAME_LARGE_UNSORTED: &str = "fake_words_400_000_unsorted.txt";
-const FILENAME_GOOD_WORDS: &str = "test_good_words.txt";
-const FILENAME_NON_WORDS: &str = "test_non_words.txt";
-const FILENAME_ENGLISH_3_000: &str = "english_words_3_000.txt";
-const FILENAME_ENGLISH_30: &str = "C:\\Data\\Text\\English words 30.txt";
-const FILENAME_ENGLISH_5: &str = "C:\\Data\\Text\\English words 5.txt";
+// A trie of any of the types that can be chosen with --type.
+enum AnyLetterTrie {
+    Base(BaseLetterTrie),
+    NoParent(NoParentLetterTrie),
+    Map(LetterTrieSet),
+}
+
+impl AnyLetterTrie {
+    fn find(&self, prefix: &str) -> Option<FixedNode> {
+        match self {
+            AnyLetterTrie::Base(t) => t.find(prefix),
+            AnyLetterTrie::NoParent(t) => t.find(prefix),
+            AnyLetterTrie::Map(t) => t.find(prefix),
+        }
+    }
+
+    fn to_dot_with_options(&self, root_prefix: &str, opt: &DotOptions) -> Option<String> {
+        match self {
+            AnyLetterTrie::Base(t) => t.to_dot_with_options(root_prefix, opt),
+            AnyLetterTrie::NoParent(t) => t.to_dot_with_options(root_prefix, opt),
+            AnyLetterTrie::Map(t) => t.to_dot_with_options(root_prefix, opt),
+        }
+    }
 
-const LABEL_FREEZE: &str = "freeze";
-const LABEL_UNFREEZE: &str = "unfreeze";
-const LABEL_PRINT_ROOT: &str = "print root";
+    fn to_d3_hierarchy(&self, root_prefix: &str, opt: &D3Options) -> Option<Value> {
+        match self {
+            AnyLetterTrie::Base(t) => t.to_d3_hierarchy(root_prefix, opt),
+            AnyLetterTrie::NoParent(t) => t.to_d3_hierarchy(root_prefix, opt),
+            AnyLetterTrie::Map(t) => t.to_d3_hierarchy(root_prefix, opt),
+        }
+    }
+
+    fn stats(&self) -> TrieStats {
+        match self {
+            AnyLetterTrie::Base(t) => t.stats(),
+            AnyLetterTrie::NoParent(t) => t.stats(),
+            AnyLetterTrie::Map(t) => t.stats(),
+        }
+    }
+}
 
 fn main() {
-    println!("\nLetter Trie 5\n");
-
-    // let all_datasets = vec![Dataset::TestSmallSorted, Dataset::TestSmallUnsorted,
-    //	Dataset::TestMediumSorted, Dataset::TestMediumUnsorted,
-    //	Dataset::TestLargeSorted, Dataset::TestLargeUnsorted];
-    let all_datasets = vec![Dataset::TestLargeSorted, Dataset::TestLargeUnsorted];
-    // let all_methods = vec![LoadMethod::ReadVecFill, LoadMethod::VecFill, LoadMethod::Continuous, LoadMethod::ContinuousParallel];
-    let all_methods = vec![LoadMethod::Continuous, LoadMethod::ContinuousParallel];
-    // let all_types = vec![LetterTrieType::Base, LetterTrieType::NoParent, LetterTrieType::MinStruct];
-    let all_types = vec![LetterTrieType::Base];
-
-    // try_combinations(&all_datasets, &all_methods, &all_types);
-    // display_small_trie();
-    // try_large_trie();
-
-    // create_all_shuffled_files(&all_sizes);
-    // try_freeze();
-    // try_find_loop();
-    // try_find_loop_from_iterator();
-    // try_find_loop_like_iterator();
-    create_find_files();
-    // try_load_words();
-    // print_node_counts();
-    // make_test_files(&FILENAME_ENGLISH_3_000, 3_000, 3);
-    // make_test_files(&FILENAME_ENGLISH_30, 5);
-    examine_generated_sequences(&FILENAME_ENGLISH_3_000, 3_000, 3);
-}
-
-fn examine_generated_sequences(
-    source_filename: &str,
-    expected_source_word_count: usize,
-    max_depth: usize,
-) {
-    let example_sequences = words_from_file(&source_filename);
-    assert_eq!(example_sequences.len(), expected_source_word_count);
+    let matches = make_app().get_matches();
+    match matches.subcommand() {
+        ("build", Some(m)) => build(m),
+        ("lint", Some(m)) => lint(m),
+        ("lookup", Some(m)) => lookup(m),
+        ("complete", Some(m)) => complete(m),
+        ("fuzzy", Some(m)) => fuzzy(m),
+        ("pattern", Some(m)) => pattern(m),
+        ("anagram", Some(m)) => anagram(m),
+        ("stats", Some(m)) => stats(m),
+        ("repl", Some(m)) => repl(m),
+        ("dot", Some(m)) => dot(m),
+        ("d3", Some(m)) => d3(m),
+        ("generate", Some(m)) => generate(m),
+        ("shuffle", Some(m)) => shuffle(m),
+        ("bench", Some(m)) => bench(m),
+        ("bench-generators", Some(m)) => compare_word_generators(m),
+        ("substring-index", Some(m)) => try_substring_index(m),
+        ("datasets", Some(_)) => materialize_datasets(),
+        _ => unreachable!(),
+    }
+}
+
+fn make_app() -> App<'static, 'static> {
+    App::new("letter_trie")
+        .about("Build, query and benchmark letter tries.")
+        .after_help(
+            "INPUT can be a word list with one word per line, a trie saved by the build \
+             command, or the name of a test dataset such as medium-sorted.",
+        )
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .subcommand(
+            SubCommand::with_name("build")
+                .about("Load a word list and save it as a trie that loads faster")
+                .arg(input_arg())
+                .arg(
+                    Arg::with_name("output")
+                        .required(true)
+                        .help("The file to save the trie to"),
+                )
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(
+                    Arg::with_name("validate")
+                        .long("validate")
+                        .help("Check the word list first as lint does and stop if the default policy fails"),
+                )
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about(
+                    "Report duplicate words, lines with more than one word, non-letters, overlong words \
+                     and unsorted words in a word list",
+                )
+                .arg(input_arg())
+                .arg(sorted_arg())
+                .arg(
+                    Arg::with_name("max-len")
+                        .long("max-len")
+                        .takes_value(true)
+                        .default_value("32")
+                        .help("The longest word allowed"),
+                )
+                .arg(
+                    Arg::with_name("strict")
+                        .long("strict")
+                        .help("Fail on every kind of problem, not only whitespace and unsorted words"),
+                )
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("lookup")
+                .about("Look up words or prefixes and show the node for each one")
+                .arg(input_arg())
+                .arg(
+                    Arg::with_name("words")
+                        .required(true)
+                        .multiple(true)
+                        .help("The words or prefixes"),
+                )
+                .arg(type_arg())
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("complete")
+                .about("List the words that start with a prefix")
+                .arg(input_arg())
+                .arg(Arg::with_name("prefix").required(true))
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(limit_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("fuzzy")
+                .about("List the words within an edit distance of a word")
+                .arg(input_arg())
+                .arg(Arg::with_name("word").required(true))
+                .arg(
+                    Arg::with_name("distance")
+                        .long("distance")
+                        .short("d")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("The highest number of letters inserted, deleted or replaced"),
+                )
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(limit_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("pattern")
+                .about("List the words matching a pattern where ? is one letter and * is any run")
+                .arg(input_arg())
+                .arg(Arg::with_name("pattern").required(true))
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(limit_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("anagram")
+                .about("List the words that use exactly the given letters, where ? is a blank")
+                .arg(input_arg())
+                .arg(Arg::with_name("letters").required(true))
+                .arg(
+                    Arg::with_name("partial")
+                        .long("partial")
+                        .help("Also list words that use only some of the letters"),
+                )
+                .arg(
+                    Arg::with_name("min-length")
+                        .long("min-length")
+                        .takes_value(true)
+                        .default_value("2")
+                        .help("With --partial, the shortest word to list"),
+                )
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(limit_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Show the shape of a trie: branching, word lengths, letters by position and more")
+                .arg(input_arg())
+                .arg(type_arg())
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(format_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("repl")
+                .about("Load a trie once and explore it interactively with tab completion")
+                .arg(input_arg())
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(
+                    Arg::with_name("history")
+                        .long("history")
+                        .takes_value(true)
+                        .default_value(DEFAULT_HISTORY_FILENAME)
+                        .help("The file that keeps the command history between sessions"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("dot")
+                .about("Write a trie or part of it as a Graphviz graph")
+                .arg(input_arg())
+                .arg(prefix_arg())
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("The number of levels to show below the start of the graph"),
+                )
+                .arg(
+                    Arg::with_name("max-children")
+                        .long("max-children")
+                        .takes_value(true)
+                        .default_value("26")
+                        .help("The most children to show for each node"),
+                )
+                .arg(
+                    Arg::with_name("counts")
+                        .long("counts")
+                        .help("Show the word count and height of each node"),
+                )
+                .arg(type_arg())
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("d3")
+                .about("Write a trie or part of it as a D3 hierarchy in JSON or as an HTML page to explore")
+                .arg(input_arg())
+                .arg(prefix_arg())
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .takes_value(true)
+                        .default_value("4")
+                        .help("The number of levels to include below the start of the hierarchy"),
+                )
+                .arg(
+                    Arg::with_name("min-words")
+                        .long("min-words")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Leave out the subtrees with fewer words than this"),
+                )
+                .arg(
+                    Arg::with_name("html")
+                        .long("html")
+                        .help("Write a self-contained HTML page instead of the JSON"),
+                )
+                .arg(type_arg())
+                .arg(load_method_arg())
+                .arg(sorted_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generate fake words that follow the letter patterns of a word list")
+                .arg(
+                    Arg::with_name("source")
+                        .required(true)
+                        .help("A word list of real words, such as english_words_3_000.txt"),
+                )
+                .arg(
+                    Arg::with_name("count")
+                        .long("count")
+                        .short("n")
+                        .takes_value(true)
+                        .default_value("1000"),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .long("depth")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("The number of letters to look back when choosing the next letter"),
+                )
+                .arg(seed_arg())
+                .arg(
+                    Arg::with_name("min-length")
+                        .long("min-length")
+                        .takes_value(true)
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::with_name("max-length")
+                        .long("max-length")
+                        .takes_value(true)
+                        .default_value("16"),
+                )
+                .arg(
+                    Arg::with_name("exclude-examples")
+                        .long("exclude-examples")
+                        .help("Leave out words that are in the source list"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("shuffle")
+                .about("Write the lines of a file in random order")
+                .arg(input_arg())
+                .arg(Arg::with_name("output").required(true))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("bench")
+                .about("Time every combination of dataset, load method and trie type and compare their memory use")
+                .arg(
+                    Arg::with_name("dataset")
+                        .long("dataset")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .possible_values(&names(&Dataset::ALL, Dataset::name))
+                        .default_value("medium-unsorted"),
+                )
+                .arg(type_arg().multiple(true).number_of_values(1))
+                .arg(load_method_arg().multiple(true).number_of_values(1))
+                .arg(
+                    Arg::with_name("metrics")
+                        .long("metrics")
+                        .help(
+                            "Count the node hits and misses, new nodes and duplicates, which slows the \
+                             load a little, and show them with the work done by each thread",
+                        ),
+                )
+                .arg(format_arg().possible_value("csv")),
+        )
+        .subcommand(
+            SubCommand::with_name("bench-generators")
+                .about(
+                    "Compare the memory and speed of the two Markov models when generating as many distinct \
+                     words as the large dataset",
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .default_value(FILENAME_ENGLISH_3_000)
+                        .help("A word list of real words to learn the letter patterns from"),
+                )
+                .arg(
+                    Arg::with_name("depth")
+                        .long("depth")
+                        .takes_value(true)
+                        .default_value("3")
+                        .help("The number of letters to look back when choosing the next letter"),
+                )
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("substring-index")
+                .about("Time building a substring index and searching it, and show how much bigger it is than the trie")
+                .arg(
+                    Arg::with_name("dataset")
+                        .long("dataset")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .possible_values(&names(&Dataset::ALL, Dataset::name))
+                        .default_value("medium-unsorted"),
+                )
+                .arg(
+                    Arg::with_name("substring")
+                        .long("substring")
+                        .takes_value(true)
+                        .default_value("ght")
+                        .help("The substring to search for"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("datasets")
+                .about("Generate any missing test datasets and check their checksums"),
+        )
+}
 
-    let generated_words = generate_words(&example_sequences, 10, max_depth);
+fn input_arg() -> Arg<'static, 'static> {
+    Arg::with_name("input")
+        .required(true)
+        .help("The words to load")
 }
 
-fn make_test_files(source_filename: &str, expected_source_word_count: usize, max_depth: usize) {
-    let example_sequences = words_from_file(&source_filename);
-    assert_eq!(example_sequences.len(), expected_source_word_count);
+fn prefix_arg() -> Arg<'static, 'static> {
+    Arg::with_name("prefix")
+        .long("prefix")
+        .takes_value(true)
+        .default_value("")
+        .help("Start at the node for this prefix instead of the root")
+}
 
-    let mut words_large = generate_words(&example_sequences, WORD_COUNT_LARGE, max_depth);
-    assert_eq!(words_large.len(), WORD_COUNT_LARGE);
+fn type_arg() -> Arg<'static, 'static> {
+    Arg::with_name("type")
+        .long("type")
+        .short("t")
+        .takes_value(true)
+        .possible_values(&names(&LetterTrieType::ALL, LetterTrieType::name))
+        .default_value(LetterTrieType::Map.name())
+        .help("The trie implementation")
+}
 
-    let mut words_medium = Vec::from_iter(words_large[..WORD_COUNT_MEDIUM].iter().cloned());
-    assert_eq!(words_medium.len(), WORD_COUNT_MEDIUM);
+fn load_method_arg() -> Arg<'static, 'static> {
+    Arg::with_name("load-method")
+        .long("load-method")
+        .short("m")
+        .takes_value(true)
+        .possible_values(&names(&LoadMethod::ALL, LoadMethod::name))
+        .default_value(LoadMethod::Continuous.name())
+        .help("How to read the word list")
+}
 
-    file_from_lines(FILENAME_LARGE_UNSORTED, &words_large);
-    words_large.sort_unstable();
-    file_from_lines(FILENAME_LARGE_SORTED, &words_large);
+fn sorted_arg() -> Arg<'static, 'static> {
+    Arg::with_name("sorted")
+        .long("sorted")
+        .help("The word list is sorted, at least by the first letter, as continuous-parallel needs")
+}
 
-    file_from_lines(FILENAME_MEDIUM_UNSORTED, &words_medium);
-    words_medium.sort_unstable();
-    file_from_lines(FILENAME_MEDIUM_SORTED, &words_medium);
+fn format_arg() -> Arg<'static, 'static> {
+    Arg::with_name("format")
+        .long("format")
+        .short("f")
+        .takes_value(true)
+        .possible_values(&["text", "json"])
+        .default_value("text")
 }
 
-/*
-fn make_test_file(
-    source_filename: &str,
-    dest_sorted_filename: &str,
-    dest_unsorted_filename: &str,
-    target_word_count: usize,
-    max_depth: usize)
-{
-    let source_trie = BaseLetterTrie::from_file(
-        source_filename,
-        false,
-        &LoadMethod::Continuous,
-    );
+fn limit_arg() -> Arg<'static, 'static> {
+    Arg::with_name("limit")
+        .long("limit")
+        .short("l")
+        .takes_value(true)
+        .default_value(DEFAULT_LIMIT)
+        .help("The most words to list")
+}
+
+fn seed_arg() -> Arg<'static, 'static> {
+    Arg::with_name("seed")
+        .long("seed")
+        .takes_value(true)
+        .help("A seed for repeatable results")
+}
+
+fn output_arg() -> Arg<'static, 'static> {
+    Arg::with_name("output")
+        .long("output")
+        .short("o")
+        .takes_value(true)
+        .help("Write to this file instead of printing")
+}
+
+// The short names of every choice in one of the option enums.
+fn names<T>(all: &[T], name: fn(&T) -> &'static str) -> Vec<&'static str> {
+    all.iter().map(name).collect()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn output_format(m: &ArgMatches) -> OutputFormat {
+    match m.value_of("format") {
+        Some("json") => OutputFormat::Json,
+        _ => OutputFormat::Text,
+    }
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
+
+// The filename for INPUT and whether the words in it are sorted. A dataset name stands for the dataset's file.
+fn input_file(m: &ArgMatches) -> (String, bool) {
+    let input = m.value_of("input").unwrap();
+    let is_sorted = m.is_present("sorted");
+    if Path::new(input).exists() {
+        return (input.to_owned(), is_sorted);
+    }
+    match input.parse::<Dataset>() {
+        Ok(dataset) => (
+            dataset.filename().to_owned(),
+            is_sorted || dataset.is_sorted(),
+        ),
+        Err(_) => fail(&format!("\"{}\" is not a file or a dataset name.", input)),
+    }
+}
+
+fn load_method(m: &ArgMatches, is_sorted: bool) -> LoadMethod {
+    let load_method = value_t!(m, "load-method", LoadMethod).unwrap_or(LoadMethod::Continuous);
+    if load_method == LoadMethod::ContinuousParallel && !is_sorted {
+        fail("continuous-parallel needs a sorted word list. Add --sorted if the words are sorted.");
+    }
+    load_method
+}
+
+// Load INPUT as a LetterTrieSet, which is the type that supports the search commands.
+fn load_set(m: &ArgMatches) -> LetterTrieSet {
+    let (filename, is_sorted) = input_file(m);
+    if LetterTrieSet::is_saved_file(&filename) {
+        LetterTrieSet::from_saved_file(&filename)
+    } else {
+        LetterTrieSet::from_file(&filename, is_sorted, &load_method(m, is_sorted))
+    }
+}
+
+// Load INPUT as the type chosen with --type.
+fn load_trie(m: &ArgMatches) -> AnyLetterTrie {
+    let letter_trie_type = value_t!(m, "type", LetterTrieType).unwrap_or(LetterTrieType::Map);
+    let (filename, is_sorted) = input_file(m);
+    if LetterTrieSet::is_saved_file(&filename) {
+        match letter_trie_type {
+            LetterTrieType::Map => {
+                return AnyLetterTrie::Map(LetterTrieSet::from_saved_file(&filename))
+            }
+            _ => fail("A saved trie can only be loaded with --type map."),
+        }
+    }
+    let load_method = load_method(m, is_sorted);
+    match letter_trie_type {
+        LetterTrieType::Base => AnyLetterTrie::Base(BaseLetterTrie::from_file(
+            &filename,
+            is_sorted,
+            &load_method,
+        )),
+        LetterTrieType::NoParent => AnyLetterTrie::NoParent(NoParentLetterTrie::from_file(
+            &filename,
+            is_sorted,
+            &load_method,
+        )),
+        LetterTrieType::Map => {
+            AnyLetterTrie::Map(LetterTrieSet::from_file(&filename, is_sorted, &load_method))
+        }
+    }
+}
+
+fn fixed_node_json(node: &FixedNode) -> Value {
+    json!({
+        "prefix": node.prefix,
+        "depth": node.depth,
+        "is_word": node.is_word,
+        "child_count": node.child_count,
+        "node_count": node.node_count,
+        "word_count": node.word_count,
+        "height": node.height,
+    })
+}
+
+// Print a list of words, one per line as text or as a JSON object that also has the total number found.
+fn print_words(m: &ArgMatches, words: Vec<String>, extra: Value) {
+    let limit = value_t!(m, "limit", usize).unwrap_or_else(|e| e.exit());
+    let total = words.len();
+    let words: Vec<String> = words.into_iter().take(limit).collect();
+    match output_format(m) {
+        OutputFormat::Text => {
+            for word in words.iter() {
+                println!("{}", word);
+            }
+            if total > words.len() {
+                println!("... and {} more", format_count(total - words.len()));
+            }
+        }
+        OutputFormat::Json => {
+            let mut value = extra;
+            value["total"] = json!(total);
+            value["words"] = json!(words);
+            print_json(&value);
+        }
+    }
+}
+
+fn build(m: &ArgMatches) {
+    let output = m.value_of("output").unwrap();
+    if m.is_present("validate") {
+        let (filename, is_sorted) = input_file(m);
+        if !LetterTrieSet::is_saved_file(&filename) {
+            let policy = LintPolicy::make_default();
+            let report = lint_word_file(&filename, is_sorted, &DEFAULT_NORMALIZER, &policy);
+            if report.fails(&policy) {
+                eprint!("{}", report);
+                fail("The word list failed validation. Run lint for details.");
+            }
+        }
+    }
+    let start = Instant::now();
+    let t = load_set(m);
+    let load_time = start.elapsed();
+    let start = Instant::now();
+    t.save(output);
+    let save_time = start.elapsed();
+    match output_format(m) {
+        OutputFormat::Text => println!(
+            "Saved {} words ({} nodes) to \"{}\". Load = {:?}; save = {:?}.",
+            format_count(t.word_count()),
+            format_count(t.node_count()),
+            output,
+            load_time,
+            save_time
+        ),
+        OutputFormat::Json => print_json(&json!({
+            "output": output,
+            "word_count": t.word_count(),
+            "node_count": t.node_count(),
+            "load_ms": load_time.as_secs_f64() * 1_000.0,
+            "save_ms": save_time.as_secs_f64() * 1_000.0,
+        })),
+    }
+}
+
+fn lint(m: &ArgMatches) {
+    let (filename, is_sorted) = input_file(m);
+    let mut policy = if m.is_present("strict") {
+        LintPolicy::make_strict()
+    } else {
+        LintPolicy::make_default()
+    };
+    policy.max_word_len = value_t!(m, "max-len", usize).unwrap_or_else(|e| e.exit());
+    let report = lint_word_file(&filename, is_sorted, &DEFAULT_NORMALIZER, &policy);
+    let fails = report.fails(&policy);
+    match output_format(m) {
+        OutputFormat::Text => print!("{}", report),
+        OutputFormat::Json => {
+            let mut value = report.to_json();
+            value["fails"] = json!(fails);
+            print_json(&value);
+        }
+    }
+    if fails {
+        process::exit(1);
+    }
+}
 
-    let list = BTreeSet::new();
-    (0..target_word_count) {
-        let mut word = String::from(" ");
-        do {
-            let word_len = word.len()
-            let prefix: &str = if (word_len < max_depth) {
-                &word[..]
-            } else {
-                &word[(word_len - max_depth)..]
+fn lookup(m: &ArgMatches) {
+    let t = load_trie(m);
+    let words: Vec<&str> = m.values_of("words").unwrap().collect();
+    match output_format(m) {
+        OutputFormat::Text => {
+            for word in words {
+                match t.find(word) {
+                    Some(node) => println!(
+                        "{}: {}; {} words start with it; height {}",
+                        word,
+                        if node.is_word { "word" } else { "prefix only" },
+                        format_count(node.word_count),
+                        node.height
+                    ),
+                    None => println!("{}: not found", word),
+                }
             }
-            let current_node = source_trie.find(prefix);
-            let current_c = current_node.c;
-            let current_
         }
+        OutputFormat::Json => {
+            let results: Vec<Value> = words
+                .iter()
+                .map(|word| {
+                    json!({
+                        "query": word,
+                        "node": t.find(word).as_ref().map(fixed_node_json),
+                    })
+                })
+                .collect();
+            print_json(&json!(results));
+        }
+    }
+}
+
+fn complete(m: &ArgMatches) {
+    let t = load_set(m);
+    let prefix = m.value_of("prefix").unwrap();
+    let words = t.iter_prefix(prefix).map(|(word, _)| word).collect();
+    print_words(m, words, json!({ "prefix": prefix }));
+}
+
+fn fuzzy(m: &ArgMatches) {
+    let t = load_set(m);
+    let word = m.value_of("word").unwrap();
+    let max_distance = value_t!(m, "distance", usize).unwrap_or_else(|e| e.exit());
+    let matches = t.fuzzy_search(word, max_distance);
+    if output_format(m) == OutputFormat::Json {
+        let limit = value_t!(m, "limit", usize).unwrap_or_else(|e| e.exit());
+        let words: Vec<Value> = matches
+            .iter()
+            .take(limit)
+            .map(|(word, distance, _)| json!({ "word": word, "distance": distance }))
+            .collect();
+        print_json(&json!({ "word": word, "total": matches.len(), "words": words }));
+    } else {
+        let words = matches
+            .into_iter()
+            .map(|(word, distance, _)| format!("{}\t{}", word, distance))
+            .collect();
+        print_words(m, words, json!({}));
+    }
+}
+
+fn pattern(m: &ArgMatches) {
+    let t = load_set(m);
+    let pattern = m.value_of("pattern").unwrap();
+    let words = t
+        .words_matching(pattern)
+        .into_iter()
+        .map(|(word, _)| word)
+        .collect();
+    print_words(m, words, json!({ "pattern": pattern }));
+}
+
+fn anagram(m: &ArgMatches) {
+    let t = load_set(m);
+    let letters = m.value_of("letters").unwrap();
+    let matches = if m.is_present("partial") {
+        let min_length = value_t!(m, "min-length", usize).unwrap_or_else(|e| e.exit());
+        t.words_from_letters(letters, min_length)
+    } else {
+        t.anagrams(letters)
+    };
+    let words = matches.into_iter().map(|(word, _)| word).collect();
+    print_words(m, words, json!({ "letters": letters }));
+}
+
+fn stats(m: &ArgMatches) {
+    let start = Instant::now();
+    let t = load_trie(m);
+    let load_time = start.elapsed();
+    let start = Instant::now();
+    let stats = t.stats();
+    let stats_time = start.elapsed();
+    match output_format(m) {
+        OutputFormat::Text => {
+            print!("{}", stats);
+            println!("\nload time: {:?}; stats time: {:?}", load_time, stats_time);
+        }
+        OutputFormat::Json => {
+            let mut value = stats.to_json();
+            value["load_ms"] = json!(load_time.as_secs_f64() * 1_000.0);
+            value["stats_ms"] = json!(stats_time.as_secs_f64() * 1_000.0);
+            print_json(&value);
+        }
+    }
+}
+
+// Connects the line editor to a TrieExplorer so that tab completion comes from the loaded trie. The explorer is
+// shared with the loop in repl(), which runs the commands.
+struct ReplHelper {
+    explorer: Rc<RefCell<TrieExplorer>>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.explorer.borrow().complete(line, pos))
     }
 }
-*/
-
-fn print_node_counts() {
-    // vec![Dataset::TestSmallSorted, Dataset::TestMediumSorted, Dataset::TestLargeSorted]].iter().foreach(|dataset| {
-    // 	let t =
-    //});
-    small_trie().print_root_alt();
-    medium_trie().print_root_alt();
-    large_trie().print_root_alt();
-}
-
-fn try_load_words() {
-    dbg!(good_words().len());
-    dbg!(non_words().len());
-    dbg!(large_dataset_words_hash_set().len());
-}
-
-fn small_trie() -> BaseLetterTrie {
-    BaseLetterTrie::from_file_test(
-        &Dataset::TestSmallSorted.filename(),
-        true,
-        &LoadMethod::Continuous,
-        &DisplayDetailOptions::make_no_display(),
-        Some(WORD_COUNT_SMALL),
-    )
-}
-
-fn medium_trie() -> BaseLetterTrie {
-    BaseLetterTrie::from_file_test(
-        &Dataset::TestMediumSorted.filename(),
-        true,
-        &LoadMethod::Continuous,
-        &DisplayDetailOptions::make_no_display(),
-        Some(WORD_COUNT_MEDIUM),
-    )
-}
-
-fn large_trie() -> BaseLetterTrie {
-    BaseLetterTrie::from_file_test(
-        &Dataset::TestLargeSorted.filename(),
-        true,
-        &LoadMethod::ContinuousParallel,
-        &DisplayDetailOptions::make_no_display(),
-        Some(WORD_COUNT_LARGE),
-    )
-}
-
-fn try_large_trie() {
-    let dataset = Dataset::TestLargeSorted;
-    let load_method = LoadMethod::ContinuousParallel;
-    let letter_trie_type = LetterTrieType::Base;
-    let opt = DisplayDetailOptions::make_moderate(&dataset, &load_method, &letter_trie_type);
-    let t = BaseLetterTrie::from_file_test(
-        &dataset.filename(),
-        dataset.is_sorted(),
-        &load_method,
-        &opt,
-        Some(WORD_COUNT_LARGE),
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+fn repl(m: &ArgMatches) {
+    let explorer = Rc::new(RefCell::new(TrieExplorer::new(load_set(m))));
+    println!(
+        "Loaded {} words. Type \"help\" for the commands.",
+        format_count(explorer.borrow().trie().len())
     );
-    println!("{:#?}", &t.to_fixed_node());
+    let history_filename = m.value_of("history").unwrap();
+    let mut editor = Editor::<ReplHelper>::new();
+    editor.set_helper(Some(ReplHelper {
+        explorer: Rc::clone(&explorer),
+    }));
+    // There's no history the first time.
+    let _ = editor.load_history(history_filename);
+    loop {
+        let prompt = explorer.borrow().prompt();
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => fail(&e.to_string()),
+        };
+        if !line.trim().is_empty() {
+            editor.add_history_entry(line.as_str());
+        }
+        let response = explorer.borrow_mut().execute(&line);
+        match response {
+            ReplResponse::Output(text) if text.is_empty() => {}
+            ReplResponse::Output(text) => println!("{}", text),
+            ReplResponse::Quit => break,
+        }
+    }
+    if let Err(e) = editor.save_history(history_filename) {
+        eprintln!(
+            "Couldn't save the history to \"{}\": {}",
+            history_filename, e
+        );
+    }
+}
+
+fn dot(m: &ArgMatches) {
+    let t = load_trie(m);
+    let prefix = m.value_of("prefix").unwrap();
+    let max_depth = value_t!(m, "max-depth", usize).unwrap_or_else(|e| e.exit());
+    let max_children = value_t!(m, "max-children", usize).unwrap_or_else(|e| e.exit());
+    let opt = if m.is_present("counts") {
+        DotOptions::make_with_counts(max_depth, max_children)
+    } else {
+        DotOptions::make_limits(max_depth, max_children)
+    };
+    let dot = t
+        .to_dot_with_options(prefix, &opt)
+        .unwrap_or_else(|| fail(&format!("No words start with \"{}\".", prefix)));
+    match m.value_of("output") {
+        Some(output) => fs::write(output, dot).expect("Error writing file."),
+        None => print!("{}", dot),
+    }
+}
+
+fn d3(m: &ArgMatches) {
+    let t = load_trie(m);
+    let prefix = m.value_of("prefix").unwrap();
+    let max_depth = value_t!(m, "max-depth", usize).unwrap_or_else(|e| e.exit());
+    let min_word_count = value_t!(m, "min-words", usize).unwrap_or_else(|e| e.exit());
+    let hierarchy = t
+        .to_d3_hierarchy(prefix, &D3Options::make(max_depth, min_word_count))
+        .unwrap_or_else(|| fail(&format!("No words start with \"{}\".", prefix)));
+    let text = if m.is_present("html") {
+        let title = format!("{} from \"{}\"", m.value_of("input").unwrap(), prefix);
+        d3_html_page(&hierarchy, &title)
+    } else {
+        format!("{}\n", hierarchy)
+    };
+    match m.value_of("output") {
+        Some(output) => fs::write(output, text).expect("Error writing file."),
+        None => print!("{}", text),
+    }
 }
 
-fn try_find_loop() {
-    let t = small_trie();
-    // let word = "creature";
-    let word = "and";
-    println!("\n{:#?}", t.find(word));
-    println!("\n{:#?}", t.find_loop(word));
+fn generate(m: &ArgMatches) {
+    let source_filename = m.value_of("source").unwrap();
+    let count = value_t!(m, "count", usize).unwrap_or_else(|e| e.exit());
+    let max_depth = value_t!(m, "depth", usize).unwrap_or_else(|e| e.exit());
+    let example_words = words_from_file(source_filename);
+    let mut generator = WordGenerator::new(&example_words, max_depth)
+        .min_length(value_t!(m, "min-length", usize).unwrap_or_else(|e| e.exit()))
+        .max_length(value_t!(m, "max-length", usize).unwrap_or_else(|e| e.exit()))
+        .exclude_example_words(m.is_present("exclude-examples"))
+        .silent(true);
+    if m.is_present("seed") {
+        generator = generator.seed(value_t!(m, "seed", u64).unwrap_or_else(|e| e.exit()));
+    }
+    let words: Vec<String> = generator.iter().take(count).collect();
+    if words.len() < count {
+        eprintln!(
+            "Could only generate {} distinct words.",
+            format_count(words.len())
+        );
+    }
+    match m.value_of("output") {
+        Some(output) => file_from_lines(output, &words),
+        None => {
+            for word in words {
+                println!("{}", word);
+            }
+        }
+    }
 }
 
-/*
-fn try_find_loop_from_iterator() {
-    let t = small_trie();
-    // let word = "creature";
-    let word = "and";
-    println!("\n{:#?}", t.find(word));
-    println!("\n{:#?}", t.find_loop_from_iterator(word));
+fn shuffle(m: &ArgMatches) {
+    let (filename, _) = input_file(m);
+    let seed = if m.is_present("seed") {
+        Some(value_t!(m, "seed", u64).unwrap_or_else(|e| e.exit()))
+    } else {
+        None
+    };
+    create_shuffled_file(&filename, m.value_of("output").unwrap(), seed);
 }
-*/
 
-/*
-fn try_find_loop_like_iterator() {
-    let t = small_trie();
-    let mut prefix = "and";
-    println!("\n\"{}\":\n{:#?}", prefix, t.find_loop_like_iterator(prefix));
-    prefix = "ands";
-    println!("\n\"{}\":\n{:#?}", prefix, t.find_loop_like_iterator(prefix));
-    prefix = "creature";
-    println!("\n\"{}\":\n{:#?}", prefix, t.find_loop_like_iterator(prefix));
-    prefix = "creatu";
-    println!("\n\"{}\":\n{:#?}", prefix, t.find_loop_like_iterator(prefix));
+fn bench(m: &ArgMatches) {
+    let datasets = values_t!(m, "dataset", Dataset).unwrap_or_else(|e| e.exit());
+    // The type and load method have a single default value, so run every one of them unless they're given.
+    let types = if m.occurrences_of("type") > 0 {
+        values_t!(m, "type", LetterTrieType).unwrap_or_else(|e| e.exit())
+    } else {
+        LetterTrieType::ALL.to_vec()
+    };
+    let methods = if m.occurrences_of("load-method") > 0 {
+        values_t!(m, "load-method", LoadMethod).unwrap_or_else(|e| e.exit())
+    } else {
+        LoadMethod::ALL.to_vec()
+    };
+    try_combinations(
+        &datasets,
+        &methods,
+        &types,
+        m.is_present("metrics"),
+        match m.value_of("format") {
+            Some("json") => BenchFormat::Json,
+            Some("csv") => BenchFormat::Csv,
+            _ => BenchFormat::Text,
+        },
+    );
 }
-*/
 
-fn try_freeze() {
-    let fn_name = "try_freeze()";
-    let mut t = large_trie();
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
-    assert_large_root(&t.to_fixed_node());
+// Compare the memory and speed of the two Markov models when generating as many distinct words as the large
+// dataset.
+fn compare_word_generators(m: &ArgMatches) {
+    let fn_name = "compare_word_generators()";
+    let example_words = words_from_file(m.value_of("source").unwrap());
+    let max_depth = value_t!(m, "depth", usize).unwrap_or_else(|e| e.exit());
+    let word_count = Dataset::TestLargeSorted.word_count();
+
+    let start = Instant::now();
+    let model = SequenceModel::new(&example_words, max_depth);
+    print_elapsed_from_start(true, fn_name, "build SequenceModel", start);
+    let start = Instant::now();
+    let context_trie = ContextTrie::new(&example_words, max_depth);
+    print_elapsed_from_start(true, fn_name, "build ContextTrie", start);
+
+    println!(
+        "SequenceModel: about {} bytes.",
+        format_count(model.estimated_bytes())
+    );
+    println!(
+        "ContextTrie: {} nodes; about {} bytes.",
+        format_count(context_trie.node_count()),
+        format_count(context_trie.estimated_bytes())
+    );
 
-    print_elapsed(true, fn_name, LABEL_FREEZE, || t.freeze());
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
-    assert_large_root(&t.to_fixed_node());
+    let mut rng = if m.is_present("seed") {
+        StdRng::seed_from_u64(value_t!(m, "seed", u64).unwrap_or_else(|e| e.exit()))
+    } else {
+        StdRng::from_entropy()
+    };
+    let start = Instant::now();
+    generate_distinct_words(word_count, || model.generate_word(&mut rng));
+    print_elapsed_from_start(true, fn_name, "generate with SequenceModel", start);
+    let start = Instant::now();
+    generate_distinct_words(word_count, || context_trie.generate_word(&mut rng));
+    print_elapsed_from_start(true, fn_name, "generate with ContextTrie", start);
+}
 
-    print_elapsed(true, fn_name, LABEL_UNFREEZE, || t.unfreeze());
-    print_elapsed(true, fn_name, LABEL_PRINT_ROOT, || t.print_root_alt());
-    assert_large_root(&t.to_fixed_node());
+// Generate words until there are `word_count` distinct ones, giving up like a WordGenerator iterator does after
+// too many words in a row that were already generated.
+fn generate_distinct_words<F: FnMut() -> String>(
+    word_count: usize,
+    mut generate_word: F,
+) -> HashSet<String> {
+    let mut words = HashSet::new();
+    let mut attempts_without_new_word = 0;
+    while words.len() < word_count && attempts_without_new_word < MAX_ATTEMPTS_WITHOUT_NEW_WORD {
+        if words.insert(generate_word()) {
+            attempts_without_new_word = 0;
+        } else {
+            attempts_without_new_word += 1;
+        }
+    }
+    if words.len() < word_count {
+        eprintln!(
+            "Could only generate {} distinct words.",
+            format_count(words.len())
+        );
+    }
+    words
 }
 
-fn display_small_trie() {
-    println!("{:#?}", &small_trie());
+fn try_substring_index(m: &ArgMatches) {
+    let fn_name = "try_substring_index()";
+    let datasets = values_t!(m, "dataset", Dataset).unwrap_or_else(|e| e.exit());
+    let substring = m.value_of("substring").unwrap();
+    for dataset in datasets.iter() {
+        let mut index = None;
+        print_elapsed(true, fn_name, "build", || {
+            index = Some(SubstringIndex::from_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &LoadMethod::ContinuousParallel,
+            ));
+        });
+        let index = index.unwrap();
+        println!("{}", index.describe_blowup());
+        let step_label = format!("words_containing(\"{}\")", substring);
+        print_elapsed(true, fn_name, &step_label, || {
+            println!("{} words", index.words_containing(substring).len());
+        });
+    }
+}
+
+// Generate any missing datasets and confirm that all of them have the expected word counts and checksums.
+fn materialize_datasets() {
+    let start = Instant::now();
+    for spec in DATASET_SPECS.iter() {
+        println!(
+            "{}: {} words.",
+            DATASET_REGISTRY.path(spec.filename),
+            format_count(spec.word_count)
+        );
+    }
+    print_elapsed_from_start(true, "materialize_datasets()", "all datasets", start);
 }
 
-fn try_combinations(datasets: &[Dataset], methods: &[LoadMethod], types: &[LetterTrieType]) {
+fn try_combinations(
+    datasets: &[Dataset],
+    methods: &[LoadMethod],
+    types: &[LetterTrieType],
+    show_metrics: bool,
+    format: BenchFormat,
+) {
+    let mut json_rows = vec![];
+    if format == BenchFormat::Csv {
+        println!(
+            "dataset,load_method,type,total_bytes,{}",
+            BUILD_REPORT_CSV_HEADER
+        );
+    }
     for one_dataset in datasets {
+        let mut rows = vec![];
         for one_method in methods {
             for one_type in types {
-                try_one_combination(&one_dataset, &one_method, &one_type);
+                // The progress messages would get mixed in with JSON or CSV.
+                let mut opt = if format == BenchFormat::Text {
+                    DisplayDetailOptions::make_moderate(one_dataset, one_method, one_type)
+                } else {
+                    DisplayDetailOptions::make_no_display()
+                };
+                opt.count_inserts = show_metrics;
+                if let Some((report, heap_size)) =
+                    try_one_combination(one_dataset, one_method, one_type, &opt)
+                {
+                    if show_metrics && format == BenchFormat::Text {
+                        print!("\n{}", report.metrics);
+                    }
+                    rows.push((one_method, one_type, report, heap_size));
+                }
+            }
+        }
+        match format {
+            BenchFormat::Text => print_comparison_table(one_dataset, &rows),
+            BenchFormat::Json => {
+                for (load_method, letter_trie_type, report, heap_size) in rows {
+                    json_rows.push(json!({
+                        "dataset": one_dataset.name(),
+                        "load_method": load_method.name(),
+                        "type": letter_trie_type.name(),
+                        "total_bytes": heap_size.total_bytes(),
+                        "report": report.to_json(),
+                    }));
+                }
+            }
+            BenchFormat::Csv => {
+                for (load_method, letter_trie_type, report, heap_size) in rows {
+                    println!(
+                        "{},{},{},{},{}",
+                        one_dataset.name(),
+                        load_method.name(),
+                        letter_trie_type.name(),
+                        heap_size.total_bytes(),
+                        report.to_csv_row()
+                    );
+                }
             }
         }
     }
+    if format == BenchFormat::Json {
+        print_json(&Value::Array(json_rows));
+    }
 }
 
+// Returns the report on loading the trie and its memory use, or None if the combination isn't supported.
 fn try_one_combination(
     dataset: &Dataset,
     load_method: &LoadMethod,
     letter_trie_type: &LetterTrieType,
-) {
+    opt: &DisplayDetailOptions,
+) -> Option<(BuildReport, HeapSize)> {
     let filename = &dataset.filename();
     let is_sorted = dataset.is_sorted();
-    // let opt = DisplayDetailOptions::make_overall_time(dataset, load_method, letter_trie_type);
-    let opt = DisplayDetailOptions::make_moderate(dataset, load_method, letter_trie_type);
-    let expected_word_count = dataset.word_count();
-    if USE_CHAR_GET_COUNTER {
-        CharGetCounter::reset();
-    }
+    let expected_word_count = Some(dataset.word_count());
     match letter_trie_type {
         LetterTrieType::Base => {
-            BaseLetterTrie::from_file_test(
+            let (t, report) = BaseLetterTrie::from_file_test(
                 filename,
                 is_sorted,
-                &load_method,
-                &opt,
-                Some(expected_word_count),
+                load_method,
+                opt,
+                expected_word_count,
             );
+            Some((report, t.heap_size()))
         }
         LetterTrieType::NoParent => {
-            if is_sorted || *load_method != LoadMethod::ContinuousParallel {
-                NoParentLetterTrie::from_file_test(
-                    filename,
-                    is_sorted,
-                    &load_method,
-                    &opt,
-                    Some(expected_word_count),
-                );
+            if !is_sorted && *load_method == LoadMethod::ContinuousParallel {
+                return None;
             }
+            let (t, report) = NoParentLetterTrie::from_file_test(
+                filename,
+                is_sorted,
+                load_method,
+                opt,
+                expected_word_count,
+            );
+            Some((report, t.heap_size()))
+        }
+        LetterTrieType::Map => {
+            let (t, report) = LetterTrieSet::from_file_test(
+                filename,
+                is_sorted,
+                load_method,
+                opt,
+                expected_word_count,
+            );
+            Some((report, t.heap_size()))
         }
-    };
-    if USE_CHAR_GET_COUNTER {
-        CharGetCounter::print_optional();
-    }
-}
-
-fn create_find_files() {
-    let content =
-        fs::read_to_string(Dataset::TestLargeSorted.filename()).expect("Error reading file.");
-    let source_vec: Vec<&str> = content.split('\n').collect();
-    let mut words = vec![];
-    let mut non_words = vec![];
-    for word in source_vec.iter().step_by(100).take(WORD_COUNT_GOOD) {
-        words.push(word.to_owned());
-        non_words.push(format!("{}q", word));
-    }
-    // If these assertions fail because there are too few words, lower the step_by() value above.
-    assert_eq!(words.len(), WORD_COUNT_GOOD);
-    assert_eq!(non_words.len(), WORD_COUNT_NON);
-
-    let mut file = File::create(FILENAME_GOOD_WORDS).expect("Error creating target file.");
-    for word in words {
-        writeln!(file, "{}", word).expect("Error writing a line.");
-    }
-
-    file = File::create(FILENAME_NON_WORDS).expect("Error creating target file.");
-    for word in non_words {
-        writeln!(file, "{}", word).expect("Error writing a line.");
     }
 }
 
-/*
-fn create_all_shuffled_files(datasets: &Vec<Dataset>) {
-    for one_dataset in datasets {
-        let source_filename = String::from(one_size.filename());
-        let target_filename = source_filename.replace(".txt", "_shuffled.txt");
-        create_shuffled_file(&source_filename, &target_filename);
+// Print the load time and memory use of each combination tried on one dataset side by side.
+fn print_comparison_table(
+    dataset: &Dataset,
+    rows: &[(&LoadMethod, &LetterTrieType, BuildReport, HeapSize)],
+) {
+    println!("\n{}:", dataset.name());
+    println!(
+        "{:<22} {:<10} {:>12} {:>14} {:>12} {:>12} {:>12} {:>10} {:>10}",
+        "load method",
+        "type",
+        "load ms",
+        "total bytes",
+        "nodes",
+        "maps",
+        "rc",
+        "per word",
+        "per node"
+    );
+    for (load_method, letter_trie_type, report, heap_size) in rows {
+        let elapsed = report
+            .metrics
+            .step_time(LABEL_STEP_OVERALL)
+            .unwrap_or_default();
+        println!(
+            "{:<22} {:<10} {:>12.1} {:>14} {:>12} {:>12} {:>12} {:>10.1} {:>10.1}",
+            load_method.name(),
+            letter_trie_type.name(),
+            elapsed.as_secs_f64() * 1_000.0,
+            format_count(heap_size.total_bytes()),
+            format_count(heap_size.node_bytes),
+            format_count(heap_size.map_bytes),
+            format_count(heap_size.rc_bytes),
+            heap_size.bytes_per_word(),
+            heap_size.bytes_per_node()
+        );
     }
 }
 
-fn create_shuffled_file(source_filename: &str, target_filename: &str) {
+// Write the non-blank lines of a file in random order, using a seed if there is one so the order can be repeated.
+fn create_shuffled_file(source_filename: &str, target_filename: &str, seed: Option<u64>) {
     let content = fs::read_to_string(source_filename).expect("Error reading file.");
-    let mut v: Vec<&str> = content.split('\n').collect();
-    v.shuffle(&mut rand::thread_rng());
+    let mut v: Vec<&str> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    v.shuffle(&mut rng);
     let mut file = File::create(target_filename).expect("Error creating target file.");
     for s in v {
         writeln!(file, "{}", s).expect("Error writing a line.");
     }
 }
-*/
-
-/*
-fn try_find() {
-    let t = &mut CharExtNode::new();
-    t.add_word("creature");
-    t.add_word("cross");
-    t.add_word("and");
-    println!("{:#?}", t);
-    let mut found_cross = t.find("cross");
-    // let mut found_river = t.find("river");
-    altvals!(t.to_fixed_char_node(), t.find("cross"), t.find("creatu"), t.find("an"), t.find("c"));
-}
-
-fn try_iterator() {
-    let t = &mut CharBTreeNode::new();
-    simple_trie(t);
-    println!("{:#?}", t);
-    for node in t.iter_breadth_first() {
-        println!("{:?}", node);
-    }
-}
-*/
@@ -3,64 +3,267 @@ use std::fmt::{self, Debug};
 use std::fs;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::mem;
 use std::time::Instant;
 
+use crate::trie_validation::TrieValidator;
 use crate::*;
 
-pub struct NoParentLetterTrie {
-    c: char,
-    depth: usize,
-    children: BTreeMap<char, Self>,
-    is_word: bool,
+/// A stripped-down trie with no parent links where each node directly owns its child nodes.
+///
+/// Like TrieMap, the nodes are generic over the type of key element. The letter trie that implements `LetterTrie`
+/// is `NoParentLetterTrie`, the case where the key elements are chars.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie: NoParentTrie<u8> = NoParentTrie::new();
+/// trie.add_key(&[192, 168, 1][..]);
+/// assert!(trie.contains_word(&[192, 168, 1][..]));
+/// assert!(!trie.contains_word(&[192, 168][..]));
+/// ```
+pub struct NoParentTrie<K> {
+    // The root node's key element is `K::root_sentinel()`, such as a single space for a char, which doesn't count
+    // toward the words represented by the trie.
+    root: Node<K>,
+    // Applied to every word or prefix that's added or looked up.
+    normalizer: Normalizer,
+    // A companion trie holding each key in reverse.
+    suffix_index: Option<Box<NoParentTrie<K>>>,
 }
 
-impl NoParentLetterTrie {
+/// A trie with one letter per node.
+pub type NoParentLetterTrie = NoParentTrie<char>;
+
+impl<K: KeyElement> NoParentTrie<K> {
     pub fn new() -> Self {
-        let c = ' ';
-        let depth = 0;
-        let is_word = false;
-        Self::make_node(c, depth, is_word)
+        Self::with_normalizer(Normalizer::make_default())
     }
 
-    fn make_node(c: char, depth: usize, is_word: bool) -> Self {
+    /// Create an empty trie that normalizes words with the given normalizer instead of the default
+    /// `trim().to_lowercase()`.
+    pub fn with_normalizer(normalizer: Normalizer) -> Self {
+        let c = K::root_sentinel();
+        let depth = 0;
+        let is_word = false;
+        let root = Node::make_node(c, depth, is_word);
         Self {
-            c,
-            depth,
-            children: BTreeMap::new(),
-            is_word,
+            root,
+            normalizer,
+            suffix_index: None,
         }
     }
 
-    fn add_word(&mut self, s: &str) {
-        let s = s.trim();
-        if !s.is_empty() {
-            let v: Vec<char> = s.to_lowercase().chars().collect();
+    /// Add a key after splitting it into key elements, which for text means passing it through the trie's
+    /// normalizer. An empty key or one that the normalizer rejects is ignored.
+    pub fn add_key<Q: ToKeyElements<K> + ?Sized>(&mut self, key: &Q) {
+        if let Some(v) = key.to_key_elements(&self.normalizer) {
             let v_len = v.len();
             self.add_from_vec_chars(&v, v_len, 0);
         }
     }
 
-    pub fn add_from_vec_chars(&mut self, v: &[char], v_len: usize, char_index: usize) {
+    /// Returns true if a key was added as a whole, not just as the start of a longer key.
+    pub fn contains_word<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> bool {
+        match word.to_key_elements(&self.normalizer) {
+            Some(v) => {
+                let v_len = v.len();
+                self.root.is_word_child(v, v_len, 0)
+            }
+            None => false,
+        }
+    }
+
+    pub fn add_from_vec_chars(&mut self, v: &[K], v_len: usize, char_index: usize) {
+        self.add_from_vec_chars_counted(v, v_len, char_index, None);
+    }
+
+    // The same as add_from_vec_chars() while recording the hits, misses and new nodes if there are counts to update.
+    fn add_from_vec_chars_counted(
+        &mut self,
+        v: &[K],
+        v_len: usize,
+        char_index: usize,
+        counts: Option<&mut InsertCounts>,
+    ) {
         if v_len > 0 {
-            self.add_from_vec_chars_one_node(v, v_len, char_index);
+            self.add_to_suffix_index(&v[char_index..v_len]);
+            self.root
+                .add_from_vec_chars_one_node(v, v_len, char_index, counts);
         }
     }
 
-    fn add_from_vec_chars_one_node(&mut self, v: &[char], v_len: usize, char_index: usize) {
-        if char_index < v_len {
-            let c = v[char_index];
-            let is_word = char_index == v_len - 1;
-            let child_node_opt = self.children.get_mut(&c);
-            if let Some(child_node) = child_node_opt {
-                if is_word {
-                    child_node.is_word = true;
+    /// Remove a key that was added as a whole and return true if it was there. Any nodes that are left without a
+    /// word either at the node or below it are removed as well.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::new();
+    /// trie.add_word("cross");
+    /// trie.add_word("crossed");
+    /// assert!(trie.remove_key("crossed"));
+    /// assert!(!trie.remove_key("crossed"));
+    /// assert!(trie.contains_word("cross"));
+    /// assert_eq!(trie.node_count(), 6);
+    /// ```
+    pub fn remove_key<Q: ToKeyElements<K> + ?Sized>(&mut self, key: &Q) -> bool {
+        let v = match key.to_key_elements(&self.normalizer) {
+            Some(v) if !v.is_empty() => v,
+            _ => return false,
+        };
+        let is_removed = self.root.remove_one_node(&v, 0);
+        if is_removed {
+            if let Some(suffix_index) = &mut self.suffix_index {
+                let reversed: Vec<K> = v.iter().rev().cloned().collect();
+                suffix_index.root.remove_one_node(&reversed, 0);
+            }
+        }
+        is_removed
+    }
+
+    /// Move all of the words from another trie into this one.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = NoParentLetterTrie::new();
+    /// trie.add_word("cross");
+    /// let mut other = NoParentLetterTrie::new();
+    /// other.add_word("crossed");
+    /// other.add_word("azure");
+    /// trie.merge(other);
+    /// assert_eq!(trie.word_count(), 3);
+    /// assert!(trie.contains_word("crossed"));
+    /// ```
+    pub fn merge(&mut self, other: Self) {
+        if self.suffix_index.is_some() {
+            let mut keys = vec![];
+            other.root.add_keys(&mut vec![], &mut keys);
+            for v in keys {
+                self.add_to_suffix_index(&v);
+            }
+        }
+        self.root.merge_node(other.root);
+    }
+
+    /// Start keeping a companion trie with every key in reverse, as with `TrieMap::enable_suffix_index()`. The
+    /// companion trie is built from the words already in the trie and from then on it's kept in sync by
+    /// `add_key()`, `remove_key()` and `merge()`.
+    pub fn enable_suffix_index(&mut self) {
+        let mut suffix_index = NoParentTrie::new();
+        let mut keys = vec![];
+        self.root.add_keys(&mut vec![], &mut keys);
+        for v in keys {
+            let reversed: Vec<K> = v.into_iter().rev().collect();
+            suffix_index.add_from_vec_chars(&reversed, reversed.len(), 0);
+        }
+        self.suffix_index = Some(Box::new(suffix_index));
+    }
+
+    /// Stop keeping the companion trie of reversed keys.
+    pub fn disable_suffix_index(&mut self) {
+        self.suffix_index = None;
+    }
+
+    /// Returns true if the trie is keeping a companion trie of reversed keys.
+    pub fn has_suffix_index(&self) -> bool {
+        self.suffix_index.is_some()
+    }
+
+    fn add_to_suffix_index(&mut self, v: &[K]) {
+        if v.is_empty() {
+            return;
+        }
+        if let Some(suffix_index) = &mut self.suffix_index {
+            let reversed: Vec<K> = v.iter().rev().cloned().collect();
+            suffix_index.add_from_vec_chars(&reversed, reversed.len(), 0);
+        }
+    }
+
+    /// Find the words that end with a suffix, in alphabetical order. The suffix itself is included if it's a word.
+    /// Without a suffix index this has to check every word in the trie.
+    pub fn words_with_suffix<Q: ToKeyElements<K> + ?Sized>(&self, suffix: &Q) -> Vec<K::Key> {
+        match suffix.to_key_elements(&self.normalizer) {
+            Some(suffix) => self.words_with_prefix_and_suffix_elements(&[], &suffix),
+            None => vec![],
+        }
+    }
+
+    /// Find the words that start with a prefix and end with a suffix, in alphabetical order. The prefix and suffix
+    /// may overlap. The nodes don't cache their word counts, so with a suffix index this always walks the subtree
+    /// below the reversed suffix.
+    pub fn words_with_prefix_and_suffix<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+        suffix: &Q,
+    ) -> Vec<K::Key> {
+        match (
+            prefix.to_key_elements(&self.normalizer),
+            suffix.to_key_elements(&self.normalizer),
+        ) {
+            (Some(prefix), Some(suffix)) => {
+                self.words_with_prefix_and_suffix_elements(&prefix, &suffix)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn words_with_prefix_and_suffix_elements(&self, prefix: &[K], suffix: &[K]) -> Vec<K::Key> {
+        let prefix_node = match self.root.get_node(prefix) {
+            Some(node) => node,
+            None => return vec![],
+        };
+        let mut keys: Vec<Vec<K>> = vec![];
+        match &self.suffix_index {
+            Some(suffix_index) => {
+                let mut reversed_suffix: Vec<K> = suffix.iter().rev().cloned().collect();
+                let suffix_node = match suffix_index.root.get_node(&reversed_suffix) {
+                    Some(node) => node,
+                    None => return vec![],
+                };
+                let mut reversed_keys = vec![];
+                suffix_node.add_keys(&mut reversed_suffix, &mut reversed_keys);
+                for reversed in reversed_keys {
+                    let v: Vec<K> = reversed.into_iter().rev().collect();
+                    if v.starts_with(prefix) {
+                        keys.push(v);
+                    }
                 }
-                child_node.add_from_vec_chars_one_node(v, v_len, char_index + 1);
-            } else {
-                let mut new_child_node = Self::make_node(c, self.depth + 1, is_word);
-                new_child_node.add_from_vec_chars_one_node(v, v_len, char_index + 1);
-                self.children.insert(c, new_child_node);
+                keys.sort();
             }
+            None => {
+                prefix_node.add_keys(&mut prefix.to_vec(), &mut keys);
+                keys.retain(|v| v.ends_with(suffix));
+            }
+        }
+        keys.iter().map(|v| K::make_key(v)).collect()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.root.node_count()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.root.word_count()
+    }
+
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
+}
+
+impl NoParentLetterTrie {
+    /// Add a word after passing it through the trie's normalizer. A blank word or one that the normalizer rejects
+    /// is ignored.
+    pub fn add_word(&mut self, s: &str) {
+        if let Some(v) = self.normalizer.load_chars(s) {
+            let v_len = v.len();
+            self.add_from_vec_chars(&v, v_len, 0);
         }
     }
 
@@ -113,14 +316,21 @@ impl NoParentLetterTrie {
         }
     }
 
-    fn load_read_vec_fill(&mut self, filename: &str, opt: &DisplayDetailOptions) {
+    fn load_read_vec_fill(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        report: &mut BuildReport,
+    ) {
         let start = Instant::now();
         let content = fs::read_to_string(filename).expect("Error reading file.");
-        print_elapsed_from_start(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
+        report
+            .metrics
+            .record_elapsed(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
 
         let start = Instant::now();
-        let words: Vec<&str> = content.split('\n').collect();
-        print_elapsed_from_start(
+        let words: Vec<&str> = content.lines().collect();
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_MAKE_VECTOR,
@@ -133,9 +343,17 @@ impl NoParentLetterTrie {
 
         let start = Instant::now();
         for word in words {
-            self.add_word(word);
+            if let Some(vec_char) = report.load_line(&self.normalizer, word) {
+                let v_len = vec_char.len();
+                self.add_from_vec_chars_counted(
+                    &vec_char,
+                    v_len,
+                    0,
+                    opt.count_inserts.then_some(&mut report.metrics.counts),
+                );
+            }
         }
-        print_elapsed_from_start(
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_LOAD_FROM_VEC,
@@ -145,14 +363,24 @@ impl NoParentLetterTrie {
         self.print(opt.object_detail_level);
     }
 
-    fn load_vec_fill(&mut self, filename: &str, opt: &DisplayDetailOptions) {
+    fn load_vec_fill(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        report: &mut BuildReport,
+    ) {
         let start = Instant::now();
-        let v = make_vec_char_test(filename, opt, None);
+        let v = make_vec_char_test(filename, opt, None, &self.normalizer, report);
         for vec_char in v {
             let v_len = vec_char.len();
-            self.add_from_vec_chars(&vec_char, v_len, 0);
+            self.add_from_vec_chars_counted(
+                &vec_char,
+                v_len,
+                0,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
         }
-        print_elapsed_from_start(
+        report.metrics.record_elapsed(
             opt.print_step_time,
             &opt.label,
             LABEL_STEP_LOAD_FROM_VEC,
@@ -161,21 +389,34 @@ impl NoParentLetterTrie {
         self.print(opt.object_detail_level);
     }
 
-    fn load_continuous(&mut self, filename: &str) {
+    fn load_continuous(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        report: &mut BuildReport,
+    ) {
         let file = File::open(filename).unwrap();
         for line in BufReader::new(file).lines() {
             let line = line.unwrap();
-            let line = line.trim();
-            if !line.is_empty() {
-                let vec_char: Vec<char> = line.to_lowercase().chars().collect();
+            if let Some(vec_char) = report.load_line(&self.normalizer, &line) {
                 let v_len = vec_char.len();
-                self.add_from_vec_chars(&vec_char, v_len, 0);
+                self.add_from_vec_chars_counted(
+                    &vec_char,
+                    v_len,
+                    0,
+                    opt.count_inserts.then_some(&mut report.metrics.counts),
+                );
             }
         }
     }
 
-    fn load_continuous_parallel(&mut self, filename: &str) {
-        self.load_continuous(filename);
+    fn load_continuous_parallel(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        report: &mut BuildReport,
+    ) {
+        self.load_continuous(filename, opt, report);
 
         /*
         let (tx, rx) = mpsc::channel();
@@ -239,6 +480,94 @@ impl NoParentLetterTrie {
     }
     */
 
+    pub fn describe_one_line(&self) -> String {
+        self.root.describe_one_line()
+    }
+
+    pub fn describe_deep(&self, s: &mut String, depth: usize) {
+        self.root.describe_deep(s, depth)
+    }
+
+    pub fn prefix(&self) -> String {
+        String::from("")
+    }
+
+    #[deprecated(
+        note = "explore the trie with the \"ls\" and \"cd\" commands of the REPL or with TrieExplorer"
+    )]
+    pub fn print_prefixes(&self, prefix_count: usize) -> usize {
+        self.root.print_prefixes("", prefix_count)
+    }
+
+    pub fn is_word_recursive(&self, prefix: &str) -> bool {
+        let prefix: Vec<char> = match self.normalizer.to_chars(prefix) {
+            Some(prefix) => prefix,
+            None => return false,
+        };
+        let prefix_len = prefix.len();
+        self.root.is_word_child(prefix, prefix_len, 0)
+    }
+
+    // Find the node for a prefix that's already been normalized, which is the root for an empty prefix.
+    fn find_node(&self, prefix: &[char]) -> Option<&Node> {
+        let mut node = &self.root;
+        for c in prefix.iter() {
+            node = node.children.get(c)?;
+        }
+        Some(node)
+    }
+}
+
+struct Node<K = char> {
+    c: K,
+    depth: usize,
+    children: BTreeMap<K, Node<K>>,
+    is_word: bool,
+}
+
+impl<K: KeyElement> Node<K> {
+    fn make_node(c: K, depth: usize, is_word: bool) -> Self {
+        Self {
+            c,
+            depth,
+            children: BTreeMap::new(),
+            is_word,
+        }
+    }
+
+    fn add_from_vec_chars_one_node(
+        &mut self,
+        v: &[K],
+        v_len: usize,
+        char_index: usize,
+        mut counts: Option<&mut InsertCounts>,
+    ) {
+        if char_index < v_len {
+            let c = v[char_index].clone();
+            let is_word = char_index == v_len - 1;
+            let child_node_opt = self.children.get_mut(&c);
+            if let Some(counts) = counts.as_deref_mut() {
+                counts.record(child_node_opt.is_some());
+            }
+            if let Some(child_node) = child_node_opt {
+                if is_word {
+                    if let Some(counts) = counts.as_deref_mut() {
+                        counts.record_word(child_node.is_word);
+                    }
+                    child_node.is_word = true;
+                }
+                child_node.add_from_vec_chars_one_node(v, v_len, char_index + 1, counts);
+            } else {
+                let mut new_child_node = Self::make_node(c.clone(), self.depth + 1, is_word);
+                if let Some(counts) = counts.as_deref_mut() {
+                    counts.record_node();
+                }
+                new_child_node.add_from_vec_chars_one_node(v, v_len, char_index + 1, counts);
+                self.children.insert(c, new_child_node);
+            }
+        }
+    }
+
     pub fn node_count(&self) -> usize {
         let mut calc_count = 1;
         for child_node in self.children.values() {
@@ -266,58 +595,91 @@ impl NoParentLetterTrie {
         max_child_height + 1
     }
 
-    fn find_child(
-        &self,
-        prefix: Vec<char>,
-        prefix_len: usize,
-        prefix_index: usize,
-    ) -> Option<FixedNode> {
-        if prefix_index >= prefix_len {
-            None
-        } else {
-            let c = prefix[prefix_index];
-            if let Some(child_node) = self.children.get(&c) {
-                if prefix_index == prefix_len - 1 {
-                    // We've found the node.
-                    Some(child_node.to_fixed_node())
-                } else {
-                    child_node.find_child(prefix, prefix_len, prefix_index + 1)
-                }
-            } else {
-                None
-            }
+    // Find the node for a sequence of key elements below this node, which is this node for an empty sequence.
+    fn get_node(&self, v: &[K]) -> Option<&Self> {
+        let mut node = self;
+        for c in v.iter() {
+            node = node.children.get(c)?;
         }
+        Some(node)
     }
 
-    pub fn describe_one_line(&self) -> String {
-        let is_word_desc = if self.is_word { " (word)" } else { "" };
-        format!("NoParentLetterTrie: {}{}", self.c, is_word_desc)
+    // Add the key elements of every word at this node or below it, where `prefix` holds the key elements down to
+    // this node.
+    fn add_keys(&self, prefix: &mut Vec<K>, keys: &mut Vec<Vec<K>>) {
+        if self.is_word {
+            keys.push(prefix.clone());
+        }
+        for (c, child_node) in self.children.iter() {
+            prefix.push(c.clone());
+            child_node.add_keys(prefix, keys);
+            prefix.pop();
+        }
     }
 
-    pub fn describe_deep(&self, s: &mut String, depth: usize) {
-        s.push_str(&format!(
-            "{}\n",
-            format_indent(depth, &(self.describe_one_line()))
-        ));
-        if depth < DEBUG_TRIE_MAX_DEPTH {
-            for child_node in self.children.values().take(DEBUG_TRIE_MAX_CHILDREN) {
-                child_node.describe_deep(s, depth + 1);
+    // Clear the word at the end of a sequence of key elements below this node, then drop the nodes along the way
+    // that no longer lead to a word. Returns true if there was a word to clear.
+    fn remove_one_node(&mut self, v: &[K], char_index: usize) -> bool {
+        let c = &v[char_index];
+        let child_node = match self.children.get_mut(c) {
+            Some(child_node) => child_node,
+            None => return false,
+        };
+        let is_removed = if char_index == v.len() - 1 {
+            mem::replace(&mut child_node.is_word, false)
+        } else {
+            child_node.remove_one_node(v, char_index + 1)
+        };
+        if !child_node.is_word && child_node.children.is_empty() {
+            self.children.remove(c);
+        }
+        is_removed
+    }
+
+    // Move the children of another node with the same key element into this one.
+    fn merge_node(&mut self, other: Self) {
+        self.is_word |= other.is_word;
+        for (c, other_child_node) in other.children {
+            match self.children.get_mut(&c) {
+                Some(child_node) => child_node.merge_node(other_child_node),
+                None => {
+                    self.children.insert(c, other_child_node);
+                }
             }
         }
     }
 
-    pub fn prefix(&self) -> String {
-        String::from("")
+    fn is_word_child(&self, prefix: Vec<K>, prefix_len: usize, prefix_index: usize) -> bool {
+        if prefix_index >= prefix_len {
+            false
+        } else {
+            let c = &prefix[prefix_index];
+            if let Some(child_node) = self.children.get(c) {
+                if prefix_index == prefix_len - 1 {
+                    // We've found the node.
+                    child_node.is_word
+                } else {
+                    child_node.is_word_child(prefix, prefix_len, prefix_index + 1)
+                }
+            } else {
+                false
+            }
+        }
     }
+}
 
-    pub fn print_prefixes(&self, prefix_count: usize) -> usize {
+impl Node {
+    // The nodes don't link to their parents, so the prefix of this node is passed down from the trie.
+    fn print_prefixes(&self, prefix: &str, prefix_count: usize) -> usize {
         let mut remaining_prefix_count = prefix_count;
         let mut prefixes_printed = 0;
         for child_node in self.children.values() {
-            println!("{}", child_node.prefix());
+            let child_prefix = format!("{}{}", prefix, child_node.c);
+            println!("{}", child_prefix);
             remaining_prefix_count -= 1;
             if remaining_prefix_count > 0 {
-                let one_prefixes_printed = child_node.print_prefixes(remaining_prefix_count);
+                let one_prefixes_printed =
+                    child_node.print_prefixes(&child_prefix, remaining_prefix_count);
                 remaining_prefix_count -= one_prefixes_printed;
                 prefixes_printed += one_prefixes_printed;
             } else {
@@ -327,26 +689,117 @@ impl NoParentLetterTrie {
         prefixes_printed
     }
 
-    pub fn is_word_recursive(&self, prefix: &str) -> bool {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
-        let prefix_len = prefix.len();
-        self.is_word_child(prefix, prefix_len, 0)
+    // Check a node and the ones below it for LetterTrie::validate(). `parent` is the key this node is stored under
+    // and the parent's depth, or None for the root. There are no parent links or cached counts to check.
+    fn validate_node(
+        &self,
+        parent: Option<(char, usize)>,
+        prefix: &mut String,
+        v: &mut TrieValidator,
+    ) {
+        v.check_node(
+            prefix,
+            parent,
+            self.c,
+            self.depth,
+            self.is_word,
+            self.children.len(),
+        );
+        for (key, child_node) in self.children.iter() {
+            prefix.push(*key);
+            child_node.validate_node(Some((*key, self.depth)), prefix, v);
+            prefix.pop();
+        }
     }
 
-    fn is_word_child(&self, prefix: Vec<char>, prefix_len: usize, prefix_index: usize) -> bool {
+    fn find_child(
+        &self,
+        prefix: Vec<char>,
+        prefix_len: usize,
+        prefix_index: usize,
+    ) -> Option<FixedNode> {
         if prefix_index >= prefix_len {
-            false
+            None
         } else {
             let c = prefix[prefix_index];
             if let Some(child_node) = self.children.get(&c) {
                 if prefix_index == prefix_len - 1 {
                     // We've found the node.
-                    child_node.is_word
+                    Some(child_node.to_fixed_node())
                 } else {
-                    child_node.is_word_child(prefix, prefix_len, prefix_index + 1)
+                    child_node.find_child(prefix, prefix_len, prefix_index + 1)
                 }
             } else {
-                false
+                None
+            }
+        }
+    }
+
+    // The prefix is left empty since a node doesn't know its parent.
+    fn to_fixed_node(&self) -> FixedNode {
+        FixedNode {
+            c: self.c,
+            prefix: "".to_owned(),
+            depth: self.depth,
+            is_word: self.is_word,
+            child_count: self.children.len(),
+            node_count: self.node_count(),
+            word_count: self.word_count(),
+            height: self.height(),
+            surface_forms: vec![],
+        }
+    }
+
+    pub fn describe_one_line(&self) -> String {
+        let is_word_desc = if self.is_word { " (word)" } else { "" };
+        format!("NoParentLetterTrie: {}{}", self.c, is_word_desc)
+    }
+
+    // The child nodes are stored inline in the BTreeMap, so each node adds only its map to the memory used.
+    fn add_heap_size(&self, h: &mut HeapSize) {
+        h.node_count += 1;
+        if self.is_word {
+            h.word_count += 1;
+        }
+        h.add_children::<char, Self>(self.children.len(), true);
+        for child_node in self.children.values() {
+            child_node.add_heap_size(h);
+        }
+    }
+
+    // Add this node and the ones below it to a graph, returning the ID of this node if the writer included it.
+    pub(crate) fn write_graph<W: GraphWriter>(
+        &self,
+        w: &mut W,
+        prefix: &mut String,
+        depth: usize,
+    ) -> Option<usize> {
+        let node = if w.needs_counts() {
+            self.to_fixed_node()
+        } else {
+            shallow_fixed_node(self.c, self.depth, self.is_word, self.children.len())
+        };
+        let id = w.add_node(prefix, &node, depth)?;
+        let shown_count = w.children_to_show(depth, self.children.len());
+        for (c, child_node) in self.children.iter().take(shown_count) {
+            prefix.push(*c);
+            let child_id = child_node.write_graph(w, prefix, depth + 1);
+            prefix.pop();
+            if let Some(child_id) = child_id {
+                w.add_edge(id, child_id, *c);
+            }
+        }
+        Some(id)
+    }
+
+    pub fn describe_deep(&self, s: &mut String, depth: usize) {
+        s.push_str(&format!(
+            "{}\n",
+            format_indent(depth, &(self.describe_one_line()))
+        ));
+        if depth < DEBUG_TRIE_MAX_DEPTH {
+            for child_node in self.children.values().take(DEBUG_TRIE_MAX_CHILDREN) {
+                child_node.describe_deep(s, depth + 1);
             }
         }
     }
@@ -355,50 +808,128 @@ impl NoParentLetterTrie {
 impl LetterTrie for NoParentLetterTrie {
     fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
         let opt = DisplayDetailOptions::make_no_display();
-        Self::from_file_test(filename, is_sorted, load_method, &opt, None)
+        Self::from_file_test(filename, is_sorted, load_method, &opt, None).0
     }
 
-    fn from_file_test(
+    fn from_file_report(
         filename: &str,
         _is_sorted: bool,
         load_method: &LoadMethod,
         opt: &DisplayDetailOptions,
         _expected_word_count: Option<usize>,
-    ) -> Self {
-        let mut t = Self::new();
-        print_elapsed(
+        normalizer: &Normalizer,
+    ) -> (Self, BuildReport) {
+        let mut t = Self::with_normalizer(normalizer.clone());
+        let mut report = BuildReport::default();
+        report.metrics.is_counted = opt.count_inserts;
+        let start = Instant::now();
+        match load_method {
+            LoadMethod::ReadVecFill => t.load_read_vec_fill(filename, opt, &mut report),
+            LoadMethod::VecFill => t.load_vec_fill(filename, opt, &mut report),
+            LoadMethod::Continuous => t.load_continuous(filename, opt, &mut report),
+            LoadMethod::ContinuousParallel => {
+                t.load_continuous_parallel(filename, opt, &mut report)
+            }
+        };
+        report.metrics.record_elapsed(
             opt.print_overall_time,
             &opt.label,
             LABEL_STEP_OVERALL,
-            || {
-                match load_method {
-                    LoadMethod::ReadVecFill => &t.load_read_vec_fill(filename, opt),
-                    LoadMethod::VecFill => &t.load_vec_fill(filename, opt),
-                    LoadMethod::Continuous => &t.load_continuous(filename),
-                    LoadMethod::ContinuousParallel => &t.load_continuous_parallel(filename),
-                };
-            },
+            start,
         );
-        t
+        (t, report)
     }
 
     fn find(&self, prefix: &str) -> Option<FixedNode> {
-        let prefix: Vec<char> = prefix.to_lowercase().chars().collect();
+        let prefix: Vec<char> = self.normalizer.to_chars(prefix)?;
         let prefix_len = prefix.len();
-        self.find_child(prefix, prefix_len, 0)
+        self.root.find_child(prefix, prefix_len, 0)
+    }
+
+    fn all_prefix_words(&self, s: &str) -> Vec<String> {
+        let s: Vec<char> = match self.normalizer.to_chars(s) {
+            Some(s) => s,
+            None => return vec![],
+        };
+        let mut words = vec![];
+        let mut node = &self.root;
+        for (index, c) in s.iter().enumerate() {
+            match node.children.get(c) {
+                Some(child_node) => {
+                    if child_node.is_word {
+                        words.push(s[..=index].iter().collect());
+                    }
+                    node = child_node;
+                }
+                None => break,
+            }
+        }
+        words
+    }
+
+    fn enable_suffix_index(&mut self) {
+        NoParentTrie::enable_suffix_index(self);
+    }
+
+    fn disable_suffix_index(&mut self) {
+        NoParentTrie::disable_suffix_index(self);
+    }
+
+    fn has_suffix_index(&self) -> bool {
+        NoParentTrie::has_suffix_index(self)
+    }
+
+    fn words_with_suffix(&self, suffix: &str) -> Vec<String> {
+        NoParentTrie::words_with_suffix(self, suffix)
+    }
+
+    fn words_with_prefix_and_suffix(&self, prefix: &str, suffix: &str) -> Vec<String> {
+        NoParentTrie::words_with_prefix_and_suffix(self, prefix, suffix)
     }
 
     fn to_fixed_node(&self) -> FixedNode {
-        FixedNode {
-            c: self.c,
-            prefix: "".to_owned(),
-            depth: self.depth,
-            is_word: self.is_word,
-            child_count: self.children.len(),
-            node_count: self.node_count(),
-            word_count: self.word_count(),
-            height: self.height(),
+        self.root.to_fixed_node()
+    }
+
+    fn to_dot_with_options(&self, root_prefix: &str, opt: &DotOptions) -> Option<String> {
+        let prefix: Vec<char> = self.normalizer.to_chars(root_prefix)?;
+        let node = self.find_node(&prefix)?;
+        let mut w = DotWriter::new(opt);
+        node.write_graph(&mut w, &mut prefix.iter().collect(), 0);
+        Some(w.finish())
+    }
+
+    fn to_d3_hierarchy(&self, root_prefix: &str, opt: &D3Options) -> Option<serde_json::Value> {
+        let prefix: Vec<char> = self.normalizer.to_chars(root_prefix)?;
+        let node = self.find_node(&prefix)?;
+        let mut w = D3Writer::new(opt);
+        node.write_graph(&mut w, &mut prefix.iter().collect(), 0);
+        Some(w.finish())
+    }
+
+    fn stats(&self) -> TrieStats {
+        let mut w = StatsWriter::new();
+        self.root.write_graph(&mut w, &mut String::new(), 0);
+        w.finish()
+    }
+
+    fn heap_size(&self) -> HeapSize {
+        let mut h = HeapSize::default();
+        h.add_normalizer(&self.normalizer);
+        if let Some(suffix_index) = &self.suffix_index {
+            let mut suffix_h = HeapSize::default();
+            suffix_h.node_bytes += mem::size_of::<NoParentLetterTrie>();
+            suffix_index.root.add_heap_size(&mut suffix_h);
+            h.add_bytes(&suffix_h);
         }
+        self.root.add_heap_size(&mut h);
+        h
+    }
+
+    fn validate(&self) -> Vec<TrieViolation> {
+        let mut v = TrieValidator::default();
+        self.root.validate_node(None, &mut String::new(), &mut v);
+        v.finish()
     }
 }
 
@@ -450,18 +981,157 @@ mod tests {
     fn small_root() {
         let dataset = Dataset::TestSmallUnsorted;
         let t = NoParentLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
         assert_small_root(&t.to_fixed_node());
     }
 
+    #[test]
+    fn generic_keys() {
+        let mut t: NoParentTrie<String> = NoParentTrie::new();
+        t.add_key("New York");
+        t.add_key("new york city");
+        assert!(t.contains_word("new  york"));
+        assert!(t.contains_word("NEW YORK CITY"));
+        assert!(!t.contains_word("york"));
+        assert_eq!(t.root.c, String::new());
+        assert_eq!(t.node_count(), 4);
+        assert_eq!(t.word_count(), 2);
+    }
+
+    #[test]
+    fn validate() {
+        let dataset = Dataset::TestMediumSorted;
+        for load_method in LoadMethod::ALL.iter() {
+            let t =
+                NoParentLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), load_method);
+            assert_eq!(t.validate(), vec![]);
+        }
+
+        let mut t = NoParentLetterTrie::new();
+        t.add_word("ab");
+        t.root.c = '*';
+        let a = t.root.children.get_mut(&'a').unwrap();
+        a.c = 'x';
+        a.children.get_mut(&'b').unwrap().is_word = false;
+        let violations: Vec<(String, TrieViolationKind)> = t
+            .validate()
+            .into_iter()
+            .map(|violation| (violation.prefix, violation.kind))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                ("".to_owned(), TrieViolationKind::RootSentinel { c: '*' }),
+                (
+                    "a".to_owned(),
+                    TrieViolationKind::KeyMismatch { key: 'a', c: 'x' }
+                ),
+                ("ab".to_owned(), TrieViolationKind::DeadEnd),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefix_words() {
+        fn prefix_words<T: LetterTrie>() -> Vec<(Vec<String>, Option<String>)> {
+            let dataset = Dataset::TestSmallUnsorted;
+            let t = T::from_file(
+                dataset.filename(),
+                dataset.is_sorted(),
+                &LoadMethod::Continuous,
+            );
+            ["Andy", "crossed", "azure", "xyz", ""]
+                .iter()
+                .map(|s| (t.all_prefix_words(s), t.longest_prefix_word(s)))
+                .collect()
+        }
+
+        let words = prefix_words::<NoParentLetterTrie>();
+        assert_eq!(words[0].0, vec!["a", "an", "and"]);
+        assert_eq!(words[1].1, Some("crossed".to_owned()));
+        assert_eq!(words[3], (vec![], None));
+        assert_eq!(words[4], (vec![], None));
+        assert_eq!(words, prefix_words::<BaseLetterTrie>());
+        assert_eq!(words, prefix_words::<LetterTrieSet>());
+    }
+
+    #[test]
+    fn suffix_index_stays_in_sync() {
+        let mut t = NoParentLetterTrie::new();
+        t.add_word("geology");
+        t.enable_suffix_index();
+        t.add_word("biology");
+        t.add_word("logo");
+        assert_eq!(t.words_with_suffix("logy"), vec!["biology", "geology"]);
+        assert!(t.remove_key("geology"));
+        assert!(!t.remove_key("geology"));
+        assert_eq!(t.words_with_suffix("logy"), vec!["biology"]);
+        assert_eq!(t.node_count(), 12);
+
+        let mut other = NoParentLetterTrie::new();
+        other.add_word("ecology");
+        other.add_word("aba");
+        t.merge(other);
+        assert_eq!(t.words_with_suffix("ology"), vec!["biology", "ecology"]);
+        assert_eq!(t.words_with_prefix_and_suffix("ab", "ba"), vec!["aba"]);
+        assert_eq!(t.words_with_prefix_and_suffix("lo", "go"), vec!["logo"]);
+        assert!(t.validate().is_empty());
+
+        let with_index_bytes = t.heap_size().total_bytes();
+        t.disable_suffix_index();
+        assert!(!t.has_suffix_index());
+        assert!(t.heap_size().total_bytes() < with_index_bytes);
+        assert_eq!(t.words_with_suffix("ology"), vec!["biology", "ecology"]);
+    }
+
+    #[test]
+    fn suffix_index_all_load_methods() {
+        fn suffix_words<T: LetterTrie>(load_method: &LoadMethod) -> Vec<Vec<String>> {
+            let dataset = Dataset::TestSmallUnsorted;
+            let mut t = T::from_file(dataset.filename(), dataset.is_sorted(), load_method);
+            let mut words = vec![t.words_with_suffix("ed")];
+            t.enable_suffix_index();
+            assert!(t.has_suffix_index());
+            words.push(t.words_with_suffix("ed"));
+            words.push(t.words_with_suffix("re"));
+            words.push(t.words_with_prefix_and_suffix("a", "n"));
+            words.push(t.words_with_prefix_and_suffix("CR", "E"));
+            words.push(t.words_with_suffix(""));
+            words.push(t.words_with_prefix_and_suffix("x", "e"));
+            words.push(t.words_with_suffix("q"));
+            words
+        }
+
+        for load_method in [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ]
+        .iter()
+        {
+            let words = suffix_words::<NoParentLetterTrie>(load_method);
+            assert_eq!(words[0], vec!["crossed"]);
+            assert_eq!(words[1], vec!["crossed"]);
+            assert_eq!(words[2], vec!["azure", "creature"]);
+            assert_eq!(words[3], vec!["an"]);
+            assert_eq!(words[4], vec!["crease", "creative", "creature"]);
+            assert_eq!(words[5].len(), WORD_COUNT_SMALL);
+            assert!(words[6].is_empty());
+            assert!(words[7].is_empty());
+            assert_eq!(words, suffix_words::<BaseLetterTrie>(load_method));
+            assert_eq!(words, suffix_words::<LetterTrieSet>(load_method));
+        }
+    }
+
     #[test]
     fn large_read_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = NoParentLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::ReadVecFill,
         );
@@ -472,7 +1142,7 @@ mod tests {
     fn large_vec_fill_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = NoParentLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::VecFill,
         );
@@ -483,7 +1153,7 @@ mod tests {
     fn large_continuous_root() {
         let dataset = Dataset::TestLargeUnsorted;
         let t = NoParentLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::Continuous,
         );
@@ -494,7 +1164,7 @@ mod tests {
     fn large_continuous_parallel_root() {
         let dataset = Dataset::TestLargeSorted;
         let t = NoParentLetterTrie::from_file(
-            &dataset.filename(),
+            dataset.filename(),
             dataset.is_sorted(),
             &LoadMethod::ContinuousParallel,
         );
@@ -506,7 +1176,7 @@ mod tests {
         let t = large_tree();
         let words = good_words();
         for word in words {
-            assert_eq!(true, t.is_word_recursive(&word));
+            assert!(t.is_word_recursive(&word));
         }
     }
 
@@ -526,7 +1196,7 @@ mod tests {
         let t = large_tree();
         let words = non_words();
         for word in words {
-            assert_eq!(false, t.is_word_recursive(&word));
+            assert!(!t.is_word_recursive(&word));
         }
     }
 
@@ -547,7 +1217,7 @@ mod tests {
         let hash_set = large_dataset_words_hash_set();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, hash_set.contains(&word));
+                assert!(hash_set.contains(&word));
             }
         });
     }
@@ -558,7 +1228,7 @@ mod tests {
         let t = large_tree();
         b.iter(|| {
             for word in words.clone() {
-                assert_eq!(true, t.is_word_recursive(&word));
+                assert!(t.is_word_recursive(&word));
             }
         });
     }
@@ -581,7 +1251,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             NoParentLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::ReadVecFill,
             );
@@ -593,7 +1263,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             NoParentLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::VecFill,
             );
@@ -605,7 +1275,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             NoParentLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::Continuous,
             );
@@ -617,7 +1287,7 @@ mod tests {
         b.iter(|| {
             let dataset = Dataset::TestMediumSorted;
             NoParentLetterTrie::from_file(
-                &dataset.filename(),
+                dataset.filename(),
                 dataset.is_sorted(),
                 &LoadMethod::ContinuousParallel,
             );
@@ -638,7 +1308,7 @@ mod tests {
         for line in BufReader::new(file).lines() {
             let line = line.unwrap();
             let line = line.trim();
-            if line.len() > 0 {
+            if !line.is_empty() {
                 v.push(line.to_string());
             }
         }
@@ -646,11 +1316,11 @@ mod tests {
     }
 
     fn good_words() -> Vec<String> {
-        words_from_file(FILENAME_GOOD_WORDS)
+        words_from_file(DATASET_REGISTRY.path(FILENAME_GOOD_WORDS))
     }
 
     fn non_words() -> Vec<String> {
-        words_from_file(FILENAME_NON_WORDS)
+        words_from_file(DATASET_REGISTRY.path(FILENAME_NON_WORDS))
     }
 
     fn large_dataset_words_hash_set() -> HashSet<String> {
@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+lazy_static! {
+    /// The normalizer used when none is given, which matches the `trim().to_lowercase()` that the tries have always
+    /// applied.
+    pub static ref DEFAULT_NORMALIZER: Normalizer = Normalizer::make_default();
+}
+
+/// The Unicode normalization form applied to each word.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnicodeForm {
+    /// Leave the characters as they are.
+    Unchanged,
+    /// Canonical composition, so "u" followed by a combining diaeresis becomes the single character "ü".
+    Nfc,
+    /// Compatibility decomposition, so "ü" becomes "u" followed by a combining diaeresis and a ligature like "ﬁ"
+    /// becomes "fi".
+    Nfkd,
+}
+
+/// What to do with a word that has characters outside of `Normalizer::whitelist`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvalidWordAction {
+    /// Leave the word out of the trie.
+    Reject,
+    /// Leave the word out of the trie and keep an `InvalidWord` naming the word and the characters that weren't
+    /// allowed in the `invalid_words` of the `BuildReport` for the load.
+    Report,
+}
+
+/// The steps that turn a line from a word file, or a word passed to a lookup function, into the characters that
+/// make up the path through a trie.
+///
+/// The same normalizer is used when adding words and when looking them up, so with case folding turned off
+/// "Polish" and "polish" are different words, while with diacritics stripped "Zürich" and "Zurich" are the same
+/// word. The steps are applied in this order:
+/// - Whitespace before and after the word is trimmed.
+/// - If `case_fold` is true the word is converted to lowercase.
+/// - Each character found in `mappings` is replaced, as in "ß" to "ss".
+/// - If `strip_diacritics` is true, accents and other combining marks are removed.
+/// - The word is converted to `unicode_form`.
+/// - If there's a `whitelist`, a word with any character not in the whitelist is handled according to
+///   `invalid_word_action`.
+///
+/// If `preserve_surface_forms` is true a LetterTrieMap also keeps each word as it was spelled before
+/// normalization (apart from trimming), so a lookup of "zurich" can return "Zürich".
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut normalizer = Normalizer::make_default();
+/// normalizer.strip_diacritics = true;
+/// normalizer.mappings.insert('ß', "ss".to_owned());
+/// normalizer.whitelist = Some("abcdefghijklmnopqrstuvwxyz".chars().collect());
+///
+/// assert_eq!(normalizer.normalize(" Zürich "), Ok("zurich".to_owned()));
+/// assert_eq!(normalizer.normalize("Straße"), Ok("strasse".to_owned()));
+/// assert!(normalizer.normalize("route 66").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normalizer {
+    pub case_fold: bool,
+    pub unicode_form: UnicodeForm,
+    pub strip_diacritics: bool,
+    pub mappings: BTreeMap<char, String>,
+    pub whitelist: Option<BTreeSet<char>>,
+    pub invalid_word_action: InvalidWordAction,
+    /// Keep the original spelling of each word at its node. Only LetterTrieMap stores surface forms; the other
+    /// implementations ignore this setting.
+    pub preserve_surface_forms: bool,
+}
+
+/// A word that was turned away because it had characters that aren't in the normalizer's whitelist.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidWord {
+    /// The word after the other normalization steps.
+    pub word: String,
+    /// The characters that aren't in the whitelist, in the order they appear in the word.
+    pub invalid_chars: Vec<char>,
+}
+
+impl fmt::Display for InvalidWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid word \"{}\": characters {:?} are not allowed.",
+            self.word, self.invalid_chars
+        )
+    }
+}
+
+// What the normalizer made of one line of a word file.
+pub(crate) enum LoadedLine {
+    Word(Vec<char>),
+    Blank,
+    Rejected(InvalidWord),
+}
+
+impl Normalizer {
+    /// Create a normalizer that trims whitespace and converts to lowercase, and nothing else.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let normalizer = Normalizer::make_default();
+    /// assert_eq!(normalizer.normalize("  Polish "), Ok("polish".to_owned()));
+    /// ```
+    pub fn make_default() -> Self {
+        Self {
+            case_fold: true,
+            unicode_form: UnicodeForm::Unchanged,
+            strip_diacritics: false,
+            mappings: BTreeMap::new(),
+            whitelist: None,
+            invalid_word_action: InvalidWordAction::Reject,
+            preserve_surface_forms: false,
+        }
+    }
+
+    /// Create a normalizer that folds case and strips diacritics for the trie walk but keeps each word's original
+    /// spelling, so "iphone" finds "iPhone" and "zurich" finds "Zürich".
+    pub fn make_folded_preserving() -> Self {
+        Self {
+            unicode_form: UnicodeForm::Nfc,
+            strip_diacritics: true,
+            preserve_surface_forms: true,
+            ..Self::make_default()
+        }
+    }
+
+    /// Create a normalizer that only trims whitespace so that words keep their case and accents.
+    pub fn make_exact() -> Self {
+        Self {
+            case_fold: false,
+            ..Self::make_default()
+        }
+    }
+
+    /// Create a normalizer that folds words down to the 26 lowercase letters a to z. Accents are stripped, "ß"
+    /// becomes "ss", and any word that still has a character outside of a to z (such as a digit, a space or an
+    /// apostrophe) is left out and listed in the `BuildReport`.
+    pub fn make_ascii_letters() -> Self {
+        let mut mappings = BTreeMap::new();
+        mappings.insert('ß', "ss".to_owned());
+        mappings.insert('æ', "ae".to_owned());
+        mappings.insert('œ', "oe".to_owned());
+        Self {
+            case_fold: true,
+            unicode_form: UnicodeForm::Nfc,
+            strip_diacritics: true,
+            mappings,
+            whitelist: Some((b'a'..=b'z').map(char::from).collect()),
+            invalid_word_action: InvalidWordAction::Report,
+            preserve_surface_forms: false,
+        }
+    }
+
+    /// Apply the normalization steps to a word. A blank word becomes an empty string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an InvalidWord if there's a whitelist and the normalized word has a character that's not in it.
+    pub fn normalize(&self, word: &str) -> Result<String, InvalidWord> {
+        let word = word.trim();
+        let word = if self.case_fold {
+            word.to_lowercase()
+        } else {
+            word.to_owned()
+        };
+        let word = if self.mappings.is_empty() {
+            word
+        } else {
+            let mut mapped = String::with_capacity(word.len());
+            for c in word.chars() {
+                match self.mappings.get(&c) {
+                    Some(replacement) => mapped.push_str(replacement),
+                    None => mapped.push(c),
+                }
+            }
+            mapped
+        };
+        let word = if self.strip_diacritics {
+            word.nfd().filter(|c| !is_combining_mark(*c)).collect()
+        } else {
+            word
+        };
+        let word: String = match self.unicode_form {
+            UnicodeForm::Unchanged => word,
+            UnicodeForm::Nfc => word.nfc().collect(),
+            UnicodeForm::Nfkd => word.nfkd().collect(),
+        };
+        if let Some(whitelist) = &self.whitelist {
+            let invalid_chars: Vec<char> =
+                word.chars().filter(|c| !whitelist.contains(c)).collect();
+            if !invalid_chars.is_empty() {
+                return Err(InvalidWord {
+                    word,
+                    invalid_chars,
+                });
+            }
+        }
+        Ok(word)
+    }
+
+    /// Normalize a word or a prefix that's being looked up in a trie. A word that's not valid can't be in the trie
+    /// so the result is None, while a blank word is an empty vector which matches the root of the trie.
+    pub fn to_chars(&self, word: &str) -> Option<Vec<char>> {
+        self.normalize(word).ok().map(|word| word.chars().collect())
+    }
+
+    /// Normalize a word that's being added to a trie. A word that's not valid is left out and the result is None,
+    /// as it is for a blank word. Only the loaders that return a `BuildReport` keep track of the invalid words.
+    pub fn load_chars(&self, word: &str) -> Option<Vec<char>> {
+        match self.load_line(word) {
+            LoadedLine::Word(v) => Some(v),
+            LoadedLine::Blank | LoadedLine::Rejected(_) => None,
+        }
+    }
+
+    // The same as load_chars() while telling a blank line from one the normalizer rejected.
+    pub(crate) fn load_line(&self, line: &str) -> LoadedLine {
+        match self.normalize(line) {
+            Ok(word) if !word.is_empty() => LoadedLine::Word(word.chars().collect()),
+            Ok(_) => LoadedLine::Blank,
+            Err(invalid_word) => LoadedLine::Rejected(invalid_word),
+        }
+    }
+
+    /// Returns true if this normalizer does exactly what `make_default()` does.
+    pub fn is_default(&self) -> bool {
+        *self == *DEFAULT_NORMALIZER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_trim_to_lowercase() {
+        let normalizer = Normalizer::make_default();
+        for word in ["  Polish", "polish", "ZÜRICH ", "Straße", "route 66", ""] {
+            assert_eq!(normalizer.normalize(word), Ok(word.trim().to_lowercase()));
+        }
+        assert!(normalizer.is_default());
+        assert_eq!(normalizer.to_chars("  "), Some(vec![]));
+        assert_eq!(normalizer.load_chars("  "), None);
+    }
+
+    #[test]
+    fn exact() {
+        let normalizer = Normalizer::make_exact();
+        assert_eq!(normalizer.normalize(" Polish"), Ok("Polish".to_owned()));
+        assert!(!normalizer.is_default());
+    }
+
+    #[test]
+    fn unicode_forms() {
+        let composed = "\u{00fc}";
+        let decomposed = "u\u{0308}";
+        let mut normalizer = Normalizer::make_default();
+        normalizer.unicode_form = UnicodeForm::Nfc;
+        assert_eq!(normalizer.normalize(decomposed), Ok(composed.to_owned()));
+        normalizer.unicode_form = UnicodeForm::Nfkd;
+        assert_eq!(normalizer.normalize(composed), Ok(decomposed.to_owned()));
+        assert_eq!(normalizer.normalize("\u{fb01}ne"), Ok("fine".to_owned()));
+    }
+
+    #[test]
+    fn ascii_letters() {
+        let normalizer = Normalizer::make_ascii_letters();
+        assert_eq!(normalizer.normalize("Zürich"), Ok("zurich".to_owned()));
+        assert_eq!(
+            normalizer
+                .normalize("Crème Brûlée")
+                .unwrap_err()
+                .invalid_chars,
+            vec![' ']
+        );
+        assert_eq!(
+            normalizer.normalize("STRASSE"),
+            normalizer.normalize("Straße")
+        );
+        assert_eq!(normalizer.normalize("Æon"), Ok("aeon".to_owned()));
+        assert_eq!(
+            normalizer.normalize("r2d2").unwrap_err(),
+            InvalidWord {
+                word: "r2d2".to_owned(),
+                invalid_chars: vec!['2', '2'],
+            }
+        );
+        assert_eq!(normalizer.load_chars("r2d2"), None);
+    }
+}
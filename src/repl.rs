@@ -0,0 +1,418 @@
+use crate::*;
+
+const DEFAULT_WORD_LIMIT: usize = 20;
+const DEFAULT_FUZZY_DISTANCE: usize = 1;
+
+/// The commands understood by `TrieExplorer::execute()`, used for tab completion of the first word on a line.
+pub const REPL_COMMANDS: [&str; 13] = [
+    "add", "cd", "exit", "find", "fuzzy", "help", "ls", "pwd", "quit", "rm", "stat", "up", "words",
+];
+
+const HELP: &str = "\
+cd <letters>    Move the cursor down by some letters. \"cd /<letters>\" starts from the root, \"cd ..\" goes up
+                one letter and \"cd\" alone goes back to the root.
+up [n]          Move the cursor up by one or n letters.
+pwd             Show the letters from the root to the cursor.
+ls              List the children of the cursor with their word and node counts. A \"*\" marks a word.
+words [n]       List the first 20 or n words below the cursor.
+stat            Show the FixedNode for the cursor.
+find <word>     Look up a word or prefix from the root.
+fuzzy <word> [distance]
+                List the words within an edit distance of a word, 1 by default.
+add <word>...   Add words.
+rm <word>...    Remove words.
+quit            Leave. Ctrl-D also works.";
+
+/// What happened when a line was executed.
+#[derive(Debug, PartialEq)]
+pub enum ReplResponse {
+    /// Text to show, which may be empty.
+    Output(String),
+    /// The user asked to leave.
+    Quit,
+}
+
+/// The state behind the interactive REPL: a trie that's loaded once plus a cursor pointing at one of its nodes,
+/// like a current directory in a shell.
+///
+/// `execute()` runs one line and returns the output as a string rather than printing it, and `complete()` gives
+/// tab completions that come from the trie itself, so the same logic can be driven by a line editor or by a test.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallSorted;
+/// let trie = LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+/// let mut explorer = TrieExplorer::new(trie);
+///
+/// explorer.execute("cd cr");
+/// assert_eq!(explorer.prompt(), "cr> ");
+/// assert_eq!(
+///     explorer.execute("ls"),
+///     ReplResponse::Output("e  4 words, 13 nodes\no  2 words, 5 nodes".to_owned())
+/// );
+///
+/// // Completion follows the trie down to the next point where it branches.
+/// assert_eq!(explorer.complete("cd o", 4), (3, vec!["oss".to_owned()]));
+/// assert_eq!(explorer.execute("quit"), ReplResponse::Quit);
+/// ```
+pub struct TrieExplorer {
+    trie: LetterTrieSet,
+    cursor: Vec<char>,
+}
+
+impl TrieExplorer {
+    pub fn new(trie: LetterTrieSet) -> Self {
+        Self {
+            trie,
+            cursor: vec![],
+        }
+    }
+
+    pub fn trie(&self) -> &LetterTrieSet {
+        &self.trie
+    }
+
+    /// The letters from the root to the cursor.
+    pub fn cursor(&self) -> String {
+        self.cursor.iter().collect()
+    }
+
+    /// The prompt to show before each line, which includes the cursor.
+    pub fn prompt(&self) -> String {
+        format!("{}> ", self.cursor())
+    }
+
+    /// Run one line. Blank lines do nothing, and mistakes such as an unknown command or a missing argument are
+    /// reported in the output.
+    pub fn execute(&mut self, line: &str) -> ReplResponse {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let output = match args.split_first() {
+            None => String::new(),
+            Some((command, args)) => match *command {
+                "cd" => self.cd(args.first().cloned()),
+                "up" => match parse_count(args.first(), 1) {
+                    Ok(n) => {
+                        let len = self.cursor.len().saturating_sub(n);
+                        self.cursor.truncate(len);
+                        String::new()
+                    }
+                    Err(message) => message,
+                },
+                "pwd" => format!("\"{}\"", self.cursor()),
+                "ls" => self.ls(),
+                "words" => match parse_count(args.first(), DEFAULT_WORD_LIMIT) {
+                    Ok(n) => self.words(n),
+                    Err(message) => message,
+                },
+                "stat" => format!("{:#?}", self.trie.get_fixed_node(&self.cursor).unwrap()),
+                "find" => match args.first() {
+                    Some(word) => self.find(word),
+                    None => "Usage: find <word>".to_owned(),
+                },
+                "fuzzy" => match (
+                    args.first(),
+                    parse_count(args.get(1), DEFAULT_FUZZY_DISTANCE),
+                ) {
+                    (Some(word), Ok(distance)) => self.fuzzy(word, distance),
+                    (None, _) => "Usage: fuzzy <word> [distance]".to_owned(),
+                    (_, Err(message)) => message,
+                },
+                "add" => self.add(args),
+                "rm" => self.rm(args),
+                "help" => HELP.to_owned(),
+                "quit" | "exit" => return ReplResponse::Quit,
+                _ => format!("Unknown command \"{}\". Type \"help\" for a list.", command),
+            },
+        };
+        ReplResponse::Output(output)
+    }
+
+    fn cd(&mut self, arg: Option<&str>) -> String {
+        let arg = match arg {
+            None | Some("/") => {
+                self.cursor.clear();
+                return String::new();
+            }
+            Some("..") => {
+                self.cursor.pop();
+                return String::new();
+            }
+            Some(arg) => arg,
+        };
+        let (mut v, letters) = match arg.strip_prefix('/') {
+            Some(letters) => (vec![], letters),
+            None => (self.cursor.clone(), arg),
+        };
+        match self.trie.normalizer().to_chars(letters) {
+            Some(chars) => v.extend(chars),
+            None => return format!("\"{}\" can't be in the trie.", letters),
+        }
+        if self.trie.get_node(&v).is_some() {
+            self.cursor = v;
+            String::new()
+        } else {
+            format!("No words start with \"{}\".", v.iter().collect::<String>())
+        }
+    }
+
+    fn ls(&self) -> String {
+        let node = self.trie.get_node(&self.cursor).unwrap();
+        node.children()
+            .iter()
+            .map(|(c, child_node)| {
+                format!(
+                    "{}{}  {} words, {} nodes",
+                    c,
+                    if child_node.value().is_some() {
+                        "*"
+                    } else {
+                        ""
+                    },
+                    format_count(child_node.word_count()),
+                    format_count(child_node.node_count())
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn words(&self, limit: usize) -> String {
+        let total = self.trie.prefix_word_count(&self.cursor[..]);
+        let mut lines: Vec<String> = self
+            .trie
+            .iter_prefix(&self.cursor[..])
+            .take(limit)
+            .map(|(word, _)| word)
+            .collect();
+        if total > lines.len() {
+            lines.push(format!(
+                "... and {} more",
+                format_count(total - lines.len())
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn find(&self, word: &str) -> String {
+        match self.trie.find(word) {
+            Some(node) => format!(
+                "{}; {} words start with it; height {}",
+                if node.is_word { "word" } else { "prefix only" },
+                format_count(node.word_count),
+                node.height
+            ),
+            None => "not found".to_owned(),
+        }
+    }
+
+    fn fuzzy(&self, word: &str, max_distance: usize) -> String {
+        self.trie
+            .fuzzy_search(word, max_distance)
+            .into_iter()
+            .map(|(word, distance, _)| format!("{}\t{}", word, distance))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn add(&mut self, words: &[&str]) -> String {
+        let len_before = self.trie.len();
+        for word in words {
+            self.trie.insert(*word, ());
+        }
+        format!(
+            "Added {} of {} words. The trie has {} words.",
+            self.trie.len() - len_before,
+            words.len(),
+            format_count(self.trie.len())
+        )
+    }
+
+    fn rm(&mut self, words: &[&str]) -> String {
+        let removed_count = words
+            .iter()
+            .filter(|word| self.trie.remove(**word).is_some())
+            .count();
+        // Removing a word also removes the nodes that no longer lead to any word, which may include the cursor.
+        while self.trie.get_node(&self.cursor).is_none() {
+            self.cursor.pop();
+        }
+        format!(
+            "Removed {} of {} words. The trie has {} words.",
+            removed_count,
+            words.len(),
+            format_count(self.trie.len())
+        )
+    }
+
+    /// Find the tab completions for the word that ends at `pos` in the line. Returns the position where that word
+    /// starts and the possible replacements for it.
+    ///
+    /// The first word on the line completes to a command. After that the words complete from the trie: relative
+    /// to the cursor for `cd` (or from the root if the word starts with "/") and from the root for everything else.
+    /// Each candidate follows the trie down through any nodes with only one child, so a long unbranched run of
+    /// letters is filled in with one press of Tab.
+    pub fn complete(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before = &line[..pos];
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let partial = &before[start..];
+        if before[..start].trim().is_empty() {
+            let commands = REPL_COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(partial))
+                .map(|command| command.to_string())
+                .collect();
+            return (start, commands);
+        }
+        let command = before.split_whitespace().next().unwrap();
+        let (base, letters, shown_prefix) = match (command, partial.strip_prefix('/')) {
+            ("cd", None) => (self.cursor.clone(), partial, ""),
+            ("cd", Some(letters)) => (vec![], letters, "/"),
+            _ => (vec![], partial, ""),
+        };
+        let letters = match self.trie.normalizer().to_chars(letters) {
+            Some(letters) => letters,
+            None => return (start, vec![]),
+        };
+        let mut v = base.clone();
+        v.extend(letters.iter());
+        let node = match self.trie.get_node(&v) {
+            Some(node) => node,
+            None => return (start, vec![]),
+        };
+        let mut candidates = vec![];
+        if node.children().is_empty() || node.value().is_some() {
+            candidates.push(letters.clone());
+        }
+        for (c, mut child_node) in node.children().iter() {
+            let mut candidate = letters.clone();
+            candidate.push(*c);
+            while child_node.value().is_none() && child_node.children().len() == 1 {
+                let (c, grandchild_node) = child_node.children().iter().next().unwrap();
+                candidate.push(*c);
+                child_node = grandchild_node;
+            }
+            candidates.push(candidate);
+        }
+        let candidates = candidates
+            .into_iter()
+            .map(|candidate| format!("{}{}", shown_prefix, candidate.iter().collect::<String>()))
+            .collect();
+        (start, candidates)
+    }
+}
+
+// Parse an optional count such as the n in "words n".
+fn parse_count(arg: Option<&&str>, default: usize) -> Result<usize, String> {
+    match arg {
+        None => Ok(default),
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("\"{}\" isn't a number.", arg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_explorer() -> TrieExplorer {
+        let dataset = Dataset::TestSmallUnsorted;
+        TrieExplorer::new(LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        ))
+    }
+
+    fn output(explorer: &mut TrieExplorer, line: &str) -> String {
+        match explorer.execute(line) {
+            ReplResponse::Output(text) => text,
+            ReplResponse::Quit => panic!("Unexpected quit."),
+        }
+    }
+
+    #[test]
+    fn navigation() {
+        let mut e = small_explorer();
+        assert_eq!(
+            output(&mut e, "ls"),
+            "a*  4 words, 7 nodes\nc  6 words, 20 nodes"
+        );
+        assert_eq!(output(&mut e, "cd CRE"), "");
+        assert_eq!(e.cursor(), "cre");
+        assert_eq!(output(&mut e, "cd ature"), "");
+        assert_eq!(output(&mut e, "up 4"), "");
+        assert_eq!(output(&mut e, "pwd"), "\"crea\"");
+        assert_eq!(output(&mut e, "cd x"), "No words start with \"creax\".");
+        assert_eq!(output(&mut e, "cd .."), "");
+        assert_eq!(
+            output(&mut e, "words 2"),
+            "crease\ncreative\n... and 2 more"
+        );
+        assert_eq!(output(&mut e, "cd /an"), "");
+        assert!(output(&mut e, "stat").contains("word_count: 2"));
+        assert_eq!(output(&mut e, "up 10"), "");
+        assert_eq!(e.prompt(), "> ");
+        assert_eq!(output(&mut e, ""), "");
+        assert!(output(&mut e, "jump").starts_with("Unknown command"));
+        assert_eq!(output(&mut e, "up x"), "\"x\" isn't a number.");
+        assert_eq!(e.execute("exit"), ReplResponse::Quit);
+    }
+
+    #[test]
+    fn search_and_edit() {
+        let mut e = small_explorer();
+        assert_eq!(
+            output(&mut e, "find cre"),
+            "prefix only; 4 words start with it; height 6"
+        );
+        assert_eq!(output(&mut e, "find cru"), "not found");
+        assert_eq!(output(&mut e, "fuzzy crose"), "cross\t1");
+        assert_eq!(output(&mut e, "fuzzy"), "Usage: fuzzy <word> [distance]");
+        assert_eq!(
+            output(&mut e, "add crate Cross crater"),
+            "Added 2 of 3 words. The trie has 12 words."
+        );
+        output(&mut e, "cd crossed");
+        assert_eq!(
+            output(&mut e, "rm crossed nothing"),
+            "Removed 1 of 2 words. The trie has 11 words."
+        );
+        assert_eq!(e.cursor(), "cross");
+    }
+
+    #[test]
+    fn completion() {
+        let mut e = small_explorer();
+        assert_eq!(
+            e.complete("f", 1),
+            (0, vec!["find".to_owned(), "fuzzy".to_owned()])
+        );
+        assert_eq!(
+            e.complete("find cr", 7),
+            (5, vec!["crea".to_owned(), "cross".to_owned()])
+        );
+        assert_eq!(
+            e.complete("find crea", 9),
+            (5, vec!["crease".to_owned(), "creat".to_owned()])
+        );
+        // "cross" is a word and can be finished as it is.
+        assert_eq!(
+            e.complete("find cross", 10),
+            (5, vec!["cross".to_owned(), "crossed".to_owned()])
+        );
+        assert_eq!(e.complete("find x", 6), (5, vec![]));
+        output(&mut e, "cd cr");
+        assert_eq!(e.complete("cd o", 4), (3, vec!["oss".to_owned()]));
+        assert_eq!(
+            e.complete("cd /a", 5),
+            (
+                3,
+                vec!["/a".to_owned(), "/an".to_owned(), "/azure".to_owned()]
+            )
+        );
+    }
+}
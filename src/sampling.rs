@@ -0,0 +1,280 @@
+use std::ops::{Bound, RangeBounds};
+
+use rand::Rng;
+
+use crate::trie_map::{TrieMapIterator, TrieMapNode};
+use crate::*;
+
+impl<K: KeyElement, V> TrieMap<K, V> {
+    /// Pick a word at random with every word equally likely, along with its value. Returns None if the trie is
+    /// empty.
+    ///
+    /// This picks a random position and descends from the root to the word at that position, choosing each child
+    /// with probability proportional to the number of words below it as in `select()`. After `freeze()` the word
+    /// counts are cached so each word takes time proportional to its length times the number of children per node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let mut trie = LetterTrieSet::from_file(
+    ///     dataset.filename(),
+    ///     dataset.is_sorted(),
+    ///     &LoadMethod::Continuous,
+    /// );
+    /// trie.freeze();
+    ///
+    /// let mut rng = StdRng::seed_from_u64(1);
+    /// let (word, _) = trie.random_word(&mut rng).unwrap();
+    /// assert!(trie.contains_word(&word));
+    ///
+    /// let (word, _) = trie.random_word_with_prefix(&mut rng, "cr").unwrap();
+    /// assert!(word.starts_with("cr"));
+    ///
+    /// let (word, _) = trie.random_word_with_length(&mut rng, 2..=3).unwrap();
+    /// assert!(word == "an" || word == "and");
+    /// ```
+    pub fn random_word<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(K::Key, &V)> {
+        match self.len() {
+            0 => None,
+            word_count => self.select(rng.gen_range(0, word_count)),
+        }
+    }
+
+    /// Pick a word that starts with a prefix at random with every such word equally likely. The prefix itself can
+    /// be picked if it's a word. Returns None if no word starts with the prefix.
+    pub fn random_word_with_prefix<R: Rng + ?Sized, Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        rng: &mut R,
+        prefix: &Q,
+    ) -> Option<(K::Key, &V)> {
+        // The words with the prefix are next to each other in sorted order, starting at the prefix's rank.
+        match self.prefix_word_count(prefix) {
+            0 => None,
+            word_count => {
+                let first = self.rank(prefix).unwrap();
+                self.select(first + rng.gen_range(0, word_count))
+            }
+        }
+    }
+
+    /// Pick a word whose length, counted in key elements, is within a range such as `5..=8`, with every such word
+    /// equally likely. Returns None if there's no word of a suitable length.
+    ///
+    /// The word counts cached by `freeze()` don't say how long the words are, so each call visits every node down
+    /// to the longest length in the range.
+    pub fn random_word_with_length<R: Rng + ?Sized, B: RangeBounds<usize>>(
+        &self,
+        rng: &mut R,
+        lengths: B,
+    ) -> Option<(K::Key, &V)> {
+        match self.root().word_count_with_length(0, &lengths) {
+            0 => None,
+            word_count => {
+                let mut n = rng.gen_range(0, word_count);
+                let mut v = vec![];
+                self.root().select_with_length(0, &lengths, &mut n, &mut v)
+            }
+        }
+    }
+}
+
+impl<K: KeyElement, V> TrieMapNode<K, V> {
+    fn word_count_with_length<B: RangeBounds<usize>>(&self, depth: usize, lengths: &B) -> usize {
+        let mut count = if self.value().is_some() && lengths.contains(&depth) {
+            1
+        } else {
+            0
+        };
+        if !is_past_max_length(depth + 1, lengths) {
+            for child_node in self.children().values() {
+                count += child_node.word_count_with_length(depth + 1, lengths);
+            }
+        }
+        count
+    }
+
+    // Find the nth word of a suitable length in sorted order, counting down n along the way.
+    fn select_with_length<B: RangeBounds<usize>>(
+        &self,
+        depth: usize,
+        lengths: &B,
+        n: &mut usize,
+        v: &mut Vec<K>,
+    ) -> Option<(K::Key, &V)> {
+        if let Some(value) = self.value() {
+            if lengths.contains(&depth) {
+                if *n == 0 {
+                    return Some((K::make_key(v), value));
+                }
+                *n -= 1;
+            }
+        }
+        if !is_past_max_length(depth + 1, lengths) {
+            for (c, child_node) in self.children().iter() {
+                v.push(c.clone());
+                let found = child_node.select_with_length(depth + 1, lengths, n, v);
+                if found.is_some() {
+                    return found;
+                }
+                v.pop();
+            }
+        }
+        None
+    }
+}
+
+// Returns true if words of this length and longer are outside of the range.
+fn is_past_max_length<B: RangeBounds<usize>>(len: usize, lengths: &B) -> bool {
+    match lengths.end_bound() {
+        Bound::Included(max) => len > *max,
+        Bound::Excluded(max) => len >= *max,
+        Bound::Unbounded => false,
+    }
+}
+
+impl<K: KeyElement, V: Frequency> TrieMap<K, V> {
+    /// Pick a word at random with the chance of each word proportional to its frequency, so in a map of word counts
+    /// a word seen twice as often is picked twice as often. Words with a frequency of zero are never picked.
+    /// Returns None if the total of the frequencies is zero.
+    ///
+    /// The frequencies aren't cached so each call goes through all of the words twice, once to add up the
+    /// frequencies and once to find the word at a random point in that total.
+    pub fn random_word_weighted<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(K::Key, &V)> {
+        Self::pick_weighted(rng, || self.iter())
+    }
+
+    /// Pick a word that starts with a prefix at random with the chance of each word proportional to its
+    /// frequency, as with `random_word_weighted()`.
+    pub fn random_word_weighted_with_prefix<R: Rng + ?Sized, Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        rng: &mut R,
+        prefix: &Q,
+    ) -> Option<(K::Key, &V)> {
+        Self::pick_weighted(rng, || self.iter_prefix(prefix))
+    }
+
+    fn pick_weighted<'a, R, F>(rng: &mut R, make_iter: F) -> Option<(K::Key, &'a V)>
+    where
+        K: 'a,
+        V: 'a,
+        R: Rng + ?Sized,
+        F: Fn() -> TrieMapIterator<'a, K, V>,
+    {
+        let total_frequency: usize = make_iter().map(|(_, value)| value.frequency()).sum();
+        if total_frequency == 0 {
+            return None;
+        }
+        let mut n = rng.gen_range(0, total_frequency);
+        for (key, value) in make_iter() {
+            let frequency = value.frequency();
+            if n < frequency {
+                return Some((key, value));
+            }
+            n -= frequency;
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::BTreeMap;
+
+    const FILENAME_SMALL_FREQUENCIES: &str = "english_words_10_frequencies.txt";
+
+    fn small_trie() -> LetterTrieSet {
+        let dataset = Dataset::TestSmallUnsorted;
+        let mut t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+        );
+        t.freeze();
+        t
+    }
+
+    // Count how many times each word is picked.
+    fn sample_counts<F: FnMut(&mut StdRng) -> Option<String>>(
+        sample_count: usize,
+        mut f: F,
+    ) -> BTreeMap<String, usize> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = BTreeMap::new();
+        for _ in 0..sample_count {
+            *counts.entry(f(&mut rng).unwrap()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn uniform() {
+        let t = small_trie();
+        let counts = sample_counts(10_000, |rng| t.random_word(rng).map(|(word, _)| word));
+        assert_eq!(counts.len(), WORD_COUNT_SMALL);
+        for count in counts.values() {
+            assert!(*count > 800 && *count < 1_200, "{:?}", counts);
+        }
+        assert!(LetterTrieSet::new()
+            .random_word(&mut StdRng::seed_from_u64(1))
+            .is_none());
+    }
+
+    #[test]
+    fn with_prefix_and_length() {
+        let t = small_trie();
+        let counts = sample_counts(1_000, |rng| {
+            t.random_word_with_prefix(rng, "CROSS")
+                .map(|(word, _)| word)
+        });
+        assert_eq!(counts.keys().collect::<Vec<_>>(), vec!["cross", "crossed"]);
+        assert!(t
+            .random_word_with_prefix(&mut StdRng::seed_from_u64(1), "b")
+            .is_none());
+
+        let counts = sample_counts(1_000, |rng| {
+            t.random_word_with_length(rng, 5..7).map(|(word, _)| word)
+        });
+        assert_eq!(
+            counts.keys().collect::<Vec<_>>(),
+            vec!["azure", "crease", "cross"]
+        );
+        let counts = sample_counts(100, |rng| {
+            t.random_word_with_length(rng, 8..).map(|(word, _)| word)
+        });
+        assert_eq!(
+            counts.keys().collect::<Vec<_>>(),
+            vec!["creative", "creature"]
+        );
+        assert!(t
+            .random_word_with_length(&mut StdRng::seed_from_u64(1), 9..)
+            .is_none());
+    }
+
+    #[test]
+    fn weighted() {
+        let mut t: LetterTrieMap<usize> =
+            LetterTrieMap::from_frequency_file(FILENAME_SMALL_FREQUENCIES);
+        t.insert("azure", 0);
+        let counts = sample_counts(10_000, |rng| {
+            t.random_word_weighted(rng).map(|(word, _)| word)
+        });
+        // "a" has a frequency of 3,000 out of 6,800.
+        let a_count = counts["a"];
+        assert!(a_count > 4_100 && a_count < 4_700, "{:?}", counts);
+        assert!(!counts.contains_key("azure"));
+
+        let counts = sample_counts(1_000, |rng| {
+            t.random_word_weighted_with_prefix(rng, "cross")
+                .map(|(word, _)| word)
+        });
+        assert!(counts["cross"] > counts["crossed"]);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+use crate::*;
+
+// The first field of the first line of a saved trie, which is followed by the format version, the word count and the
+// node count.
+const SAVED_TRIE_HEADER: &str = "letter_trie_set";
+const SAVED_TRIE_VERSION: usize = 1;
+
+impl LetterTrieSet {
+    /// Save the trie to a file that `from_saved_file()` can load without normalizing or sorting the words again.
+    ///
+    /// The file is a front-coded word list. After a header line, each word is written in alphabetical order as the
+    /// number of letters it shares with the previous word, a tab and the rest of the word. This is the same
+    /// information as a walk through the trie: the shared letters are the path back up to the parent node and the
+    /// rest are the new nodes below it. For a dictionary of similar words it's around half the size of the original
+    /// word list.
+    ///
+    /// The words are saved in their normalized form. Surface forms, the suffix index and the normalizer itself
+    /// aren't saved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be created or written.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    ///
+    /// let filename = std::env::temp_dir().join("letter_trie_small.trie");
+    /// let filename = filename.to_str().unwrap();
+    /// trie.save(filename);
+    /// assert!(LetterTrieSet::is_saved_file(filename));
+    ///
+    /// let loaded = LetterTrieSet::from_saved_file(filename);
+    /// assert_eq!(loaded.to_fixed_node(), trie.to_fixed_node());
+    /// ```
+    pub fn save(&self, filename: &str) {
+        let file = File::create(filename).expect("Error creating file.");
+        let mut writer = BufWriter::new(file);
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            SAVED_TRIE_HEADER,
+            SAVED_TRIE_VERSION,
+            self.word_count(),
+            self.node_count()
+        )
+        .expect("Error writing a line.");
+        let mut prev_word: Vec<char> = vec![];
+        for (word, _) in self.iter() {
+            let word: Vec<char> = word.chars().collect();
+            let shared_len = prev_word
+                .iter()
+                .zip(word.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let rest: String = word[shared_len..].iter().collect();
+            writeln!(writer, "{}\t{}", shared_len, rest).expect("Error writing a line.");
+            prev_word = word;
+        }
+    }
+
+    /// Load a trie saved with `save()`. The trie gets the default normalizer, which is used for lookups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be read, if it's not a saved trie, or if the number of words or nodes doesn't match
+    /// the header.
+    pub fn from_saved_file(filename: &str) -> Self {
+        let file = File::open(filename).expect("Error opening file.");
+        let mut lines = BufReader::new(file).lines();
+        let header = lines
+            .next()
+            .expect("Missing header line.")
+            .expect("Error reading a line.");
+        let (word_count, node_count) = parse_header(&header)
+            .unwrap_or_else(|| panic!("\"{}\" is not a saved trie.", filename));
+
+        let mut t = Self::new();
+        let mut word: Vec<char> = vec![];
+        for line in lines {
+            let line = line.expect("Error reading a line.");
+            let mut fields = line.splitn(2, '\t');
+            let shared_len: usize = fields
+                .next()
+                .unwrap()
+                .parse()
+                .expect("Shared length should be a number.");
+            let rest = fields.next().expect("Missing the rest of the word.");
+            assert!(shared_len <= word.len(), "Shared length is too long.");
+            word.truncate(shared_len);
+            word.extend(rest.chars());
+            t.add_from_vec_chars(&word);
+        }
+        assert_eq!(t.word_count(), word_count, "Wrong number of words.");
+        assert_eq!(t.node_count(), node_count, "Wrong number of nodes.");
+        t
+    }
+
+    /// Returns true if the file starts with the header written by `save()`, as opposed to being a plain word list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be opened.
+    pub fn is_saved_file(filename: &str) -> bool {
+        let file = File::open(filename).expect("Error opening file.");
+        match BufReader::new(file).lines().next() {
+            Some(Ok(header)) => parse_header(&header).is_some(),
+            _ => false,
+        }
+    }
+}
+
+// Get the word count and node count from a header line, or None if it's not the header of a saved trie.
+fn parse_header(line: &str) -> Option<(usize, usize)> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 4
+        || fields[0] != SAVED_TRIE_HEADER
+        || fields[1] != SAVED_TRIE_VERSION.to_string()
+    {
+        return None;
+    }
+    Some((fields[2].parse().ok()?, fields[3].parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn save_and_load() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let path = env::temp_dir().join("letter_trie_saved_trie_test.trie");
+        let filename = path.to_str().unwrap();
+        t.save(filename);
+        assert!(LetterTrieSet::is_saved_file(filename));
+        assert!(!LetterTrieSet::is_saved_file(dataset.filename()));
+        assert!(
+            fs::metadata(filename).unwrap().len() < fs::metadata(dataset.filename()).unwrap().len()
+        );
+
+        let loaded = LetterTrieSet::from_saved_file(filename);
+        assert_eq!(loaded.to_fixed_node(), t.to_fixed_node());
+        assert!(loaded.iter().eq(t.iter()));
+        // Lookups are normalized.
+        let (first_word, _) = t.select(0).unwrap();
+        assert!(loaded.contains_word(&first_word.to_uppercase()));
+        fs::remove_file(filename).unwrap();
+    }
+}
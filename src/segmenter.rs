@@ -0,0 +1,421 @@
+use std::cmp;
+
+use crate::*;
+
+/// The strategy used by `LetterTrieMap::segment()` to split unspaced text into words.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SegmentStrategy {
+    /// At each position take the longest word that starts there. This is fast but can paint itself into a corner,
+    /// as when "themen" becomes "theme" followed by an unknown "n".
+    Greedy,
+    /// Find the segmentation with the fewest unknown characters and then the fewest words, so "themen" becomes
+    /// "the" and "men".
+    FewestWords,
+    /// Find the segmentation with the fewest unknown characters and then the highest probability, treating each
+    /// word's frequency divided by the total of all frequencies as the chance of seeing that word.
+    MaxFrequency,
+}
+
+/// One piece of segmented text, either a word found in the trie or a run of characters that couldn't be covered
+/// by any word.
+#[derive(Debug, PartialEq)]
+pub struct Segment {
+    /// The text of the segment after normalization.
+    pub text: String,
+    /// The position in the normalized text, in characters, where the segment starts.
+    pub start: usize,
+    /// The position in the normalized text, in characters, just past the end of the segment.
+    pub end: usize,
+    /// True if the segment is a word in the trie, false if it's an unknown span.
+    pub is_word: bool,
+}
+
+// The best way found so far to segment the text up to a given position.
+#[derive(Clone)]
+struct Step {
+    unknown_count: usize,
+    cost: f64,
+    // The position where the last segment starts, and whether it's a word rather than a single unknown character.
+    prev: usize,
+    is_word: bool,
+}
+
+/// Splits text with no spaces into words from a `LetterTrieMap` using one strategy, as `LetterTrieMap::segment()`
+/// does. For `SegmentStrategy::MaxFrequency` the total of the word frequencies is found once when the segmenter is
+/// created rather than for every text.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+/// for (word, count) in vec![("a", 1000), ("b", 1000), ("ab", 1)] {
+///     trie.insert(word, count);
+/// }
+///
+/// let segmenter = Segmenter::new(&trie, SegmentStrategy::MaxFrequency);
+/// for text in ["ab", "ba"].iter() {
+///     assert_eq!(segmenter.segment(text).len(), 2);
+/// }
+/// ```
+pub struct Segmenter<'a, V> {
+    trie: &'a LetterTrieMap<V>,
+    strategy: SegmentStrategy,
+    // The total of the frequencies of every word, used only by MaxFrequency.
+    total_frequency: f64,
+}
+
+impl<'a, V: Frequency> Segmenter<'a, V> {
+    pub fn new(trie: &'a LetterTrieMap<V>, strategy: SegmentStrategy) -> Self {
+        let total_frequency = if strategy == SegmentStrategy::MaxFrequency {
+            let total_frequency: usize = trie.iter().map(|(_, value)| value.frequency()).sum();
+            cmp::max(total_frequency, 1) as f64
+        } else {
+            1.0
+        };
+        Self {
+            trie,
+            strategy,
+            total_frequency,
+        }
+    }
+
+    /// Split text into words and unknown spans. See `LetterTrieMap::segment()`.
+    pub fn segment(&self, text: &str) -> Vec<Segment> {
+        let (chars, is_rejected) = self.normalize(text);
+        // A character the normalizer rejected can't be part of a word, so the runs of characters between them are
+        // segmented separately.
+        let mut pieces = vec![];
+        let mut run_start = 0;
+        for index in 0..=chars.len() {
+            if index == chars.len() || is_rejected[index] {
+                let run_pieces = self.segment_run(&chars[run_start..index]);
+                pieces.extend(
+                    run_pieces
+                        .into_iter()
+                        .map(|(start, end, is_word)| (run_start + start, run_start + end, is_word)),
+                );
+                if index < chars.len() {
+                    pieces.push((index, index + 1, false));
+                }
+                run_start = index + 1;
+            }
+        }
+        LetterTrieMap::<V>::make_segments(&chars, pieces)
+    }
+
+    // Normalize the text as a whole if the normalizer accepts it, otherwise one character at a time. Returns the
+    // characters and which of them were rejected, which are kept as they were. A character that normalizes to
+    // nothing, such as a combining accent with diacritics stripped, is left out while whitespace is kept so it
+    // still separates words.
+    fn normalize(&self, text: &str) -> (Vec<char>, Vec<bool>) {
+        let normalizer = self.trie.normalizer();
+        if let Some(chars) = normalizer.to_chars(text) {
+            let is_rejected = vec![false; chars.len()];
+            return (chars, is_rejected);
+        }
+        let mut chars = vec![];
+        let mut is_rejected = vec![];
+        for c in text.trim().chars() {
+            match normalizer.to_chars(c.encode_utf8(&mut [0; 4])) {
+                Some(normalized) if !normalized.is_empty() => {
+                    is_rejected.extend(normalized.iter().map(|_| false));
+                    chars.extend(normalized);
+                }
+                Some(_) if !c.is_whitespace() => {}
+                _ => {
+                    chars.push(c);
+                    is_rejected.push(true);
+                }
+            }
+        }
+        (chars, is_rejected)
+    }
+
+    fn segment_run(&self, chars: &[char]) -> Vec<(usize, usize, bool)> {
+        match self.strategy {
+            SegmentStrategy::Greedy => self.trie.segment_greedy(chars),
+            SegmentStrategy::FewestWords => self.trie.segment_best(chars, |_| 1.0),
+            SegmentStrategy::MaxFrequency => self.trie.segment_best(chars, |value| {
+                let frequency = cmp::max(value.frequency(), 1) as f64;
+                -(frequency / self.total_frequency).ln()
+            }),
+        }
+    }
+}
+
+impl<V: Frequency> LetterTrieMap<V> {
+    /// Split text with no spaces, such as "thequickbrownfox", into words from the trie. Any part of the text that
+    /// can't be covered by words is reported as an unknown span, with consecutive unknown characters combined into
+    /// one span.
+    ///
+    /// The text is normalized first with the trie's normalizer and the segments are pieces of the normalized text.
+    /// If the normalizer rejects the text, each character is normalized on its own and only the characters it
+    /// rejects become unknown spans.
+    ///
+    /// This is the same as `Segmenter::new(self, strategy).segment(text)`. To segment many texts with
+    /// `SegmentStrategy::MaxFrequency`, keep a `Segmenter` so the total of the frequencies is only found once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+    /// for (word, count) in vec![("the", 50), ("them", 8), ("theme", 2), ("men", 6)] {
+    ///     trie.insert(word, count);
+    /// }
+    ///
+    /// let words = |segments: Vec<Segment>| -> Vec<(String, bool)> {
+    ///     segments.into_iter().map(|segment| (segment.text, segment.is_word)).collect()
+    /// };
+    /// assert_eq!(
+    ///     words(trie.segment("TheMen", &SegmentStrategy::Greedy)),
+    ///     vec![("theme".to_owned(), true), ("n".to_owned(), false)]
+    /// );
+    /// assert_eq!(
+    ///     words(trie.segment("TheMen", &SegmentStrategy::FewestWords)),
+    ///     vec![("the".to_owned(), true), ("men".to_owned(), true)]
+    /// );
+    /// ```
+    pub fn segment(&self, text: &str, strategy: &SegmentStrategy) -> Vec<Segment> {
+        Segmenter::new(self, strategy.clone()).segment(text)
+    }
+
+    // Returns the (start, end, is_word) of each piece, with unknown characters as pieces of one character each.
+    fn segment_greedy(&self, chars: &[char]) -> Vec<(usize, usize, bool)> {
+        let mut pieces = vec![];
+        let mut start = 0;
+        while start < chars.len() {
+            match self.root().prefix_word_lengths(&chars[start..]).pop() {
+                Some((len, _)) => {
+                    pieces.push((start, start + len, true));
+                    start += len;
+                }
+                None => {
+                    pieces.push((start, start + 1, false));
+                    start += 1;
+                }
+            }
+        }
+        pieces
+    }
+
+    // Dynamic programming over the positions in the text, where the best segmentation up to each position is built
+    // from the best segmentation up to an earlier position plus one word or one unknown character. Fewer unknown
+    // characters always wins, and after that the lowest total of the word costs.
+    fn segment_best<F: Fn(&V) -> f64>(
+        &self,
+        chars: &[char],
+        word_cost: F,
+    ) -> Vec<(usize, usize, bool)> {
+        let mut best: Vec<Option<Step>> = vec![None; chars.len() + 1];
+        best[0] = Some(Step {
+            unknown_count: 0,
+            cost: 0.0,
+            prev: 0,
+            is_word: false,
+        });
+        for start in 0..chars.len() {
+            let step = match &best[start] {
+                Some(step) => step.clone(),
+                None => continue,
+            };
+            let mut candidates = vec![(start + 1, step.unknown_count + 1, step.cost, false)];
+            for (len, value) in self.root().prefix_word_lengths(&chars[start..]) {
+                candidates.push((
+                    start + len,
+                    step.unknown_count,
+                    step.cost + word_cost(value),
+                    true,
+                ));
+            }
+            for (end, unknown_count, cost, is_word) in candidates {
+                let is_better = match &best[end] {
+                    Some(existing) => {
+                        (unknown_count, cost) < (existing.unknown_count, existing.cost)
+                    }
+                    None => true,
+                };
+                if is_better {
+                    best[end] = Some(Step {
+                        unknown_count,
+                        cost,
+                        prev: start,
+                        is_word,
+                    });
+                }
+            }
+        }
+
+        let mut pieces = vec![];
+        let mut end = chars.len();
+        while end > 0 {
+            let step = best[end].as_ref().unwrap();
+            pieces.push((step.prev, end, step.is_word));
+            end = step.prev;
+        }
+        pieces.reverse();
+        pieces
+    }
+
+    fn make_segments(chars: &[char], pieces: Vec<(usize, usize, bool)>) -> Vec<Segment> {
+        let mut segments: Vec<Segment> = vec![];
+        for (start, end, is_word) in pieces {
+            if let Some(last) = segments.last_mut() {
+                if !is_word && !last.is_word {
+                    last.end = end;
+                    last.text = chars[last.start..end].iter().collect();
+                    continue;
+                }
+            }
+            segments.push(Segment {
+                text: chars[start..end].iter().collect(),
+                start,
+                end,
+                is_word,
+            });
+        }
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_trie(words: &[(&str, usize)]) -> LetterTrieMap<usize> {
+        let mut trie = LetterTrieMap::new();
+        for (word, count) in words.iter() {
+            trie.insert(*word, *count);
+        }
+        trie
+    }
+
+    // Show unknown spans in brackets.
+    fn words(segments: Vec<Segment>) -> Vec<String> {
+        segments
+            .into_iter()
+            .map(|segment| {
+                if segment.is_word {
+                    segment.text
+                } else {
+                    format!("[{}]", segment.text)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quick_brown_fox() {
+        let trie = make_trie(&[
+            ("the", 100),
+            ("quick", 10),
+            ("brown", 10),
+            ("fox", 5),
+            ("he", 50),
+            ("qui", 1),
+        ]);
+        for strategy in [
+            SegmentStrategy::Greedy,
+            SegmentStrategy::FewestWords,
+            SegmentStrategy::MaxFrequency,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                words(trie.segment("TheQuickBrownFox", strategy)),
+                vec!["the", "quick", "brown", "fox"]
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_spans() {
+        let trie = make_trie(&[("the", 100), ("fox", 5)]);
+        let segments = trie.segment("thexyzfoxq", &SegmentStrategy::FewestWords);
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    text: "the".to_owned(),
+                    start: 0,
+                    end: 3,
+                    is_word: true,
+                },
+                Segment {
+                    text: "xyz".to_owned(),
+                    start: 3,
+                    end: 6,
+                    is_word: false,
+                },
+                Segment {
+                    text: "fox".to_owned(),
+                    start: 6,
+                    end: 9,
+                    is_word: true,
+                },
+                Segment {
+                    text: "q".to_owned(),
+                    start: 9,
+                    end: 10,
+                    is_word: false,
+                },
+            ]
+        );
+        assert_eq!(trie.segment("", &SegmentStrategy::Greedy), vec![]);
+    }
+
+    #[test]
+    fn max_frequency() {
+        let trie = make_trie(&[("a", 1000), ("b", 1000), ("ab", 1)]);
+        assert_eq!(
+            words(trie.segment("ab", &SegmentStrategy::FewestWords)),
+            vec!["ab"]
+        );
+        assert_eq!(
+            words(trie.segment("ab", &SegmentStrategy::MaxFrequency)),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn rejected_characters() {
+        let mut trie: LetterTrieMap<usize> =
+            LetterTrieMap::with_normalizer(Normalizer::make_ascii_letters());
+        for (word, count) in [("the", 100), ("quick", 10), ("fox", 5)].iter() {
+            trie.insert(*word, *count);
+        }
+        // The normalizer turns away the whole text because of the digits and the space, so only those are unknown.
+        assert_eq!(
+            words(trie.segment("The2Quick Fox99", &SegmentStrategy::FewestWords)),
+            vec!["the", "[2]", "quick", "[ ]", "fox", "[99]"]
+        );
+        // Each character is still normalized, so the accent is stripped from the "é".
+        let segmenter = Segmenter::new(&trie, SegmentStrategy::MaxFrequency);
+        assert_eq!(
+            words(segmenter.segment("théfox!")),
+            vec!["the", "fox", "[!]"]
+        );
+        assert_eq!(words(segmenter.segment("quickfox")), vec!["quick", "fox"]);
+    }
+
+    #[test]
+    fn small_dataset() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let trie = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        assert_eq!(
+            words(trie.segment("azurecreatureandcrossed", &SegmentStrategy::FewestWords)),
+            vec!["azure", "creature", "and", "crossed"]
+        );
+        assert_eq!(
+            trie.longest_prefix_word("creatures"),
+            Some(("creature".to_owned(), &()))
+        );
+    }
+}
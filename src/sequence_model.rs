@@ -0,0 +1,460 @@
+use rand::Rng;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::mem;
+
+type PrefixEntry = BTreeMap<String, NextStep>;
+type PrefixMap = BTreeMap<String, PrefixEntry>;
+type SequenceMap = BTreeMap<usize, PrefixMap>;
+
+const START: &str = "[";
+const END: &str = "]";
+// The probability used by `SequenceModel::log_likelihood()` for a transition that was never seen at any depth.
+const UNSEEN_TRANSITION_PROBABILITY: f64 = 0.000_001;
+const LABEL_MAX_DEPTH: &str = "max_depth";
+
+#[derive(Debug)]
+struct NextStep {
+    value: String,
+    count: usize,
+    share: f64,
+    range_start: f64,
+    range_end: f64,
+}
+
+/// The Markov model behind `generate_words()` and `WordGenerator`, giving the chance of each letter following a
+/// given sequence of letters.
+///
+/// For each depth from 1 to `max_depth` it holds every sequence of that many characters found in the example
+/// words, with "[" marking the start of a word, along with a count of each character that came next, with "]"
+/// marking the end of a word. So at depth 2 the prefix "[q" leads to "u" almost every time, while "ly" often
+/// leads to "]".
+///
+/// A model can be saved with `save()` and loaded with `from_file()` so it doesn't have to be rebuilt from the
+/// example words each time, and `depth_stats()` summarizes what it learned.
+///
+/// The model can also judge how much a string looks like a word in the language of the example words with
+/// `score()`, which makes it a cheap gibberish detector.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let example_words: Vec<String> = words_from_file("english_words_3_000.txt");
+/// let model = SequenceModel::new(&example_words, 3);
+///
+/// assert!(model.score("thinkable") > model.score("xkqzvbt"));
+/// assert!(model.score("Planting") > -3.0);
+/// assert!(model.score("qqqqq") < -6.0);
+///
+/// let filename = std::env::temp_dir().join("letter_trie_sequence_model_doc.txt");
+/// let filename = filename.to_str().unwrap();
+/// model.save(filename);
+/// let loaded = SequenceModel::from_file(filename);
+/// assert_eq!(loaded.max_depth(), 3);
+/// assert_eq!(loaded.score("planting"), model.score("planting"));
+/// ```
+pub struct SequenceModel {
+    max_depth: usize,
+    sequence_map: SequenceMap,
+}
+
+/// A summary of the transitions at one depth of a SequenceModel, from `SequenceModel::depth_stats()`.
+#[derive(Debug)]
+pub struct DepthStats {
+    /// The number of characters used to choose the next character.
+    pub depth: usize,
+    /// The number of distinct prefixes of this length.
+    pub prefix_count: usize,
+    /// The number of distinct pairs of a prefix and the character after it.
+    pub transition_count: usize,
+    /// The number of times any prefix was followed by any character in the example words.
+    pub observation_count: usize,
+    /// The average uncertainty about the next character in bits, weighted by how often each prefix was seen. A
+    /// value of 0.0 means the prefix always decides the next character, while picking evenly among 26 letters
+    /// would be about 4.7.
+    pub entropy: f64,
+    /// The most frequent transitions, from most to least frequent.
+    pub top_transitions: Vec<Transition>,
+}
+
+/// One prefix and a character that followed it in the example words.
+#[derive(Debug, PartialEq)]
+pub struct Transition {
+    pub prefix: String,
+    pub next: String,
+    pub count: usize,
+    /// The fraction of the times the prefix was seen that it was followed by this character.
+    pub share: f64,
+}
+
+impl SequenceModel {
+    /// Build the model from a list of real words, where `max_depth` is the highest number of letters to use when
+    /// deciding what letter comes next. Words with anything other than the letters a to z are skipped.
+    pub fn new(example_words: &[String], max_depth: usize) -> Self {
+        let regex = Regex::new(r"^[a-z]+$").unwrap();
+        // The special characters indicating the beginning and end of a word must not be characters that can be
+        // found in a word.
+        debug_assert!(!regex.is_match(START));
+        debug_assert!(!regex.is_match(END));
+
+        let mut sequence_map = SequenceMap::new();
+
+        for depth in 1..=max_depth {
+            let prefix_map = sequence_map.entry(depth).or_default();
+            for example in example_words.iter().map(|x| x.trim().to_lowercase()) {
+                if regex.is_match(&example) {
+                    let word = format!("{}{}{}", START, example, END);
+                    let last_i: isize = (word.len() as isize - depth as isize) - 1;
+                    if last_i >= 0 {
+                        for i in 0..=(last_i as usize) {
+                            let prefix = word[i..i + depth].to_owned();
+                            let next_step_value = word[i + depth..=i + depth].to_owned();
+                            Self::add_count(prefix_map, prefix, next_step_value, 1);
+                        }
+                    }
+                }
+            }
+            Self::set_shares(prefix_map);
+        }
+        Self {
+            max_depth,
+            sequence_map,
+        }
+    }
+
+    fn add_count(
+        prefix_map: &mut PrefixMap,
+        prefix: String,
+        next_step_value: String,
+        count: usize,
+    ) {
+        let prefix_entry = prefix_map.entry(prefix).or_default();
+        let next_step = prefix_entry
+            .entry(next_step_value.clone())
+            .or_insert(NextStep {
+                value: next_step_value,
+                count: 0,
+                share: 0.0,
+                range_start: 0.0,
+                range_end: 0.0,
+            });
+        next_step.count += count;
+    }
+
+    fn set_shares(prefix_map: &mut PrefixMap) {
+        for prefix_entry in prefix_map.values_mut() {
+            let count_sum = &prefix_entry
+                .values()
+                .map(|next_step| next_step.count as f64)
+                .sum::<f64>();
+            let mut range_start = 0.0;
+            for next_step in prefix_entry.values_mut() {
+                let share = next_step.count as f64 / count_sum;
+                next_step.share = share;
+                next_step.range_start = range_start;
+                next_step.range_end = range_start + share;
+                range_start += share;
+            }
+        }
+    }
+
+    /// The highest number of letters used to decide what letter comes next.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// A rough estimate of the memory used by the model in bytes, counting the size of each prefix string and each
+    /// transition but not the spare capacity inside the maps and strings. Comparable to
+    /// `ContextTrie::estimated_bytes()`.
+    pub fn estimated_bytes(&self) -> usize {
+        let mut bytes = 0;
+        for prefix_map in self.sequence_map.values() {
+            for (prefix, prefix_entry) in prefix_map.iter() {
+                bytes += mem::size_of::<(String, PrefixEntry)>() + prefix.len();
+                for next_step in prefix_entry.values() {
+                    bytes += mem::size_of::<(String, NextStep)>() + 2 * next_step.value.len();
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Generate one word from start to end. The word may be one of the example words and may be empty.
+    pub fn generate_word<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        let mut word = String::from(START);
+        while self.add_to_word(&mut word, rng) {}
+        word[1..].to_lowercase()
+    }
+
+    fn add_to_word<R: Rng + ?Sized>(&self, word: &mut String, rng: &mut R) -> bool {
+        let word_len = word.len();
+        let mut depth = word_len;
+        while depth >= 1 {
+            if let Some(prefix_map) = self.sequence_map.get(&depth) {
+                let prefix = &word[word_len - depth..].to_owned();
+                if let Some(prefix_entry) = prefix_map.get(prefix) {
+                    let next_step_value = Self::random_weighted_value(prefix_entry, rng);
+                    if next_step_value == END {
+                        return false;
+                    } else {
+                        *word = format!("{}{}", word, next_step_value);
+                        return true;
+                    }
+                }
+            }
+            depth -= 1;
+        }
+        false
+    }
+
+    fn random_weighted_value<R: Rng + ?Sized>(prefix_entry: &PrefixEntry, rng: &mut R) -> String {
+        let r = rng.gen::<f64>();
+        // If the shares add up to slightly less than 1.0 because of rounding, use the last value.
+        let next_step = prefix_entry
+            .values()
+            .find(|x| r >= x.range_start && r < x.range_end)
+            .unwrap_or_else(|| prefix_entry.values().next_back().unwrap());
+        next_step.value.to_owned()
+    }
+
+    /// The natural log of the probability that the model would generate this exact word, after trimming and
+    /// converting to lowercase. Each character, and the end of the word, is scored using the longest prefix that
+    /// was seen followed by that character, backing off to shorter prefixes as needed. A character that never
+    /// followed any prefix, such as a digit, gets a probability of one in a million.
+    ///
+    /// Longer words have lower log likelihoods simply because there are more characters; use `score()` to compare
+    /// words of different lengths.
+    pub fn log_likelihood(&self, word: &str) -> f64 {
+        self.transition_log_probabilities(word).iter().sum()
+    }
+
+    /// The average log probability per character of a word (counting the end of the word as one more character),
+    /// which is closer to 0.0 the more the word looks like the example words. With English example words, real
+    /// and made-up but pronounceable words tend to score above -3.0 while random letters score well below that.
+    pub fn score(&self, word: &str) -> f64 {
+        let log_probabilities = self.transition_log_probabilities(word);
+        log_probabilities.iter().sum::<f64>() / log_probabilities.len() as f64
+    }
+
+    fn transition_log_probabilities(&self, word: &str) -> Vec<f64> {
+        let chars: Vec<String> = START
+            .chars()
+            .chain(word.trim().to_lowercase().chars())
+            .chain(END.chars())
+            .map(|c| c.to_string())
+            .collect();
+        (1..chars.len())
+            .map(|i| {
+                let next = &chars[i];
+                let share = (1..=self.max_depth.min(i))
+                    .rev()
+                    .find_map(|depth| {
+                        let prefix = chars[i - depth..i].concat();
+                        self.sequence_map
+                            .get(&depth)
+                            .and_then(|prefix_map| prefix_map.get(&prefix))
+                            .and_then(|prefix_entry| prefix_entry.get(next))
+                            .map(|next_step| next_step.share)
+                    })
+                    .unwrap_or(UNSEEN_TRANSITION_PROBABILITY);
+                share.ln()
+            })
+            .collect()
+    }
+
+    /// Summarize the model at each depth, including up to `top_count` of the most frequent transitions.
+    pub fn depth_stats(&self, top_count: usize) -> Vec<DepthStats> {
+        self.sequence_map
+            .iter()
+            .map(|(depth, prefix_map)| {
+                let mut transitions = vec![];
+                let mut observation_count = 0;
+                let mut weighted_entropy = 0.0;
+                for (prefix, prefix_entry) in prefix_map.iter() {
+                    let prefix_count: usize = prefix_entry.values().map(|x| x.count).sum();
+                    let entropy: f64 = prefix_entry
+                        .values()
+                        .map(|next_step| -next_step.share * next_step.share.log2())
+                        .sum();
+                    observation_count += prefix_count;
+                    weighted_entropy += entropy * prefix_count as f64;
+                    for next_step in prefix_entry.values() {
+                        transitions.push(Transition {
+                            prefix: prefix.clone(),
+                            next: next_step.value.clone(),
+                            count: next_step.count,
+                            share: next_step.share,
+                        });
+                    }
+                }
+                let transition_count = transitions.len();
+                // Most frequent first, then in alphabetical order.
+                transitions.sort_by(|a, b| match b.count.cmp(&a.count) {
+                    Ordering::Equal => (&a.prefix, &a.next).cmp(&(&b.prefix, &b.next)),
+                    ordering => ordering,
+                });
+                transitions.truncate(top_count);
+                DepthStats {
+                    depth: *depth,
+                    prefix_count: prefix_map.len(),
+                    transition_count,
+                    observation_count,
+                    entropy: if observation_count == 0 {
+                        0.0
+                    } else {
+                        weighted_entropy / observation_count as f64
+                    },
+                    top_transitions: transitions,
+                }
+            })
+            .collect()
+    }
+
+    /// Print every prefix at every depth along with the characters that can follow it.
+    pub fn print(&self) {
+        for depth in self.sequence_map.keys() {
+            println!("\nDepth = {}\n", depth);
+            let prefix_map = &self.sequence_map[depth];
+            for prefix in prefix_map.keys() {
+                println!("  {} ->", prefix);
+                for next_step in prefix_map[prefix].values() {
+                    // println!("    {}: count = {}", next_step.value, next_step.count);
+                    println!("    {:?}", next_step);
+                }
+            }
+        }
+    }
+
+    /// Write the model to a text file. The first line gives the maximum depth and each of the other lines has a
+    /// depth, a prefix, the next character and a count, separated by tabs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file can't be created or written.
+    pub fn save(&self, filename: &str) {
+        let mut file = File::create(filename).expect("Error creating file.");
+        writeln!(file, "{}\t{}", LABEL_MAX_DEPTH, self.max_depth).expect("Error writing a line.");
+        for (depth, prefix_map) in self.sequence_map.iter() {
+            for (prefix, prefix_entry) in prefix_map.iter() {
+                for next_step in prefix_entry.values() {
+                    writeln!(
+                        file,
+                        "{}\t{}\t{}\t{}",
+                        depth, prefix, next_step.value, next_step.count
+                    )
+                    .expect("Error writing a line.");
+                }
+            }
+        }
+    }
+
+    /// Read a model written by `save()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading, or if it's not in the format written by
+    /// `save()`.
+    pub fn from_file(filename: &str) -> Self {
+        let file = File::open(filename).unwrap();
+        let mut lines = BufReader::new(file).lines();
+        let first_line = lines.next().expect("Empty model file.").unwrap();
+        let max_depth = match first_line.split('\t').collect::<Vec<&str>>()[..] {
+            [LABEL_MAX_DEPTH, max_depth] => {
+                max_depth.parse().expect("Error reading the maximum depth.")
+            }
+            _ => panic!("Missing the maximum depth."),
+        };
+        let mut sequence_map = SequenceMap::new();
+        for depth in 1..=max_depth {
+            sequence_map.insert(depth, PrefixMap::new());
+        }
+        for line in lines {
+            let line = line.unwrap();
+            if line.is_empty() {
+                continue;
+            }
+            let parts: Vec<&str> = line.split('\t').collect();
+            assert_eq!(parts.len(), 4, "Expected four columns: {}", line);
+            let depth: usize = parts[0].parse().expect("Error reading a depth.");
+            let count: usize = parts[3].parse().expect("Error reading a count.");
+            let prefix_map = sequence_map
+                .get_mut(&depth)
+                .expect("Depth is greater than the maximum depth.");
+            Self::add_count(prefix_map, parts[1].to_owned(), parts[2].to_owned(), count);
+        }
+        for prefix_map in sequence_map.values_mut() {
+            Self::set_shares(prefix_map);
+        }
+        Self {
+            max_depth,
+            sequence_map,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::words_from_file;
+
+    fn english_model() -> SequenceModel {
+        SequenceModel::new(&words_from_file("english_words_3_000.txt"), 3)
+    }
+
+    #[test]
+    fn depth_stats() {
+        let model = english_model();
+        let stats = model.depth_stats(3);
+        assert_eq!(stats.len(), 3);
+        // Each word of only letters adds one observation at depth 1 for each letter plus one for the end.
+        let letter_count: usize = words_from_file("english_words_3_000.txt")
+            .iter()
+            .map(|word| word.to_lowercase())
+            .filter(|word| word.chars().all(|c| c.is_ascii_lowercase()))
+            .map(|word| word.len() + 1)
+            .sum();
+        assert_eq!(stats[0].observation_count, letter_count);
+        assert_eq!(stats[0].prefix_count, 27);
+        // A longer prefix says more about what comes next.
+        assert!(stats[0].entropy > stats[1].entropy);
+        assert!(stats[1].entropy > stats[2].entropy);
+        assert_eq!(stats[2].top_transitions.len(), 3);
+        assert!(stats[2].top_transitions[0].count >= stats[2].top_transitions[1].count);
+    }
+
+    #[test]
+    fn score() {
+        let model = english_model();
+        for word in ["house", "remanager", "passignal", "Incomply"].iter() {
+            assert!(model.score(word) > -3.0, "{}: {}", word, model.score(word));
+        }
+        for word in ["zxqvk", "bcdfghjklm", "12345"].iter() {
+            assert!(model.score(word) < -5.0, "{}: {}", word, model.score(word));
+        }
+        assert_eq!(
+            model.log_likelihood("9"),
+            2.0 * UNSEEN_TRANSITION_PROBABILITY.ln()
+        );
+    }
+
+    #[test]
+    fn save_and_load() {
+        let model = SequenceModel::new(&["cat".to_owned(), "cot".to_owned()], 2);
+        let filename = std::env::temp_dir().join("letter_trie_sequence_model_test.txt");
+        let filename = filename.to_str().unwrap();
+        model.save(filename);
+        let loaded = SequenceModel::from_file(filename);
+        assert_eq!(loaded.max_depth(), 2);
+        assert_eq!(
+            loaded.depth_stats(100)[1].top_transitions,
+            model.depth_stats(100)[1].top_transitions
+        );
+        // "[c" is followed by "a" half the time, then "ca" by "t" and "at" by the end of the word.
+        assert_eq!(loaded.log_likelihood("cat"), 0.5f64.ln());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeSet;
+use std::mem;
+
+use crate::trie_map::TrieMapNode;
+use crate::*;
+
+/// An index of every substring of every word in a dictionary, for "contains" queries such as finding all of the
+/// words with "ght" somewhere inside them.
+///
+/// It's a suffix trie: each suffix of each word ("night", "ight", "ght", "ht", "t") is inserted into a
+/// LetterTrieMap whose value is the list of words ending with that suffix. A substring is a prefix of some suffix,
+/// so the words containing it are found by walking down to the substring's node and collecting the word lists in
+/// the subtree below it, without looking at any of the other words.
+///
+/// Because it uses the same node structure as the word trie, `to_fixed_node()` and `find()` give the same stats as
+/// for any LetterTrie and the node counts can be compared directly. The price is memory, since a word of length n
+/// adds up to n suffixes; see `describe_blowup()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let index = SubstringIndex::from_file(
+///     dataset.filename(),
+///     dataset.is_sorted(),
+///     &LoadMethod::Continuous,
+/// );
+///
+/// assert_eq!(index.words_containing("eat"), vec!["creative", "creator", "creature"]);
+/// assert_eq!(index.words_containing("Ss"), vec!["cross", "crossed"]);
+/// assert!(index.words_containing("xyz").is_empty());
+/// assert_eq!(index.find("ure").unwrap().word_count, 1);
+/// ```
+pub struct SubstringIndex {
+    // The words in alphabetical order, so sorting the word IDs sorts the words.
+    words: Vec<String>,
+    // Each suffix mapped to the IDs of the words that end with it.
+    suffixes: LetterTrieMap<Vec<usize>>,
+    word_node_count: usize,
+}
+
+impl SubstringIndex {
+    /// Build the index from the words in an existing letter trie, using the same normalizer for lookups.
+    pub fn from_trie<V>(trie: &LetterTrieMap<V>) -> Self {
+        let words: Vec<String> = trie.iter().map(|(word, _)| word).collect();
+        let mut suffixes = LetterTrieMap::with_normalizer(trie.normalizer().clone());
+        for (word_id, word) in words.iter().enumerate() {
+            let v: Vec<char> = word.chars().collect();
+            for start in 0..v.len() {
+                suffixes
+                    .entry(&v[start..])
+                    .or_insert_with(Vec::new)
+                    .push(word_id);
+            }
+        }
+        suffixes.freeze();
+        Self {
+            words,
+            suffixes,
+            word_node_count: trie.node_count(),
+        }
+    }
+
+    /// Build the index from a word file with one word per line, loading it with any LoadMethod as with
+    /// `LetterTrie::from_file()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading.
+    pub fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
+        let trie = LetterTrieSet::from_file(filename, is_sorted, load_method);
+        Self::from_trie(&trie)
+    }
+
+    /// Find the words that contain a substring anywhere, in alphabetical order. The substring is normalized the
+    /// same way as the words so with the default normalizer the search ignores case. An empty substring matches
+    /// every word.
+    pub fn words_containing(&self, substring: &str) -> Vec<String> {
+        let word_ids: BTreeSet<usize> = self
+            .suffixes
+            .iter_prefix(substring)
+            .flat_map(|(_, word_ids)| word_ids.iter().cloned())
+            .collect();
+        word_ids
+            .into_iter()
+            .map(|word_id| self.words[word_id].clone())
+            .collect()
+    }
+
+    /// Get the stats for the node reached by a substring, where `word_count` is the number of distinct suffixes
+    /// that start with the substring. Returns None if no word contains the substring.
+    pub fn find(&self, substring: &str) -> Option<FixedNode> {
+        let v = self.suffixes.normalizer().to_chars(substring)?;
+        self.suffixes.get_fixed_node(&v)
+    }
+
+    /// The stats for the root of the suffix trie.
+    pub fn to_fixed_node(&self) -> FixedNode {
+        self.suffixes.get_fixed_node(&[]).unwrap()
+    }
+
+    /// The number of words in the dictionary.
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// The number of nodes in the suffix trie, including the root.
+    pub fn node_count(&self) -> usize {
+        self.suffixes.node_count()
+    }
+
+    /// The number of nodes in the plain trie of words that the index was built from.
+    pub fn word_node_count(&self) -> usize {
+        self.word_node_count
+    }
+
+    /// How many times as many nodes the suffix trie has as the plain trie of words.
+    pub fn blowup(&self) -> f64 {
+        self.node_count() as f64 / self.word_node_count as f64
+    }
+
+    /// A rough estimate of the memory used by the suffix trie in bytes, counting the size of each node and each
+    /// word ID but not the spare capacity inside the maps and vectors.
+    pub fn estimated_bytes(&self) -> usize {
+        let posting_count: usize = self
+            .suffixes
+            .iter()
+            .map(|(_, word_ids)| word_ids.len())
+            .sum();
+        self.node_count() * mem::size_of::<TrieMapNode<char, Vec<usize>>>()
+            + posting_count * mem::size_of::<usize>()
+    }
+
+    /// A one-line comparison of the size of the suffix trie with the size of the plain trie of words.
+    pub fn describe_blowup(&self) -> String {
+        format!(
+            "SubstringIndex: {} words; {} nodes vs. {} in the word trie ({:.1}x); about {} bytes.",
+            format_count(self.word_count()),
+            format_count(self.node_count()),
+            format_count(self.word_node_count),
+            self.blowup(),
+            format_count(self.estimated_bytes())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_index(load_method: &LoadMethod) -> SubstringIndex {
+        let dataset = Dataset::TestSmallUnsorted;
+        SubstringIndex::from_file(dataset.filename(), dataset.is_sorted(), load_method)
+    }
+
+    #[test]
+    fn small_all_load_methods() {
+        for load_method in [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ]
+        .iter()
+        {
+            let index = small_index(load_method);
+            assert_eq!(index.word_count(), WORD_COUNT_SMALL);
+            assert_eq!(index.word_node_count(), 28);
+            assert_eq!(
+                index.words_containing("r"),
+                vec!["azure", "crease", "creative", "creator", "creature", "cross", "crossed"]
+            );
+            assert_eq!(index.words_containing("n"), vec!["an", "and"]);
+            assert_eq!(index.words_containing("").len(), WORD_COUNT_SMALL);
+            assert!(index.node_count() > index.word_node_count());
+        }
+    }
+
+    #[test]
+    fn stats() {
+        let index = small_index(&LoadMethod::Continuous);
+        let root = index.to_fixed_node();
+        assert_eq!(root.node_count, index.node_count());
+        assert_eq!(root.depth, 0);
+        // "a" starts the suffixes "a", "an", "and", "azure", "ase", "ative", "ator" and "ature".
+        let node = index.find("A").unwrap();
+        assert_eq!(node.prefix, "a");
+        assert_eq!(node.word_count, 8);
+        assert!(index.find("ax").is_none());
+        assert!(index
+            .describe_blowup()
+            .starts_with("SubstringIndex: 10 words;"));
+    }
+
+    #[test]
+    fn matches_scan_medium() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let index = SubstringIndex::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+        );
+        assert_eq!(index.word_count(), WORD_COUNT_MEDIUM);
+        let mut words = words_from_file(dataset.filename());
+        words.sort_unstable();
+        for substring in ["ght", "qu", "e", "zz"].iter() {
+            let expected: Vec<String> = words
+                .iter()
+                .filter(|word| word.contains(substring))
+                .cloned()
+                .collect();
+            assert_eq!(index.words_containing(substring), expected);
+        }
+    }
+}
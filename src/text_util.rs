@@ -1,28 +1,18 @@
 #![allow(dead_code)]
 
 // use std::rand::{task_rng, Rng};
-use regex::Regex;
-use std::collections::{BTreeMap, HashSet};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
 
-type PrefixEntry = BTreeMap<String, NextStep>;
-type PrefixMap = BTreeMap<String, PrefixEntry>;
-type SequenceMap = BTreeMap<usize, PrefixMap>;
+use crate::SequenceModel;
 
-const START: &str = "[";
-const END: &str = "]";
-const CHANCE_TO_USE_DEPTH: f64 = 1.0;
 const MAX_WORD_LENGTH: usize = 16;
-
-#[derive(Debug)]
-struct NextStep {
-    value: String,
-    count: usize,
-    share: f64,
-    range_start: f64,
-    range_end: f64,
-}
+/// A WordGenerator iterator gives up after this many generated words in a row that are duplicates, are the wrong
+/// length or are example words being excluded.
+pub const MAX_ATTEMPTS_WITHOUT_NEW_WORD: usize = 100_000;
 
 /// Given a small list of real words in some language, generate a large number of mostly fake words that resemble the real ones.
 ///
@@ -31,7 +21,7 @@ struct NextStep {
 ///
 /// For a valid test the generated words should follow the patterns of the real words. For instance, going by the list of the
 /// 3,000 most common English words:
-///	- About 11.3% of words start with "s" while only 4.8% of words start with "b".
+/// - About 11.3% of words start with "s" while only 4.8% of words start with "b".
 /// - Given only that the last letter in a partially-formed word is "b", about 17% of the time that will be followed by an "e" and about 3% of the time this "b" will be the last letter of the word.
 /// - Given only that the last three letters in a partially-formed word are "ome", 38% of the time that's the end of the word.
 ///
@@ -65,6 +55,9 @@ struct NextStep {
 ///
 /// Note that the list of generated words may contain any number of the original seed words depending on the luck of the draw.
 ///
+/// The patterns are learned by a `SequenceModel`, which can also be saved, inspected and reused with
+/// `WordGenerator::from_model()`.
+///
 /// Arguments:
 /// - `example_words`: A list of real words in the target language.
 /// - `target_count`: The number of words to generate. This can be much larger than the number of words in `example_words`.
@@ -78,7 +71,7 @@ struct NextStep {
 /// let source_filename = "english_words_3_000.txt";
 /// let source_word_count = 3_000;
 ///
-///	// A million words would be fine but it's a smaller number here since this doc test will be
+/// // A million words would be fine but it's a smaller number here since this doc test will be
 /// // run repeatedly.
 /// let generated_word_count = 50_000;
 /// let depth = 3;
@@ -94,126 +87,179 @@ pub fn generate_words(
     target_count: usize,
     max_depth: usize,
 ) -> Vec<String> {
-    let sequence_map = make_sequence_map(example_words, max_depth);
-    // print_sequence_map(&sequence_map);
-    let mut set: HashSet<String> = HashSet::new();
-    while set.len() < target_count {
-        let mut word = String::from(START);
-        while add_to_word(&sequence_map, &mut word) {}
-        let final_word: String = (&word[1..]).to_lowercase().to_owned();
-        //if !example_sequences.contains(&final_word)
-        if !final_word.is_empty() && final_word.len() <= MAX_WORD_LENGTH {
-            let set_len = set.len();
-            if set_len % 1_000 == 0 {
-                println!("[{}] {}", set.len(), &final_word);
-            }
-            set.insert(final_word);
-        }
-    }
-    let v: Vec<String> = set.drain().collect::<Vec<String>>();
-    // println!("{:#?}", v);
-    println!("{:#?}", v.len());
-    v
+    WordGenerator::new(example_words, max_depth).generate(target_count)
 }
 
-fn add_to_word(sequence_map: &SequenceMap, word: &mut String) -> bool {
-    let word_len = word.len();
-    let mut depth = word_len;
-    // let mut depth = task_rng().gen_range(1, word_len + 1);
-    while depth >= 1 {
-        // if rand::random::<f64>() < CHANCE_TO_USE_DEPTH {
-        if let Some(prefix_map) = sequence_map.get(&depth) {
-            let prefix = &word[word_len - depth..].to_owned();
-            if let Some(prefix_entry) = prefix_map.get(prefix) {
-                let next_step_value = random_weighted_value(&prefix_entry);
-                if next_step_value == END {
-                    return false;
-                } else {
-                    *word = format!("{}{}", &word, next_step_value);
-                    return true;
-                }
-            }
+/// A word generator like `generate_words()` with settings that make the output reproducible.
+///
+/// Create it with `new()` and change any of the settings by chaining the builder methods. With a seed, two
+/// generators made from the same example words and settings produce exactly the same words in the same order, so a
+/// dataset like "fake_words_400_000_sorted.txt" can be regenerated bit for bit. The random number generator is
+/// ChaCha8 from `rand_chacha`, whose output for a given seed doesn't change between releases.
+///
+/// Defaults:
+/// - No seed, meaning a different random seed each time words are generated.
+/// - Words of 1 to 16 characters.
+/// - Example words may turn up among the generated words.
+/// - Progress is printed every 1,000 words, as `generate_words()` always has.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let example_words: Vec<String> = words_from_file("english_words_3_000.txt");
+/// let generator = WordGenerator::new(&example_words, 3)
+///     .seed(400)
+///     .min_length(4)
+///     .max_length(10)
+///     .exclude_example_words(true)
+///     .silent(true);
+///
+/// let words = generator.generate(1_000);
+/// assert_eq!(words.len(), 1_000);
+/// assert!(words.iter().all(|word| word.len() >= 4 && word.len() <= 10));
+/// assert!(words.iter().all(|word| !example_words.contains(word)));
+///
+/// // The same seed gives the same words, whether they're collected all at once or streamed.
+/// let streamed: Vec<String> = generator.iter().take(1_000).collect();
+/// assert_eq!(streamed, words);
+/// ```
+pub struct WordGenerator {
+    model: SequenceModel,
+    example_words: HashSet<String>,
+    seed: Option<u64>,
+    min_length: usize,
+    max_length: usize,
+    exclude_example_words: bool,
+    is_silent: bool,
+}
+
+impl WordGenerator {
+    /// Create a generator with the default settings from a list of real words, where `max_depth` is the number of
+    /// letters in a partially-formed word to use when choosing the next letter as in `generate_words()`.
+    pub fn new(example_words: &[String], max_depth: usize) -> Self {
+        let mut generator = Self::from_model(SequenceModel::new(example_words, max_depth));
+        generator.example_words = example_words
+            .iter()
+            .map(|x| x.trim().to_lowercase())
+            .collect();
+        generator
+    }
+
+    /// Create a generator with the default settings from a model that was built earlier, perhaps loaded with
+    /// `SequenceModel::from_file()`. The example words aren't part of the model so `exclude_example_words()` has no
+    /// effect.
+    pub fn from_model(model: SequenceModel) -> Self {
+        Self {
+            model,
+            example_words: HashSet::new(),
+            seed: None,
+            min_length: 1,
+            max_length: MAX_WORD_LENGTH,
+            exclude_example_words: false,
+            is_silent: false,
         }
-        // }
-        depth -= 1;
     }
-    false
-}
 
-fn random_weighted_value(prefix_entry: &PrefixEntry) -> String {
-    let r = rand::random::<f64>();
-    let next_step = prefix_entry
-        .values()
-        .find(|x| r >= x.range_start && r < x.range_end)
-        .unwrap();
-    next_step.value.to_owned()
-}
+    /// Use a fixed seed so that the same words are generated every time.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 
-fn make_sequence_map(example_words: &[String], max_depth: usize) -> SequenceMap {
-    let regex = Regex::new(r"^[a-z]+$").unwrap();
-    // The special characters indicating the beginning and end of a word must not be characters that can be found
-    // in a word.
-    debug_assert!(!regex.is_match(START));
-    debug_assert!(!regex.is_match(END));
+    /// Skip generated words with fewer characters than this.
+    pub fn min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
 
-    let mut sequence_map = SequenceMap::new();
+    /// Skip generated words with more characters than this.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
 
-    for depth in 1..=max_depth {
-        let prefix_map = sequence_map.entry(depth).or_insert_with(PrefixMap::new);
-        for example in example_words.iter().map(|x| x.trim().to_lowercase()) {
-            if regex.is_match(&example) {
-                let word = format!("{}{}{}", START, example, END);
-                let last_i: isize = (word.len() as isize - depth as isize) - 1;
-                if last_i >= 0 {
-                    for i in 0..=(last_i as usize) {
-                        let prefix = word[i..i + depth].to_owned();
-                        let prefix_entry =
-                            prefix_map.entry(prefix).or_insert_with(PrefixEntry::new);
-                        let next_step_value = word[i + depth..=i + depth].to_owned();
-                        let mut next_step =
-                            prefix_entry
-                                .entry(next_step_value.clone())
-                                .or_insert(NextStep {
-                                    value: next_step_value,
-                                    count: 0,
-                                    share: 0.0,
-                                    range_start: 0.0,
-                                    range_end: 0.0,
-                                });
-                        next_step.count += 1;
-                    }
-                }
-            }
+    /// If true, skip generated words that happen to be among the example words so the output has only fake words.
+    pub fn exclude_example_words(mut self, exclude_example_words: bool) -> Self {
+        self.exclude_example_words = exclude_example_words;
+        self
+    }
+
+    /// If true, don't print any progress.
+    pub fn silent(mut self, is_silent: bool) -> Self {
+        self.is_silent = is_silent;
+        self
+    }
+
+    /// Stream distinct generated words in the order they're generated. Each call starts over from the seed.
+    ///
+    /// The iterator ends early if it goes 100,000 attempts in a row without finding a new word that fits the
+    /// settings, which happens when the example words and settings don't allow for enough different words.
+    pub fn iter(&self) -> GeneratedWords<'_> {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        GeneratedWords {
+            generator: self,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            words: HashSet::new(),
         }
-        for prefix_entry in prefix_map.values_mut() {
-            let count_sum = &prefix_entry
-                .values()
-                .map(|next_step| next_step.count as f64)
-                .sum::<f64>();
-            let mut range_start = 0.0;
-            for mut next_step in prefix_entry.values_mut() {
-                let share = next_step.count as f64 / count_sum;
-                next_step.share = share;
-                next_step.range_start = range_start;
-                next_step.range_end = range_start + share;
-                range_start += share;
-            }
+    }
+
+    /// Generate a list of distinct words in the order they're generated.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it's not possible to generate `target_count` distinct words with these example words and settings.
+    pub fn generate(&self, target_count: usize) -> Vec<String> {
+        let v: Vec<String> = self.iter().take(target_count).collect();
+        assert_eq!(
+            v.len(),
+            target_count,
+            "Could only generate {} distinct words.",
+            v.len()
+        );
+        if !self.is_silent {
+            println!("{:#?}", v.len());
         }
+        v
     }
-    sequence_map
+
+    // Build one word from start to end, returning None if it doesn't fit the settings.
+    fn generate_one<R: Rng>(&self, rng: &mut R) -> Option<String> {
+        let final_word = self.model.generate_word(rng);
+        if final_word.len() < self.min_length.max(1) || final_word.len() > self.max_length {
+            return None;
+        }
+        if self.exclude_example_words && self.example_words.contains(&final_word) {
+            return None;
+        }
+        Some(final_word)
+    }
+}
+
+/// Iterator over distinct generated words, returned by `WordGenerator::iter()`.
+pub struct GeneratedWords<'a> {
+    generator: &'a WordGenerator,
+    rng: ChaCha8Rng,
+    words: HashSet<String>,
 }
 
-fn print_sequence_map(sequence_map: &SequenceMap) {
-    for depth in sequence_map.keys() {
-        println!("\nDepth = {}\n", depth);
-        let prefix_map = &sequence_map[depth];
-        for prefix in prefix_map.keys() {
-            println!("  {} ->", prefix);
-            for next_step in prefix_map[prefix].values() {
-                // println!("    {}: count = {}", next_step.value, next_step.count);
-                println!("    {:?}", next_step);
+impl<'a> Iterator for GeneratedWords<'a> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for _ in 0..MAX_ATTEMPTS_WITHOUT_NEW_WORD {
+            if let Some(word) = self.generator.generate_one(&mut self.rng) {
+                if !self.words.contains(&word) {
+                    if !self.generator.is_silent && self.words.len().is_multiple_of(1_000) {
+                        println!("[{}] {}", self.words.len(), word);
+                    }
+                    self.words.insert(word.clone());
+                    return Some(word);
+                }
             }
         }
+        None
     }
 }
 
@@ -253,6 +299,34 @@ pub fn words_from_file_test(filename: &str, expected_word_count: Option<usize>)
     v
 }
 
+/// Given a filename, create a Vec<(String, usize)> where each entry is one word and its count. This is the format of
+/// a frequency list, with a word, a tab and a count on each line. A line with only a word is taken to have a count of
+/// one so a plain word list can be read the same way. The words are in lowercase and blank lines are skipped.
+///
+/// # Panics
+///
+/// This will fail if the file does not exist or can't be opened for reading, or if a count is not a number.
+pub fn word_frequencies_from_file(filename: &str) -> Vec<(String, usize)> {
+    let file = File::open(filename).unwrap();
+    let mut v: Vec<(String, usize)> = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        let mut parts = line.trim().splitn(2, '\t');
+        let word = parts.next().unwrap().trim();
+        if !word.is_empty() {
+            let count = match parts.next() {
+                Some(count) => count
+                    .trim()
+                    .parse::<usize>()
+                    .expect("Error reading a count."),
+                None => 1,
+            };
+            v.push((word.to_lowercase(), count));
+        }
+    }
+    v
+}
+
 pub fn file_from_lines(filename: &str, lines: &[String]) {
     let mut file = File::create(filename).expect("Error creating file.");
     for line in lines {
@@ -0,0 +1,2789 @@
+use std::cmp::{self, Ordering};
+use std::collections::btree_map;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::{self, Debug};
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::iter;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use crate::trie_validation::{SubtreeCounts, TrieValidator};
+use crate::*;
+
+/// A trie that maps each key to a value such as a frequency, a definition ID, a part of speech or a score.
+///
+/// A key is a sequence of elements of type `K`, one per node. For a letter trie these are chars and the type is
+/// available as `LetterTrieMap<V>`, but the same insert, find, iterate and merge logic works for bytes
+/// (`ByteTrieMap<V>`), for whole words as in a phrase dictionary (`TokenTrieMap<V>`) or for any other type that
+/// implements `KeyElement`.
+///
+/// Like NoParentLetterTrie, the trie owns the root node, each node directly owns its child nodes and there are no
+/// parent links. A node whose key elements form a whole key holds `Some(value)` so `is_word` is the same as
+/// `value.is_some()`. The normalizer, the surface forms and the suffix index belong to the trie as a whole and are
+/// kept here rather than in the nodes.
+///
+/// The plain set of words used by the other implementations is the special case `LetterTrieMap<()>`, also
+/// available as `LetterTrieSet`, and that is the type that implements `LetterTrie`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+/// assert_eq!(trie.insert("creature", 12), None);
+/// assert_eq!(trie.insert("create", 40), None);
+/// assert_eq!(trie.insert("Create", 41), Some(40));
+///
+/// *trie.entry("cross").or_insert(0) += 1;
+/// *trie.entry("cross").or_insert(0) += 1;
+///
+/// assert_eq!(trie.get("cross"), Some(&2));
+/// assert_eq!(trie.get("crea"), None);
+///
+/// let words: Vec<(String, &usize)> = trie.iter_prefix("crea").collect();
+/// assert_eq!(words, vec![("create".to_owned(), &41), ("creature".to_owned(), &12)]);
+/// ```
+pub struct TrieMap<K, V> {
+    root: TrieMapNode<K, V>,
+    // The original spellings of each word, kept only if the normalizer preserves surface forms.
+    surface_forms: BTreeMap<Vec<K>, Vec<String>>,
+    // None means the default normalizer.
+    normalizer: Option<Box<Normalizer>>,
+    // A companion trie holding each key in reverse.
+    suffix_index: Option<Box<TrieMapNode<K, ()>>>,
+}
+
+// One node of a TrieMap, along with the cached values for its subtree if it's frozen.
+pub(crate) struct TrieMapNode<K, V> {
+    key: K,
+    depth: usize,
+    children: BTreeMap<K, Self>,
+    value: Option<V>,
+    is_frozen: bool,
+    node_count: Option<usize>,
+    word_count: Option<usize>,
+    height: Option<usize>,
+    max_frequency: Option<usize>,
+}
+
+/// A trie with one letter per node that maps words to values.
+pub type LetterTrieMap<V> = TrieMap<char, V>;
+
+/// A letter trie with no payloads, equivalent to the other implementations of LetterTrie.
+pub type LetterTrieSet = LetterTrieMap<()>;
+
+// A normalized word from one line of a word file along with the line as it was written, if it's being kept.
+type WordLine = (Vec<char>, Option<String>);
+
+/// A trie with one byte per node for binary keys such as IP address prefixes.
+pub type ByteTrieMap<V> = TrieMap<u8, V>;
+
+/// A trie with one whole word per node for keys that are phrases.
+pub type TokenTrieMap<V> = TrieMap<String, V>;
+
+/// One element of a key in a TrieMap, such as a letter, a byte or a whole word.
+///
+/// To make a trie over a custom alphabet, implement this for a small enum.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// enum Base { Root, A, C, G, T }
+///
+/// impl KeyElement for Base {
+///     type Key = Vec<Base>;
+///
+///     fn root_sentinel() -> Self {
+///         Base::Root
+///     }
+///
+///     fn make_key(elements: &[Self]) -> Self::Key {
+///         elements.to_vec()
+///     }
+/// }
+///
+/// let mut trie: TrieMap<Base, &str> = TrieMap::new();
+/// trie.insert(&[Base::A, Base::T, Base::G][..], "start codon");
+/// trie.insert(&[Base::T, Base::A, Base::A][..], "stop codon");
+/// assert_eq!(trie.get(&[Base::A, Base::T, Base::G][..]), Some(&"start codon"));
+/// assert_eq!(trie.iter_prefix(&[Base::T][..]).count(), 1);
+/// ```
+pub trait KeyElement: Ord + Clone + Debug {
+    /// The type of a whole key put together from its elements, such as a String made from chars.
+    type Key: Debug;
+
+    /// The element held by the root node, which is never part of a key.
+    fn root_sentinel() -> Self;
+
+    /// Put together a whole key from its elements.
+    fn make_key(elements: &[Self]) -> Self::Key;
+}
+
+impl KeyElement for char {
+    type Key = String;
+
+    fn root_sentinel() -> Self {
+        ' '
+    }
+
+    fn make_key(elements: &[Self]) -> Self::Key {
+        elements.iter().collect()
+    }
+}
+
+impl KeyElement for u8 {
+    type Key = Vec<u8>;
+
+    fn root_sentinel() -> Self {
+        0
+    }
+
+    fn make_key(elements: &[Self]) -> Self::Key {
+        elements.to_vec()
+    }
+}
+
+impl KeyElement for String {
+    type Key = Vec<String>;
+
+    fn root_sentinel() -> Self {
+        String::new()
+    }
+
+    fn make_key(elements: &[Self]) -> Self::Key {
+        elements.to_vec()
+    }
+}
+
+/// Something that can be split into the elements of a key for a TrieMap, such as a word split into its letters.
+pub trait ToKeyElements<K> {
+    /// Split into key elements after applying the trie's normalizer where it makes sense for the type of key.
+    /// Returns None if the normalizer rejects the key, which means it can't be in the trie.
+    fn to_key_elements(&self, normalizer: &Normalizer) -> Option<Vec<K>>;
+
+    /// The key as it was written, before normalization, for tries that preserve surface forms. Keys that aren't
+    /// text have no surface form.
+    fn surface_form(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A word is split into letters after normalization, which by default means it's trimmed and converted to
+/// lowercase.
+impl ToKeyElements<char> for str {
+    fn to_key_elements(&self, normalizer: &Normalizer) -> Option<Vec<char>> {
+        normalizer.to_chars(self)
+    }
+
+    fn surface_form(&self) -> Option<String> {
+        Some(self.trim().to_owned())
+    }
+}
+
+impl ToKeyElements<char> for String {
+    fn to_key_elements(&self, normalizer: &Normalizer) -> Option<Vec<char>> {
+        self.as_str().to_key_elements(normalizer)
+    }
+
+    fn surface_form(&self) -> Option<String> {
+        ToKeyElements::<char>::surface_form(self.as_str())
+    }
+}
+
+/// A string used as a binary key is split into its bytes exactly as they are and the normalizer isn't used.
+impl ToKeyElements<u8> for str {
+    fn to_key_elements(&self, _normalizer: &Normalizer) -> Option<Vec<u8>> {
+        Some(self.as_bytes().to_vec())
+    }
+}
+
+/// A phrase is split on whitespace and each word is normalized on its own.
+impl ToKeyElements<String> for str {
+    fn to_key_elements(&self, normalizer: &Normalizer) -> Option<Vec<String>> {
+        self.split_whitespace()
+            .map(|x| normalizer.normalize(x).ok())
+            .collect()
+    }
+
+    fn surface_form(&self) -> Option<String> {
+        Some(self.split_whitespace().collect::<Vec<&str>>().join(" "))
+    }
+}
+
+/// Key elements that are already split up are used exactly as they are.
+impl<K: Clone> ToKeyElements<K> for [K] {
+    fn to_key_elements(&self, _normalizer: &Normalizer) -> Option<Vec<K>> {
+        Some(self.to_vec())
+    }
+}
+
+impl<K: Clone> ToKeyElements<K> for Vec<K> {
+    fn to_key_elements(&self, _normalizer: &Normalizer) -> Option<Vec<K>> {
+        Some(self.clone())
+    }
+}
+
+/// A reference works the same as the value it refers to, so a range of string slices such as `"cat".."dog"` can be
+/// passed to `TrieMap::range()`.
+impl<K, T: ToKeyElements<K> + ?Sized> ToKeyElements<K> for &T {
+    fn to_key_elements(&self, normalizer: &Normalizer) -> Option<Vec<K>> {
+        (**self).to_key_elements(normalizer)
+    }
+
+    fn surface_form(&self) -> Option<String> {
+        (**self).surface_form()
+    }
+}
+
+/// A value in a LetterTrieMap that has a frequency (or any other count or score) used to rank words, as in
+/// `LetterTrieMap::top_k_completions()`.
+pub trait Frequency {
+    fn frequency(&self) -> usize;
+}
+
+impl Frequency for usize {
+    fn frequency(&self) -> usize {
+        *self
+    }
+}
+
+impl Frequency for u32 {
+    fn frequency(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl Frequency for u64 {
+    fn frequency(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Every word in a set counts the same.
+impl Frequency for () {
+    fn frequency(&self) -> usize {
+        1
+    }
+}
+
+impl<K: KeyElement, V> TrieMap<K, V> {
+    /// Constructor for the trie. The root of each trie is the same regardless of what keys will be added to the
+    /// trie so there are no parameters.
+    pub fn new() -> Self {
+        let key = K::root_sentinel();
+        let depth = 0;
+        Self {
+            root: TrieMapNode::make_node(key, depth),
+            surface_forms: BTreeMap::new(),
+            normalizer: None,
+            suffix_index: None,
+        }
+    }
+
+    /// Create an empty trie that normalizes keys with the given normalizer instead of the default
+    /// `trim().to_lowercase()`. See `ToKeyElements` for how the normalizer applies to each type of key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<&str> = LetterTrieMap::with_normalizer(Normalizer::make_exact());
+    /// trie.insert("Polish", "from Poland");
+    /// trie.insert("polish", "to make shiny");
+    /// assert_eq!(trie.get("Polish"), Some(&"from Poland"));
+    /// assert_eq!(trie.len(), 2);
+    /// ```
+    pub fn with_normalizer(normalizer: Normalizer) -> Self {
+        let mut t = Self::new();
+        if !normalizer.is_default() {
+            t.normalizer = Some(Box::new(normalizer));
+        }
+        t
+    }
+
+    // The root node, for structures built on top of the trie.
+    pub(crate) fn root(&self) -> &TrieMapNode<K, V> {
+        &self.root
+    }
+
+    /// The normalizer applied to every key that's added or looked up.
+    pub fn normalizer(&self) -> &Normalizer {
+        self.normalizer.as_deref().unwrap_or(&DEFAULT_NORMALIZER)
+    }
+
+    /// Add a word with its value. If the word was already present its old value is replaced and returned.
+    ///
+    /// As with loading from a file the word is passed through the trie's normalizer, so by default it may be upper-
+    /// or lowercase and whitespace before or after the word is ignored. A blank word or one that the normalizer
+    /// rejects is ignored entirely and the return value is `None`.
+    pub fn insert<Q: ToKeyElements<K> + ?Sized>(&mut self, word: &Q, value: V) -> Option<V> {
+        match word.to_key_elements(self.normalizer()) {
+            Some(v) if !v.is_empty() => {
+                let surface_form = self.surface_form_to_keep(word);
+                self.add_to_suffix_index(&v);
+                self.add_surface_form(&v, surface_form);
+                self.root.get_or_create_node(&v).value.replace(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the value for a word if the word is in the trie. A partial word that isn't itself a word has no value.
+    pub fn get<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<&V> {
+        let v = word.to_key_elements(self.normalizer())?;
+        self.get_node(&v).and_then(|node| node.value.as_ref())
+    }
+
+    /// Get a mutable reference to the value for a word if the word is in the trie.
+    pub fn get_mut<Q: ToKeyElements<K> + ?Sized>(&mut self, word: &Q) -> Option<&mut V> {
+        let v = word.to_key_elements(self.normalizer())?;
+        self.root
+            .get_node_mut(&v)
+            .and_then(|node| node.value.as_mut())
+    }
+
+    /// The original spellings of a word in a trie that preserves surface forms, in the order they were added. This
+    /// is empty if the word isn't in the trie or surface forms aren't preserved.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<()> = LetterTrieMap::with_normalizer(Normalizer::make_folded_preserving());
+    /// trie.insert("Zürich", ());
+    /// trie.insert("ZURICH", ());
+    /// trie.insert("iPhone", ());
+    /// assert_eq!(trie.surface_forms("zurich"), &["Zürich".to_owned(), "ZURICH".to_owned()]);
+    /// assert_eq!(trie.surface_forms("IPHONE"), &["iPhone".to_owned()]);
+    /// ```
+    pub fn surface_forms<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> &[String] {
+        word.to_key_elements(self.normalizer())
+            .and_then(|v| self.surface_forms.get(&v))
+            .map_or(&[], |surface_forms| surface_forms.as_slice())
+    }
+
+    /// Find every word that's a prefix of a string, from shortest to longest, along with their values. The string
+    /// itself is included if it's a word.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+    /// for (word, count) in vec![("the", 50), ("them", 8), ("theme", 2), ("men", 6)] {
+    ///     trie.insert(word, count);
+    /// }
+    /// assert_eq!(
+    ///     trie.all_prefix_words("themen"),
+    ///     vec![("the".to_owned(), &50), ("them".to_owned(), &8), ("theme".to_owned(), &2)]
+    /// );
+    /// assert_eq!(trie.longest_prefix_word("thermos"), Some(("the".to_owned(), &50)));
+    /// ```
+    pub fn all_prefix_words<Q: ToKeyElements<K> + ?Sized>(&self, s: &Q) -> Vec<(K::Key, &V)> {
+        match s.to_key_elements(self.normalizer()) {
+            Some(v) => self
+                .root
+                .prefix_word_lengths(&v)
+                .into_iter()
+                .map(|(len, value)| (K::make_key(&v[..len]), value))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Find the longest word that's a prefix of a string, which may be the string itself, along with its value.
+    pub fn longest_prefix_word<Q: ToKeyElements<K> + ?Sized>(&self, s: &Q) -> Option<(K::Key, &V)> {
+        self.all_prefix_words(s).pop()
+    }
+
+    /// Returns true if the word is in the trie. A partial word that isn't itself a word returns false.
+    pub fn contains_word<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> bool {
+        self.get(word).is_some()
+    }
+
+    /// Remove a word from the trie and return its value if it was present.
+    ///
+    /// Any nodes that are left without a word either at the node or below it are removed as well so the trie ends
+    /// up with the same shape it would have if the word had never been added.
+    pub fn remove<Q: ToKeyElements<K> + ?Sized>(&mut self, word: &Q) -> Option<V> {
+        match word.to_key_elements(self.normalizer()) {
+            Some(v) if !v.is_empty() => {
+                let value = self.root.remove_one_node(&v, 0);
+                if value.is_some() {
+                    self.surface_forms.remove(&v);
+                    if let Some(suffix_index) = &mut self.suffix_index {
+                        let reversed: Vec<K> = v.iter().rev().cloned().collect();
+                        suffix_index.remove_one_node(&reversed, 0);
+                    }
+                }
+                value
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the entry for a word for in-place manipulation, in the style of `BTreeMap::entry()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the word is blank or if the trie's normalizer rejects it.
+    pub fn entry<Q: ToKeyElements<K> + ?Sized>(&mut self, word: &Q) -> Entry<'_, K, V> {
+        let key = word
+            .to_key_elements(self.normalizer())
+            .expect("A word rejected by the normalizer can't be an entry in the trie.");
+        assert!(
+            !key.is_empty(),
+            "A blank word can't be an entry in the trie."
+        );
+        let surface_form = self.surface_form_to_keep(word);
+        if self.get_node(&key).is_some_and(|node| node.value.is_some()) {
+            self.add_surface_form(&key, surface_form);
+            let node = self.root.get_node_mut(&key).unwrap();
+            Entry::Occupied(OccupiedEntry {
+                key,
+                value: &mut node.value,
+            })
+        } else {
+            Entry::Vacant(VacantEntry {
+                key,
+                surface_form,
+                map: self,
+            })
+        }
+    }
+
+    /// Move all of the words in another trie into this one. Where a word is in both tries the value from `other`
+    /// replaces the value in this trie. The words in `other` are taken as they are, without applying this trie's
+    /// normalizer.
+    pub fn merge(&mut self, other: Self) {
+        if self.suffix_index.is_some() {
+            let mut iter = other.iter();
+            while let Some((v, _)) = iter.next_node() {
+                self.add_to_suffix_index(&v);
+            }
+        }
+        for (v, surface_forms) in other.surface_forms {
+            for surface_form in surface_forms {
+                self.add_surface_form(&v, Some(surface_form));
+            }
+        }
+        self.root.merge_node(other.root);
+    }
+
+    /// Iterate over all of the words in the trie in alphabetical order along with their values.
+    pub fn iter(&self) -> TrieMapIterator<'_, K, V> {
+        TrieMapIterator {
+            stack: vec![(vec![], &self.root)],
+        }
+    }
+
+    /// Iterate in alphabetical order over the words that start with a given prefix, along with their values. The
+    /// prefix itself is included if it's a word.
+    pub fn iter_prefix<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+    ) -> TrieMapIterator<'_, K, V> {
+        let stack = match prefix.to_key_elements(self.normalizer()) {
+            Some(v) => match self.get_node(&v) {
+                Some(node) => vec![(v, node)],
+                None => vec![],
+            },
+            None => vec![],
+        };
+        TrieMapIterator { stack }
+    }
+
+    /// The number of words in the trie.
+    pub fn len(&self) -> usize {
+        self.word_count()
+    }
+
+    /// Returns true if there are no words in the trie.
+    pub fn is_empty(&self) -> bool {
+        self.root.children.is_empty()
+    }
+
+    /// The number of words that start with a prefix, including the prefix itself if it's a word. This uses the
+    /// cached word count of the prefix's node after `freeze()`.
+    pub fn prefix_word_count<Q: ToKeyElements<K> + ?Sized>(&self, prefix: &Q) -> usize {
+        prefix
+            .to_key_elements(self.normalizer())
+            .and_then(|v| self.get_node(&v))
+            .map_or(0, |node| node.word_count())
+    }
+
+    /// The number of words in the trie that sort before a word, whether or not the word itself is in the trie.
+    /// Returns None if the normalizer rejects the word.
+    ///
+    /// The walk down to the word adds up the word counts of the children to the left of the path, so after
+    /// `freeze()` this takes time proportional to the length of the word times the number of children per node.
+    /// Without `freeze()` the word counts have to be calculated each time.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let mut trie = LetterTrieSet::from_file(
+    ///     dataset.filename(),
+    ///     dataset.is_sorted(),
+    ///     &LoadMethod::Continuous,
+    /// );
+    /// trie.freeze();
+    ///
+    /// assert_eq!(trie.rank("azure"), Some(3));
+    /// assert_eq!(trie.rank("b"), Some(4));
+    /// assert_eq!(trie.select(4), Some(("crease".to_owned(), &())));
+    /// assert_eq!(trie.floor("b"), Some(("azure".to_owned(), &())));
+    /// assert_eq!(trie.ceiling("b"), Some(("crease".to_owned(), &())));
+    ///
+    /// let words: Vec<String> = trie.range("and".."creator").map(|(word, _)| word).collect();
+    /// assert_eq!(words, vec!["and", "azure", "crease", "creative"]);
+    /// ```
+    pub fn rank<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<usize> {
+        let v = word.to_key_elements(self.normalizer())?;
+        Some(self.root.rank_elements(&v))
+    }
+
+    /// The word at a given position in sorted order, starting from 0, along with its value. Returns None if
+    /// there are `n` or fewer words. Like `rank()`, this is fast after `freeze()`.
+    pub fn select(&self, n: usize) -> Option<(K::Key, &V)> {
+        self.root.select(n)
+    }
+
+    /// Iterate in sorted order over the words within a range, along with their values, as in
+    /// `trie.range("cat".."dog")` or `trie.range("cat"..)`. The bounds are normalized the same way as the words
+    /// and don't have to be words in the trie. If the normalizer rejects a bound the range is empty.
+    pub fn range<Q, R>(&self, range: R) -> TrieMapRange<'_, K, V>
+    where
+        Q: ToKeyElements<K> + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let normalizer = self.normalizer();
+        let bound_elements = |bound: Bound<&Q>| -> Option<Bound<Vec<K>>> {
+            match bound {
+                Bound::Included(word) => word.to_key_elements(normalizer).map(Bound::Included),
+                Bound::Excluded(word) => word.to_key_elements(normalizer).map(Bound::Excluded),
+                Bound::Unbounded => Some(Bound::Unbounded),
+            }
+        };
+        match (
+            bound_elements(range.start_bound()),
+            bound_elements(range.end_bound()),
+        ) {
+            (Some(start), Some(end)) => TrieMapRange {
+                iter: self.root.iter_from(&start),
+                end,
+            },
+            _ => TrieMapRange {
+                iter: TrieMapIterator { stack: vec![] },
+                end: Bound::Unbounded,
+            },
+        }
+    }
+
+    /// The greatest word that's less than or equal to a word, along with its value.
+    pub fn floor<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<(K::Key, &V)> {
+        let v = word.to_key_elements(self.normalizer())?;
+        match self.get_node(&v).and_then(|node| node.value.as_ref()) {
+            Some(value) => Some((K::make_key(&v), value)),
+            None => self.prev_word_elements(&v),
+        }
+    }
+
+    /// The least word that's greater than or equal to a word, along with its value.
+    pub fn ceiling<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<(K::Key, &V)> {
+        let v = word.to_key_elements(self.normalizer())?;
+        self.select(self.root.rank_elements(&v))
+    }
+
+    /// The least word that's strictly greater than a word, along with its value. The word itself doesn't have to
+    /// be in the trie.
+    pub fn next_word<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<(K::Key, &V)> {
+        let v = word.to_key_elements(self.normalizer())?;
+        let is_word = self.get_node(&v).is_some_and(|node| node.value.is_some());
+        self.select(self.root.rank_elements(&v) + if is_word { 1 } else { 0 })
+    }
+
+    /// The greatest word that's strictly less than a word, along with its value. The word itself doesn't have to
+    /// be in the trie.
+    pub fn prev_word<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<(K::Key, &V)> {
+        let v = word.to_key_elements(self.normalizer())?;
+        self.prev_word_elements(&v)
+    }
+
+    fn prev_word_elements(&self, v: &[K]) -> Option<(K::Key, &V)> {
+        self.root
+            .rank_elements(v)
+            .checked_sub(1)
+            .and_then(|rank| self.select(rank))
+    }
+
+    pub(crate) fn get_node(&self, v: &[K]) -> Option<&TrieMapNode<K, V>> {
+        self.root.get_node(v)
+    }
+
+    /// Start keeping a companion trie with every key in reverse, which makes `words_with_suffix()` and
+    /// `words_with_prefix_and_suffix()` fast. The companion trie is built from the words already in the trie and
+    /// from then on it's kept in sync by `insert()`, `remove()`, `entry()` and `merge()`. `freeze()` caches the
+    /// counts in the companion trie as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let mut trie = LetterTrieSet::from_file(
+    ///     dataset.filename(),
+    ///     dataset.is_sorted(),
+    ///     &LoadMethod::ContinuousParallel,
+    /// );
+    /// trie.enable_suffix_index();
+    /// trie.insert("biology", ());
+    /// trie.insert("geology", ());
+    ///
+    /// let words: Vec<String> = trie.words_with_suffix("ology").into_iter().map(|(word, _)| word).collect();
+    /// assert_eq!(words, vec!["biology", "geology"]);
+    ///
+    /// let words: Vec<String> = trie
+    ///     .words_with_prefix_and_suffix("cr", "e")
+    ///     .into_iter()
+    ///     .map(|(word, _)| word)
+    ///     .collect();
+    /// assert_eq!(words, vec!["crease", "creative", "creature"]);
+    /// ```
+    pub fn enable_suffix_index(&mut self) {
+        let mut suffix_index = TrieMapNode::make_node(K::root_sentinel(), 0);
+        let mut iter = self.iter();
+        while let Some((v, _)) = iter.next_node() {
+            let reversed: Vec<K> = v.into_iter().rev().collect();
+            suffix_index.get_or_create_node(&reversed).value = Some(());
+        }
+        self.suffix_index = Some(Box::new(suffix_index));
+    }
+
+    /// Stop keeping the companion trie of reversed keys.
+    pub fn disable_suffix_index(&mut self) {
+        self.suffix_index = None;
+    }
+
+    /// Returns true if the trie is keeping a companion trie of reversed keys.
+    pub fn has_suffix_index(&self) -> bool {
+        self.suffix_index.is_some()
+    }
+
+    fn add_to_suffix_index(&mut self, v: &[K]) {
+        if let Some(suffix_index) = &mut self.suffix_index {
+            let reversed: Vec<K> = v.iter().rev().cloned().collect();
+            suffix_index.get_or_create_node(&reversed).value = Some(());
+        }
+    }
+
+    /// Find the words that end with a suffix, in alphabetical order along with their values. The suffix itself is
+    /// included if it's a word.
+    ///
+    /// With a suffix index from `enable_suffix_index()` this walks the companion trie to the reversed suffix and
+    /// visits only the words below that node. Without one it has to check every word in the trie.
+    pub fn words_with_suffix<Q: ToKeyElements<K> + ?Sized>(&self, suffix: &Q) -> Vec<(K::Key, &V)> {
+        match suffix.to_key_elements(self.normalizer()) {
+            Some(suffix) => self.words_with_prefix_and_suffix_elements(&[], &suffix),
+            None => vec![],
+        }
+    }
+
+    /// Find the words that start with a prefix and end with a suffix, in alphabetical order along with their
+    /// values. The prefix and suffix may overlap, so both "ab" and "ba" match "aba".
+    ///
+    /// With a suffix index this walks to the prefix in the trie and to the reversed suffix in the companion trie,
+    /// then goes through one of the two subtrees and checks each word against the other condition. After
+    /// `freeze()` the word counts of both subtrees are cached and the one with fewer words is chosen, otherwise
+    /// it's the subtree below the suffix.
+    pub fn words_with_prefix_and_suffix<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+        suffix: &Q,
+    ) -> Vec<(K::Key, &V)> {
+        let normalizer = self.normalizer();
+        match (
+            prefix.to_key_elements(normalizer),
+            suffix.to_key_elements(normalizer),
+        ) {
+            (Some(prefix), Some(suffix)) => {
+                self.words_with_prefix_and_suffix_elements(&prefix, &suffix)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn words_with_prefix_and_suffix_elements(
+        &self,
+        prefix: &[K],
+        suffix: &[K],
+    ) -> Vec<(K::Key, &V)> {
+        let prefix_node = match self.get_node(prefix) {
+            Some(node) => node,
+            None => return vec![],
+        };
+        let mut keys: Vec<Vec<K>> = vec![];
+        let reversed_suffix: Vec<K> = suffix.iter().rev().cloned().collect();
+        let suffix_node = self
+            .suffix_index
+            .as_ref()
+            .map(|suffix_index| suffix_index.get_node(&reversed_suffix));
+        // Counting the words below a node that isn't frozen would take as long as walking them, so the two
+        // subtrees are only compared if both word counts are cached. Otherwise the suffix side is walked, since a
+        // suffix is usually more selective than a prefix.
+        let is_suffix_smaller = |suffix_node: &TrieMapNode<K, ()>| match (
+            suffix_node.word_count,
+            prefix_node.word_count,
+        ) {
+            (Some(suffix_count), Some(prefix_count)) => suffix_count < prefix_count,
+            _ => true,
+        };
+        match suffix_node {
+            Some(None) => return vec![],
+            Some(Some(suffix_node)) if is_suffix_smaller(suffix_node) => {
+                let mut iter = TrieMapIterator {
+                    stack: vec![(reversed_suffix, suffix_node)],
+                };
+                while let Some((reversed, _)) = iter.next_node() {
+                    let v: Vec<K> = reversed.into_iter().rev().collect();
+                    if v.starts_with(prefix) {
+                        keys.push(v);
+                    }
+                }
+                keys.sort();
+            }
+            _ => {
+                let mut iter = TrieMapIterator {
+                    stack: vec![(prefix.to_vec(), prefix_node)],
+                };
+                while let Some((v, _)) = iter.next_node() {
+                    if v.ends_with(suffix) {
+                        keys.push(v);
+                    }
+                }
+            }
+        }
+        keys.into_iter()
+            .map(|v| {
+                let value = self.get_node(&v).unwrap().value.as_ref().unwrap();
+                (K::make_key(&v), value)
+            })
+            .collect()
+    }
+
+    fn surface_form_to_keep<Q: ToKeyElements<K> + ?Sized>(&self, word: &Q) -> Option<String> {
+        if self.normalizer().preserve_surface_forms {
+            word.surface_form()
+        } else {
+            None
+        }
+    }
+
+    fn add_surface_form(&mut self, v: &[K], surface_form: Option<String>) {
+        if let Some(surface_form) = surface_form {
+            let surface_forms = self.surface_forms.entry(v.to_vec()).or_default();
+            if !surface_forms.contains(&surface_form) {
+                surface_forms.push(surface_form);
+            }
+        }
+    }
+
+    /// Find the words within a given edit distance of a word, meaning the number of letters (or other key
+    /// elements) that would have to be inserted, deleted or replaced to turn one into the other. The distance is
+    /// measured after normalization. The results are sorted by distance and then alphabetically.
+    ///
+    /// The search keeps one row of the edit distance table per node on the current path and skips any subtree
+    /// where every entry in the row is already over `max_distance`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<()> = LetterTrieMap::new();
+    /// for word in vec!["cross", "crossed", "creature", "crease"] {
+    ///     trie.insert(word, ());
+    /// }
+    /// let words: Vec<(String, usize)> = trie
+    ///     .fuzzy_search("cros", 2)
+    ///     .into_iter()
+    ///     .map(|(word, distance, _)| (word, distance))
+    ///     .collect();
+    /// assert_eq!(words, vec![("cross".to_owned(), 1)]);
+    /// ```
+    pub fn fuzzy_search<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        word: &Q,
+        max_distance: usize,
+    ) -> Vec<(K::Key, usize, &V)> {
+        self.fuzzy_search_nodes(word, max_distance)
+            .into_iter()
+            .map(|(key, distance, node)| {
+                (K::make_key(&key), distance, node.value.as_ref().unwrap())
+            })
+            .collect()
+    }
+
+    fn fuzzy_search_nodes<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        word: &Q,
+        max_distance: usize,
+    ) -> Vec<(Vec<K>, usize, &TrieMapNode<K, V>)> {
+        let mut matches = vec![];
+        let target = match word.to_key_elements(self.normalizer()) {
+            Some(v) => v,
+            None => return matches,
+        };
+        let first_row: Vec<usize> = (0..=target.len()).collect();
+        let mut prefix = vec![];
+        for child_node in self.root.children.values() {
+            child_node.fuzzy_search_one_node(
+                &target,
+                &first_row,
+                max_distance,
+                &mut prefix,
+                &mut matches,
+            );
+        }
+        // The matches were found in alphabetical order and the sort is stable.
+        matches.sort_by_key(|(_, distance, _)| *distance);
+        matches
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.root.node_count()
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.root.word_count()
+    }
+
+    pub fn height(&self) -> usize {
+        self.root.height()
+    }
+
+    /// Cache the node count, word count and height at every node so that they don't have to be recalculated
+    /// from the whole subtree each time.
+    ///
+    /// Unlike BaseLetterTrie the trie may still be changed after it's frozen. Any change clears the cached values
+    /// on the path from the root to the changed node, and since the other nodes keep their cached values a
+    /// second call to `freeze()` only has to visit that path.
+    pub fn freeze(&mut self) {
+        self.root.freeze();
+        if let Some(suffix_index) = &mut self.suffix_index {
+            suffix_index.freeze();
+        }
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.root.unfreeze();
+        if let Some(suffix_index) = &mut self.suffix_index {
+            suffix_index.unfreeze();
+        }
+    }
+
+    /// Estimate the memory the trie uses on the heap. See `HeapSize`. Only the size of each key and value is
+    /// counted, not any memory they point to such as the text of a `String` key.
+    pub fn heap_size(&self) -> HeapSize {
+        let mut h = HeapSize::default();
+        if let Some(normalizer) = &self.normalizer {
+            h.string_bytes += mem::size_of::<Normalizer>();
+            h.add_normalizer(normalizer);
+        }
+        h.string_bytes += btree_map_heap_size::<Vec<K>, Vec<String>>(self.surface_forms.len());
+        for (v, surface_forms) in self.surface_forms.iter() {
+            h.string_bytes += v.capacity() * mem::size_of::<K>();
+            h.string_bytes += surface_forms.capacity() * mem::size_of::<String>();
+            for surface_form in surface_forms.iter() {
+                h.string_bytes += surface_form.capacity();
+            }
+        }
+        if let Some(suffix_index) = &self.suffix_index {
+            let mut suffix_h = HeapSize::default();
+            suffix_h.node_bytes += mem::size_of::<TrieMapNode<K, ()>>();
+            suffix_index.add_heap_size(&mut suffix_h);
+            h.add_bytes(&suffix_h);
+        }
+        self.root.add_heap_size(&mut h);
+        h
+    }
+
+    pub fn describe_one_line(&self) -> String {
+        self.root.describe_one_line()
+    }
+
+    pub fn describe_deep(&self, s: &mut String, depth: usize) {
+        self.root.describe_deep(s, depth);
+    }
+}
+
+impl<K: KeyElement, V> TrieMapNode<K, V> {
+    fn make_node(key: K, depth: usize) -> Self {
+        Self {
+            key,
+            depth,
+            children: BTreeMap::new(),
+            value: None,
+            is_frozen: false,
+            node_count: None,
+            word_count: None,
+            height: None,
+            max_frequency: None,
+        }
+    }
+
+    // The child nodes in key order, for structures built on top of the trie.
+    pub(crate) fn children(&self) -> &BTreeMap<K, Self> {
+        &self.children
+    }
+
+    pub(crate) fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    pub(crate) fn get_node(&self, v: &[K]) -> Option<&Self> {
+        let mut node = self;
+        for c in v {
+            node = node.children.get(c)?;
+        }
+        Some(node)
+    }
+
+    // Any caller may change the value at the node so the cached values along the path are cleared.
+    fn get_node_mut(&mut self, v: &[K]) -> Option<&mut Self> {
+        self.get_node(v)?;
+        let mut node = self;
+        for c in v {
+            node.unfreeze_one();
+            node = node.children.get_mut(c).unwrap();
+        }
+        node.unfreeze_one();
+        Some(node)
+    }
+
+    fn get_or_create_node(&mut self, v: &[K]) -> &mut Self {
+        self.get_or_create_node_counted(v, None)
+    }
+
+    // The same as get_or_create_node() while recording the hits, misses and new nodes if there are counts to update.
+    fn get_or_create_node_counted(
+        &mut self,
+        v: &[K],
+        mut counts: Option<&mut InsertCounts>,
+    ) -> &mut Self {
+        let mut node = self;
+        for c in v {
+            node.unfreeze_one();
+            let depth = node.depth + 1;
+            node = match node.children.entry(c.clone()) {
+                btree_map::Entry::Occupied(e) => {
+                    if let Some(counts) = counts.as_deref_mut() {
+                        counts.record(true);
+                    }
+                    e.into_mut()
+                }
+                btree_map::Entry::Vacant(e) => {
+                    if let Some(counts) = counts.as_deref_mut() {
+                        counts.record(false);
+                        counts.record_node();
+                    }
+                    e.insert(Self::make_node(c.clone(), depth))
+                }
+            };
+        }
+        node.unfreeze_one();
+        node
+    }
+
+    // The lengths of the words that are prefixes of a sequence of key elements, from shortest to longest, along
+    // with their values.
+    pub(crate) fn prefix_word_lengths(&self, v: &[K]) -> Vec<(usize, &V)> {
+        let mut lengths = vec![];
+        let mut node = self;
+        for (index, c) in v.iter().enumerate() {
+            match node.children.get(c) {
+                Some(child_node) => {
+                    if let Some(value) = &child_node.value {
+                        lengths.push((index + 1, value));
+                    }
+                    node = child_node;
+                }
+                None => break,
+            }
+        }
+        lengths
+    }
+
+    fn remove_one_node(&mut self, v: &[K], char_index: usize) -> Option<V> {
+        let c = &v[char_index];
+        self.unfreeze_one();
+        let child_node = self.children.get_mut(c)?;
+        let value = if char_index == v.len() - 1 {
+            child_node.value.take()
+        } else {
+            child_node.remove_one_node(v, char_index + 1)
+        };
+        child_node.unfreeze_one();
+        if child_node.value.is_none() && child_node.children.is_empty() {
+            self.children.remove(c);
+        }
+        value
+    }
+
+    fn merge_node(&mut self, other: Self) {
+        self.unfreeze_one();
+        if other.value.is_some() {
+            self.value = other.value;
+        }
+        for (c, other_child_node) in other.children {
+            match self.children.entry(c) {
+                btree_map::Entry::Vacant(e) => {
+                    e.insert(other_child_node);
+                }
+                btree_map::Entry::Occupied(mut e) => {
+                    e.get_mut().merge_node(other_child_node);
+                }
+            }
+        }
+    }
+
+    fn rank_elements(&self, v: &[K]) -> usize {
+        let mut rank = 0;
+        let mut node = self;
+        for c in v {
+            // A word that's a proper prefix of this one sorts before it.
+            if node.value.is_some() {
+                rank += 1;
+            }
+            for child_node in node.children.range(..c).map(|(_, child_node)| child_node) {
+                rank += child_node.word_count();
+            }
+            match node.children.get(c) {
+                Some(child_node) => node = child_node,
+                None => return rank,
+            }
+        }
+        rank
+    }
+
+    fn select(&self, n: usize) -> Option<(K::Key, &V)> {
+        let mut n = n;
+        let mut node = self;
+        let mut v = vec![];
+        'descend: loop {
+            if let Some(value) = &node.value {
+                if n == 0 {
+                    return Some((K::make_key(&v), value));
+                }
+                n -= 1;
+            }
+            for (c, child_node) in node.children.iter() {
+                let word_count = child_node.word_count();
+                if n < word_count {
+                    v.push(c.clone());
+                    node = child_node;
+                    continue 'descend;
+                }
+                n -= word_count;
+            }
+            return None;
+        }
+    }
+
+    // An iterator that starts at the first word within a lower bound. The stack holds the subtrees to the right of
+    // the path down to the bound, with the nearest on top.
+    fn iter_from(&self, start: &Bound<Vec<K>>) -> TrieMapIterator<'_, K, V> {
+        let (v, is_included) = match start {
+            Bound::Included(v) => (v, true),
+            Bound::Excluded(v) => (v, false),
+            Bound::Unbounded => {
+                return TrieMapIterator {
+                    stack: vec![(vec![], self)],
+                }
+            }
+        };
+        let mut stack = vec![];
+        let mut node = self;
+        let mut prefix = vec![];
+        for c in v {
+            for (child_c, child_node) in node
+                .children
+                .range((Bound::Excluded(c), Bound::Unbounded))
+                .rev()
+            {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(child_c.clone());
+                stack.push((child_prefix, child_node));
+            }
+            match node.children.get(c) {
+                Some(child_node) => {
+                    prefix.push(c.clone());
+                    node = child_node;
+                }
+                None => return TrieMapIterator { stack },
+            }
+        }
+        if is_included {
+            stack.push((prefix, node));
+        } else {
+            for (child_c, child_node) in node.children.iter().rev() {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(child_c.clone());
+                stack.push((child_prefix, child_node));
+            }
+        }
+        TrieMapIterator { stack }
+    }
+
+    fn fuzzy_search_one_node<'a>(
+        &'a self,
+        target: &[K],
+        prev_row: &[usize],
+        max_distance: usize,
+        prefix: &mut Vec<K>,
+        matches: &mut Vec<(Vec<K>, usize, &'a Self)>,
+    ) {
+        prefix.push(self.key.clone());
+        let mut row = vec![prev_row[0] + 1];
+        for (i, c) in target.iter().enumerate() {
+            let replace_cost = if *c == self.key { 0 } else { 1 };
+            let distance = cmp::min(
+                cmp::min(row[i] + 1, prev_row[i + 1] + 1),
+                prev_row[i] + replace_cost,
+            );
+            row.push(distance);
+        }
+        let distance = row[target.len()];
+        if distance <= max_distance && self.value.is_some() {
+            matches.push((prefix.clone(), distance, self));
+        }
+        if row.iter().min().is_some_and(|min| *min <= max_distance) {
+            for child_node in self.children.values() {
+                child_node.fuzzy_search_one_node(target, &row, max_distance, prefix, matches);
+            }
+        }
+        prefix.pop();
+    }
+
+    pub(crate) fn node_count(&self) -> usize {
+        if self.is_frozen {
+            self.node_count.unwrap()
+        } else {
+            let mut calc_count = 1;
+            for child_node in self.children.values() {
+                calc_count += child_node.node_count();
+            }
+            calc_count
+        }
+    }
+
+    pub(crate) fn word_count(&self) -> usize {
+        if self.is_frozen {
+            self.word_count.unwrap()
+        } else {
+            let mut count = if self.value.is_some() { 1 } else { 0 };
+            for child_node in self.children.values() {
+                count += child_node.word_count();
+            }
+            count
+        }
+    }
+
+    pub(crate) fn height(&self) -> usize {
+        if self.is_frozen {
+            self.height.unwrap()
+        } else {
+            let mut max_child_height = 0;
+            for child_node in self.children.values() {
+                let child_height = child_node.height();
+                if child_height > max_child_height {
+                    max_child_height = child_height;
+                }
+            }
+            max_child_height + 1
+        }
+    }
+
+    fn freeze(&mut self) {
+        if !self.is_frozen {
+            let mut node_count = 1;
+            let mut word_count = if self.value.is_some() { 1 } else { 0 };
+            let mut max_child_height = 0;
+            for child_node in self.children.values_mut() {
+                child_node.freeze();
+                node_count += child_node.node_count.unwrap();
+                word_count += child_node.word_count.unwrap();
+                max_child_height = cmp::max(max_child_height, child_node.height.unwrap());
+            }
+            self.node_count = Some(node_count);
+            self.word_count = Some(word_count);
+            self.height = Some(max_child_height + 1);
+            self.is_frozen = true;
+        }
+    }
+
+    fn unfreeze(&mut self) {
+        for child_node in self.children.values_mut() {
+            child_node.unfreeze();
+        }
+        self.unfreeze_one();
+    }
+
+    // Clear the cached values for this node only.
+    fn unfreeze_one(&mut self) {
+        self.node_count = None;
+        self.word_count = None;
+        self.height = None;
+        self.max_frequency = None;
+        self.is_frozen = false;
+    }
+
+    fn add_heap_size(&self, h: &mut HeapSize) {
+        h.node_count += 1;
+        if self.value.is_some() {
+            h.word_count += 1;
+        }
+        h.add_children::<K, Self>(self.children.len(), true);
+        for child_node in self.children.values() {
+            child_node.add_heap_size(h);
+        }
+    }
+
+    fn describe_one_line(&self) -> String {
+        let is_word_desc = if self.value.is_some() { " (word)" } else { "" };
+        format!("TrieMap: {:?}{}", self.key, is_word_desc)
+    }
+
+    fn describe_deep(&self, s: &mut String, depth: usize) {
+        s.push_str(&format!(
+            "{}\n",
+            format_indent(depth, &(self.describe_one_line()))
+        ));
+        if depth < DEBUG_TRIE_MAX_DEPTH {
+            for child_node in self.children.values().take(DEBUG_TRIE_MAX_CHILDREN) {
+                child_node.describe_deep(s, depth + 1);
+            }
+        }
+    }
+}
+
+impl<K: KeyElement, V: Frequency> TrieMap<K, V> {
+    /// The highest frequency of any word in the trie, or zero if there are no words.
+    pub fn max_frequency(&self) -> usize {
+        self.root.max_frequency()
+    }
+
+    /// Freeze the trie as with `freeze()` and also cache the highest frequency found in each subtree, which
+    /// lets `top_k_completions()` skip subtrees that can't contain any of the top words.
+    pub fn freeze_with_frequencies(&mut self) {
+        self.freeze();
+        self.root.freeze_max_frequency();
+    }
+
+    /// Find the `k` most frequent words that start with a prefix, from most to least frequent. Words with the same
+    /// frequency are in alphabetical order. The prefix itself is included if it's a word.
+    ///
+    /// This is a best-first search that always expands the subtree with the highest possible frequency, so once
+    /// `k` words have been found the rest of the trie is never visited. It works on any trie but it's much faster
+    /// after a call to `freeze_with_frequencies()` since otherwise the highest frequency in a subtree has to be
+    /// calculated by visiting the whole subtree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+    /// for (word, count) in vec![("cross", 50), ("create", 200), ("creature", 20), ("crease", 50), ("creek", 5)] {
+    ///     trie.insert(word, count);
+    /// }
+    /// trie.freeze_with_frequencies();
+    ///
+    /// let top: Vec<(String, usize)> = trie
+    ///     .top_k_completions("cr", 3)
+    ///     .into_iter()
+    ///     .map(|(word, count)| (word, *count))
+    ///     .collect();
+    /// assert_eq!(
+    ///     top,
+    ///     vec![("create".to_owned(), 200), ("crease".to_owned(), 50), ("cross".to_owned(), 50)]
+    /// );
+    /// ```
+    pub fn top_k_completions<Q: ToKeyElements<K> + ?Sized>(
+        &self,
+        prefix: &Q,
+        k: usize,
+    ) -> Vec<(K::Key, &V)> {
+        let mut completions = vec![];
+        let v = match prefix.to_key_elements(self.normalizer()) {
+            Some(v) => v,
+            None => return completions,
+        };
+        let start_node = match self.get_node(&v) {
+            Some(node) => node,
+            None => return completions,
+        };
+        let mut heap = BinaryHeap::new();
+        heap.push(Completion {
+            frequency: start_node.max_frequency(),
+            is_word: false,
+            prefix: v,
+            node: start_node,
+        });
+        while completions.len() < k {
+            let completion = match heap.pop() {
+                Some(completion) => completion,
+                None => break,
+            };
+            let node = completion.node;
+            if completion.is_word {
+                completions.push((
+                    K::make_key(&completion.prefix),
+                    node.value.as_ref().unwrap(),
+                ));
+            } else {
+                if let Some(value) = &node.value {
+                    heap.push(Completion {
+                        frequency: value.frequency(),
+                        is_word: true,
+                        prefix: completion.prefix.clone(),
+                        node,
+                    });
+                }
+                for (c, child_node) in node.children.iter() {
+                    let mut prefix = completion.prefix.clone();
+                    prefix.push(c.clone());
+                    heap.push(Completion {
+                        frequency: child_node.max_frequency(),
+                        is_word: false,
+                        prefix,
+                        node: child_node,
+                    });
+                }
+            }
+        }
+        completions
+    }
+}
+
+impl<K: KeyElement, V: Frequency> TrieMapNode<K, V> {
+    // The highest frequency of any word at this node or below it, or zero if there are no words.
+    fn max_frequency(&self) -> usize {
+        if let Some(max_frequency) = self.max_frequency {
+            max_frequency
+        } else {
+            let this_frequency = self.value.as_ref().map_or(0, |value| value.frequency());
+            self.children
+                .values()
+                .map(|child_node| child_node.max_frequency())
+                .fold(this_frequency, cmp::max)
+        }
+    }
+
+    fn freeze_max_frequency(&mut self) -> usize {
+        if let Some(max_frequency) = self.max_frequency {
+            max_frequency
+        } else {
+            let mut max_frequency = self.value.as_ref().map_or(0, |value| value.frequency());
+            for child_node in self.children.values_mut() {
+                max_frequency = cmp::max(max_frequency, child_node.freeze_max_frequency());
+            }
+            self.max_frequency = Some(max_frequency);
+            max_frequency
+        }
+    }
+}
+
+impl LetterTrieMap<usize> {
+    /// Create a trie from a frequency list where each line is a word, a tab and a count, as read by
+    /// `word_frequencies_from_file()`. A line with only a word counts as one occurrence, so a plain word list
+    /// can be loaded the same way. If a word appears more than once its counts are added together.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file does not exist or can't be opened for reading, or if a count isn't a number.
+    pub fn from_frequency_file(filename: &str) -> Self {
+        let mut t = Self::new();
+        for (word, count) in word_frequencies_from_file(filename) {
+            *t.entry(&word).or_insert(0) += count;
+        }
+        t
+    }
+}
+
+impl<V> LetterTrieMap<V> {
+    /// Check the whole trie as `LetterTrie::validate()` does, for a map with any type of value. There are no parent
+    /// links or reference counts. A node may be unfrozen above frozen nodes, since a change only clears the cached
+    /// counts on the path to the changed node, but a frozen node's children must all be frozen. The suffix index, if
+    /// there is one, isn't checked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<usize> = LetterTrieMap::new();
+    /// trie.insert("crease", 1);
+    /// trie.insert("creature", 2);
+    /// trie.freeze();
+    /// trie.remove("creature");
+    /// assert_eq!(trie.validate(), vec![]);
+    /// ```
+    pub fn validate(&self) -> Vec<TrieViolation> {
+        let mut v = TrieValidator::default();
+        self.root.validate_node(None, &mut String::new(), &mut v);
+        v.finish()
+    }
+
+    /// Iterate in alphabetical order over the words that start with a given prefix, returning each word in its
+    /// original spellings if the trie preserves surface forms. A word with more than one spelling appears once for
+    /// each spelling. Words without stored spellings are returned in their normalized form, the same as
+    /// `iter_prefix()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie: LetterTrieMap<usize> = LetterTrieMap::with_normalizer(Normalizer::make_folded_preserving());
+    /// trie.insert("iPhone", 1);
+    /// trie.insert("iPad", 2);
+    /// let words: Vec<(String, &usize)> = trie.iter_prefix_surface_forms("IP").collect();
+    /// assert_eq!(words, vec![("iPad".to_owned(), &2), ("iPhone".to_owned(), &1)]);
+    /// ```
+    pub fn iter_prefix_surface_forms(&self, prefix: &str) -> impl Iterator<Item = (String, &V)> {
+        let mut iter = self.iter_prefix(prefix);
+        iter::from_fn(move || iter.next_node()).flat_map(move |(key, node)| {
+            let value = node.value.as_ref().unwrap();
+            self.display_forms(&key)
+                .into_iter()
+                .map(move |form| (form, value))
+        })
+    }
+
+    /// Find the words within a given edit distance of a word as with `fuzzy_search()`, returning each word in its
+    /// original spellings if the trie preserves surface forms.
+    pub fn fuzzy_search_surface_forms(
+        &self,
+        word: &str,
+        max_distance: usize,
+    ) -> Vec<(String, usize, &V)> {
+        let mut matches = vec![];
+        for (key, distance, node) in self.fuzzy_search_nodes(word, max_distance) {
+            let value = node.value.as_ref().unwrap();
+            for form in self.display_forms(&key) {
+                matches.push((form, distance, value));
+            }
+        }
+        matches
+    }
+
+    // The stored spellings of a word, or the normalized word if there are none.
+    fn display_forms(&self, key: &[char]) -> Vec<String> {
+        match self.surface_forms.get(key) {
+            Some(surface_forms) => surface_forms.clone(),
+            None => vec![key.iter().collect()],
+        }
+    }
+
+    // The stats for the node reached by a sequence of chars that have already been normalized.
+    pub(crate) fn get_fixed_node(&self, v: &[char]) -> Option<FixedNode> {
+        self.get_node(v)
+            .map(|node| node.to_fixed_node_with_prefix(v.iter().collect(), &self.surface_forms))
+    }
+
+    // Add the nodes from the one reached by a sequence of normalized chars down to a graph.
+    fn write_graph_from<W: GraphWriter>(&self, w: &mut W, v: &[char]) -> Option<()> {
+        let node = self.get_node(v)?;
+        node.write_graph(w, &mut v.iter().collect(), 0, &self.surface_forms);
+        Some(())
+    }
+}
+
+impl<V> TrieMapNode<char, V> {
+    // Check a node and the ones below it for validate(), returning the counts for its subtree worked out without
+    // the cached values. `parent` is the key this node is stored under and the parent's depth, or None for the root.
+    fn validate_node(
+        &self,
+        parent: Option<(char, usize)>,
+        prefix: &mut String,
+        v: &mut TrieValidator,
+    ) -> SubtreeCounts {
+        let is_word = self.value.is_some();
+        v.check_node(
+            prefix,
+            parent,
+            self.key,
+            self.depth,
+            is_word,
+            self.children.len(),
+        );
+        let mut counts = SubtreeCounts::make_node(is_word);
+        for (key, child_node) in self.children.iter() {
+            if self.is_frozen && !child_node.is_frozen {
+                v.add(prefix, TrieViolationKind::UnfrozenChild { key: *key });
+            }
+            prefix.push(*key);
+            let child_counts = child_node.validate_node(Some((*key, self.depth)), prefix, v);
+            prefix.pop();
+            counts.add_child(&child_counts);
+        }
+        if self.is_frozen {
+            v.check_cached_counts(
+                prefix,
+                self.node_count,
+                self.word_count,
+                self.height,
+                &counts,
+            );
+        }
+        counts
+    }
+
+    // Add this node and the ones below it to a graph, returning the ID of this node if the writer included it.
+    fn write_graph<W: GraphWriter>(
+        &self,
+        w: &mut W,
+        prefix: &mut String,
+        depth: usize,
+        surface_forms: &BTreeMap<Vec<char>, Vec<String>>,
+    ) -> Option<usize> {
+        let node = if w.needs_counts() {
+            self.to_fixed_node_with_prefix(prefix.clone(), surface_forms)
+        } else {
+            shallow_fixed_node(
+                self.key,
+                self.depth,
+                self.value.is_some(),
+                self.children.len(),
+            )
+        };
+        let id = w.add_node(prefix, &node, depth)?;
+        let shown_count = w.children_to_show(depth, self.children.len());
+        for (c, child_node) in self.children.iter().take(shown_count) {
+            prefix.push(*c);
+            let child_id = child_node.write_graph(w, prefix, depth + 1, surface_forms);
+            prefix.pop();
+            if let Some(child_id) = child_id {
+                w.add_edge(id, child_id, *c);
+            }
+        }
+        Some(id)
+    }
+
+    // The stats for this node, with the stored spellings looked up by the node's prefix if it's a word.
+    fn to_fixed_node_with_prefix(
+        &self,
+        prefix: String,
+        surface_forms: &BTreeMap<Vec<char>, Vec<String>>,
+    ) -> FixedNode {
+        let surface_forms = if self.value.is_some() && !surface_forms.is_empty() {
+            let v: Vec<char> = prefix.chars().collect();
+            surface_forms.get(&v).cloned().unwrap_or_default()
+        } else {
+            vec![]
+        };
+        FixedNode {
+            c: self.key,
+            prefix,
+            depth: self.depth,
+            is_word: self.value.is_some(),
+            child_count: self.children.len(),
+            node_count: self.node_count(),
+            word_count: self.word_count(),
+            height: self.height(),
+            surface_forms,
+        }
+    }
+}
+
+impl LetterTrieSet {
+    pub fn add_from_vec_chars(&mut self, v: &[char]) {
+        if !v.is_empty() {
+            self.add_to_suffix_index(v);
+            self.root.get_or_create_node(v).value = Some(());
+        }
+    }
+
+    fn add_word_line(&mut self, word_line: WordLine, mut counts: Option<&mut InsertCounts>) {
+        let (vec_char, surface_form) = word_line;
+        if !vec_char.is_empty() {
+            self.add_to_suffix_index(&vec_char);
+            self.add_surface_form(&vec_char, surface_form);
+            let node = self
+                .root
+                .get_or_create_node_counted(&vec_char, counts.as_deref_mut());
+            if let Some(counts) = counts {
+                counts.record_word(node.value.is_some());
+            }
+            node.value = Some(());
+        }
+    }
+
+    // Normalize one line from a word file, keeping the trimmed line as the surface form if the normalizer
+    // preserves surface forms.
+    fn word_line(&self, line: &str, report: &mut BuildReport) -> Option<WordLine> {
+        let normalizer = self.normalizer();
+        report.load_line(normalizer, line).map(|vec_char| {
+            let surface_form = if normalizer.preserve_surface_forms {
+                Some(line.trim().to_owned())
+            } else {
+                None
+            };
+            (vec_char, surface_form)
+        })
+    }
+
+    // The equivalent of make_vec_char_test() that also keeps surface forms.
+    fn make_word_lines(
+        &self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) -> Vec<WordLine> {
+        let start = Instant::now();
+        let file = File::open(filename).unwrap();
+        let v: Vec<WordLine> = BufReader::new(file)
+            .lines()
+            .filter_map(|line| self.word_line(&line.unwrap(), report))
+            .collect();
+        report.metrics.record_elapsed(
+            opt.print_step_time,
+            &opt.label,
+            LABEL_STEP_READ_AND_VECTOR,
+            start,
+        );
+
+        if opt.object_detail_level >= 1 {
+            println!("\nWord count = {}", v.len());
+        }
+
+        if let Some(exp_word_count) = expected_word_count {
+            assert_eq!(v.len(), exp_word_count);
+        }
+
+        v
+    }
+
+    fn print(&self, detail_level: usize) {
+        match detail_level {
+            1 => println!("{:?}", self.to_fixed_node()),
+            2 => println!("{:#?}", self.to_fixed_node()),
+            _ => (),
+        }
+    }
+
+    fn load_read_vec_fill(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
+        let start = Instant::now();
+        let content = fs::read_to_string(filename).expect("Error reading file.");
+        report
+            .metrics
+            .record_elapsed(opt.print_step_time, &opt.label, LABEL_STEP_READ_FILE, start);
+
+        let start = Instant::now();
+        let words: Vec<WordLine> = content
+            .lines()
+            .filter_map(|x| self.word_line(x, report))
+            .collect();
+        if let Some(exp_word_count) = expected_word_count {
+            assert_eq!(words.len(), exp_word_count);
+        }
+        report.metrics.record_elapsed(
+            opt.print_step_time,
+            &opt.label,
+            LABEL_STEP_MAKE_VECTOR,
+            start,
+        );
+
+        if opt.object_detail_level >= 1 {
+            println!("\nWord count = {}", words.len());
+        }
+
+        let start = Instant::now();
+        for word_line in words {
+            self.add_word_line(
+                word_line,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
+        }
+        report.metrics.record_elapsed(
+            opt.print_step_time,
+            &opt.label,
+            LABEL_STEP_LOAD_FROM_VEC,
+            start,
+        );
+
+        self.print(opt.object_detail_level);
+    }
+
+    fn load_vec_fill(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
+        let start = Instant::now();
+        let v = self.make_word_lines(filename, opt, expected_word_count, report);
+        for word_line in v {
+            self.add_word_line(
+                word_line,
+                opt.count_inserts.then_some(&mut report.metrics.counts),
+            );
+        }
+        report.metrics.record_elapsed(
+            opt.print_step_time,
+            &opt.label,
+            LABEL_STEP_LOAD_FROM_VEC,
+            start,
+        );
+        self.print(opt.object_detail_level);
+    }
+
+    fn load_continuous(
+        &mut self,
+        filename: &str,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
+        let file = File::open(filename).unwrap();
+        let mut word_count = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line.unwrap();
+            if let Some(word_line) = self.word_line(&line, report) {
+                self.add_word_line(
+                    word_line,
+                    opt.count_inserts.then_some(&mut report.metrics.counts),
+                );
+                word_count += 1;
+            }
+        }
+        if let Some(exp_word_count) = expected_word_count {
+            assert_eq!(word_count, exp_word_count);
+        }
+    }
+
+    fn load_continuous_parallel(
+        &mut self,
+        filename: &str,
+        is_sorted: bool,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        report: &mut BuildReport,
+    ) {
+        let mut v = self.make_word_lines(filename, opt, expected_word_count, report);
+
+        if !is_sorted {
+            let start = Instant::now();
+            v.sort_unstable_by(|a, b| a.0[0].cmp(&b.0[0]));
+            report.metrics.record_elapsed(
+                opt.print_step_time,
+                &opt.label,
+                LABEL_STEP_SORT_VECTOR,
+                start,
+            );
+        }
+
+        let (tx, rx) = mpsc::channel();
+
+        let mut thread_count = 0;
+        let mut prev_c = ' ';
+        let mut this_vec: Vec<WordLine> = vec![];
+        for word_line in v {
+            let this_c = word_line.0[0];
+            if this_c != prev_c {
+                thread_count += Self::create_thread_for_part_of_vec(
+                    this_vec,
+                    opt.count_inserts,
+                    mpsc::Sender::clone(&tx),
+                );
+                this_vec = vec![];
+                prev_c = this_c;
+            }
+            this_vec.push(word_line);
+        }
+
+        thread_count += Self::create_thread_for_part_of_vec(
+            this_vec,
+            opt.count_inserts,
+            mpsc::Sender::clone(&tx),
+        );
+
+        for (received, thread_metrics) in rx.iter().take(thread_count) {
+            self.merge(received);
+            report.metrics.add_thread(thread_metrics);
+        }
+    }
+
+    // Returns the number of threads spawned, which will be 1 if there are items in the vector, otherwise 0.
+    fn create_thread_for_part_of_vec(
+        v: Vec<WordLine>,
+        count_inserts: bool,
+        tx: mpsc::Sender<(Self, ThreadMetrics)>,
+    ) -> usize {
+        if !v.is_empty() {
+            thread::spawn(move || {
+                let start = Instant::now();
+                let first_char = v[0].0[0];
+                let word_count = v.len();
+                let mut counts = InsertCounts::default();
+                let mut t = Self::new();
+                if count_inserts {
+                    counts.record_node();
+                }
+                for word_line in v {
+                    t.add_word_line(word_line, count_inserts.then_some(&mut counts));
+                }
+                let thread_metrics = ThreadMetrics {
+                    first_char,
+                    word_count,
+                    counts,
+                    elapsed: start.elapsed(),
+                };
+                tx.send((t, thread_metrics)).unwrap();
+            });
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl LetterTrie for LetterTrieSet {
+    fn from_file(filename: &str, is_sorted: bool, load_method: &LoadMethod) -> Self {
+        let opt = DisplayDetailOptions::make_no_display();
+        Self::from_file_test(filename, is_sorted, load_method, &opt, None).0
+    }
+
+    fn from_file_report(
+        filename: &str,
+        is_sorted: bool,
+        load_method: &LoadMethod,
+        opt: &DisplayDetailOptions,
+        expected_word_count: Option<usize>,
+        normalizer: &Normalizer,
+    ) -> (Self, BuildReport) {
+        let mut t = Self::with_normalizer(normalizer.clone());
+        let mut report = BuildReport::default();
+        report.metrics.is_counted = opt.count_inserts;
+        let start = Instant::now();
+        match load_method {
+            LoadMethod::ReadVecFill => {
+                t.load_read_vec_fill(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::VecFill => {
+                t.load_vec_fill(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::Continuous => {
+                t.load_continuous(filename, opt, expected_word_count, &mut report);
+            }
+            LoadMethod::ContinuousParallel => {
+                t.load_continuous_parallel(
+                    filename,
+                    is_sorted,
+                    opt,
+                    expected_word_count,
+                    &mut report,
+                );
+            }
+        };
+        report.metrics.record_elapsed(
+            opt.print_overall_time,
+            &opt.label,
+            LABEL_STEP_OVERALL,
+            start,
+        );
+        (t, report)
+    }
+
+    fn find(&self, prefix: &str) -> Option<FixedNode> {
+        let v: Vec<char> = prefix.to_key_elements(self.normalizer())?;
+        if v.is_empty() {
+            None
+        } else {
+            self.get_fixed_node(&v)
+        }
+    }
+
+    fn all_prefix_words(&self, s: &str) -> Vec<String> {
+        TrieMap::all_prefix_words(self, s)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    fn longest_prefix_word(&self, s: &str) -> Option<String> {
+        TrieMap::longest_prefix_word(self, s).map(|(word, _)| word)
+    }
+
+    fn enable_suffix_index(&mut self) {
+        TrieMap::enable_suffix_index(self);
+    }
+
+    fn disable_suffix_index(&mut self) {
+        TrieMap::disable_suffix_index(self);
+    }
+
+    fn has_suffix_index(&self) -> bool {
+        TrieMap::has_suffix_index(self)
+    }
+
+    fn words_with_suffix(&self, suffix: &str) -> Vec<String> {
+        TrieMap::words_with_suffix(self, suffix)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    fn words_with_prefix_and_suffix(&self, prefix: &str, suffix: &str) -> Vec<String> {
+        TrieMap::words_with_prefix_and_suffix(self, prefix, suffix)
+            .into_iter()
+            .map(|(word, _)| word)
+            .collect()
+    }
+
+    fn to_fixed_node(&self) -> FixedNode {
+        self.get_fixed_node(&[]).unwrap()
+    }
+
+    fn to_dot_with_options(&self, root_prefix: &str, opt: &DotOptions) -> Option<String> {
+        let prefix: Vec<char> = root_prefix.to_key_elements(self.normalizer())?;
+        let mut w = DotWriter::new(opt);
+        self.write_graph_from(&mut w, &prefix)?;
+        Some(w.finish())
+    }
+
+    fn to_d3_hierarchy(&self, root_prefix: &str, opt: &D3Options) -> Option<serde_json::Value> {
+        let prefix: Vec<char> = root_prefix.to_key_elements(self.normalizer())?;
+        let mut w = D3Writer::new(opt);
+        self.write_graph_from(&mut w, &prefix)?;
+        Some(w.finish())
+    }
+
+    fn stats(&self) -> TrieStats {
+        let mut w = StatsWriter::new();
+        self.write_graph_from(&mut w, &[]);
+        w.finish()
+    }
+
+    fn heap_size(&self) -> HeapSize {
+        TrieMap::heap_size(self)
+    }
+
+    fn validate(&self) -> Vec<TrieViolation> {
+        LetterTrieMap::validate(self)
+    }
+}
+
+impl<K: KeyElement, V> Debug for TrieMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            let mut s = String::new();
+            self.describe_deep(&mut s, 0);
+            write!(f, "{}", s)
+        } else {
+            let s = self.describe_one_line();
+            write!(f, "{}", s)
+        }
+    }
+}
+
+/// A view into a single key in a TrieMap, which may either be vacant or occupied. This is returned by
+/// `TrieMap::entry()`.
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// An entry for a key that's already in the trie.
+pub struct OccupiedEntry<'a, K, V> {
+    key: Vec<K>,
+    // This is always Some(value) since the word is in the trie.
+    value: &'a mut Option<V>,
+}
+
+/// An entry for a word that's not yet in the trie. Nodes aren't added to the trie until there's a value to insert.
+pub struct VacantEntry<'a, K, V> {
+    key: Vec<K>,
+    surface_form: Option<String>,
+    map: &'a mut TrieMap<K, V>,
+}
+
+impl<'a, K: KeyElement, V> Entry<'a, K, V> {
+    /// The key for this entry, which for a letter trie is the word in lowercase.
+    pub fn key(&self) -> K::Key {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    /// Insert the default value if the word isn't in the trie, then return a mutable reference to the value.
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// Insert the result of the function if the word isn't in the trie, then return a mutable reference to the
+    /// value.
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// If the word is in the trie, modify its value in place before any call to `or_insert()`.
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+impl<'a, K: KeyElement, V: Default> Entry<'a, K, V> {
+    /// Insert the default value for the type if the word isn't in the trie, then return a mutable reference to the
+    /// value.
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<'a, K: KeyElement, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> K::Key {
+        K::make_key(&self.key)
+    }
+
+    pub fn get(&self) -> &V {
+        self.value.as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.value.as_mut().unwrap()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.value.as_mut().unwrap()
+    }
+
+    /// Replace the value and return the old value.
+    pub fn insert(&mut self, value: V) -> V {
+        self.value.replace(value).unwrap()
+    }
+}
+
+impl<'a, K: KeyElement, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> K::Key {
+        K::make_key(&self.key)
+    }
+
+    /// Add the word to the trie with the given value and return a mutable reference to the value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.add_to_suffix_index(&self.key);
+        self.map.add_surface_form(&self.key, self.surface_form);
+        self.map
+            .root
+            .get_or_create_node(&self.key)
+            .value
+            .insert(value)
+    }
+}
+
+// One entry in the priority queue used by top_k_completions(). It's either a word waiting to be returned or a
+// subtree waiting to be expanded, in which case the frequency is the highest frequency found in that subtree.
+struct Completion<'a, K, V> {
+    frequency: usize,
+    is_word: bool,
+    prefix: Vec<K>,
+    node: &'a TrieMapNode<K, V>,
+}
+
+impl<'a, K: Ord, V> Ord for Completion<'a, K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap pops the greatest item first. Higher frequencies come first. Given the same frequency, a
+        // subtree comes before a word since the subtree might hold a word with that frequency that sorts earlier
+        // alphabetically. After that the earlier prefix comes first.
+        self.frequency
+            .cmp(&other.frequency)
+            .then_with(|| other.is_word.cmp(&self.is_word))
+            .then_with(|| other.prefix.cmp(&self.prefix))
+    }
+}
+
+impl<'a, K: Ord, V> PartialOrd for Completion<'a, K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, K: Ord, V> PartialEq for Completion<'a, K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, K: Ord, V> Eq for Completion<'a, K, V> {}
+
+/// Depth-first iterator over the keys in a TrieMap and their values, in sorted order.
+pub struct TrieMapIterator<'a, K, V> {
+    stack: Vec<(Vec<K>, &'a TrieMapNode<K, V>)>,
+}
+
+impl<'a, K: KeyElement, V> TrieMapIterator<'a, K, V> {
+    // Return the next node that holds a word along with its key elements.
+    fn next_node(&mut self) -> Option<(Vec<K>, &'a TrieMapNode<K, V>)> {
+        while let Some((prefix, node)) = self.stack.pop() {
+            // Push the children in reverse order so that the first child in alphabetical order is the next one
+            // popped from the stack.
+            for (c, child_node) in node.children.iter().rev() {
+                let mut child_prefix = prefix.clone();
+                child_prefix.push(c.clone());
+                self.stack.push((child_prefix, child_node));
+            }
+            if node.value.is_some() {
+                return Some((prefix, node));
+            }
+        }
+        None
+    }
+}
+
+impl<'a, K: KeyElement, V> Iterator for TrieMapIterator<'a, K, V> {
+    type Item = (K::Key, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_node()
+            .map(|(prefix, node)| (K::make_key(&prefix), node.value.as_ref().unwrap()))
+    }
+}
+
+/// Iterator over the keys in a TrieMap that fall within a range and their values, in sorted order. Returned by
+/// `TrieMap::range()`.
+pub struct TrieMapRange<'a, K, V> {
+    iter: TrieMapIterator<'a, K, V>,
+    end: Bound<Vec<K>>,
+}
+
+impl<'a, K: KeyElement, V> Iterator for TrieMapRange<'a, K, V> {
+    type Item = (K::Key, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (prefix, node) = self.iter.next_node()?;
+        let is_in_range = match &self.end {
+            Bound::Included(end) => prefix <= *end,
+            Bound::Excluded(end) => prefix < *end,
+            Bound::Unbounded => true,
+        };
+        if is_in_range {
+            Some((K::make_key(&prefix), node.value.as_ref().unwrap()))
+        } else {
+            // Every word after this one is also past the end of the range.
+            self.iter.stack.clear();
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILENAME_SMALL_FREQUENCIES: &str = "english_words_10_frequencies.txt";
+    const FILENAME_WORDS_TO_NORMALIZE: &str = "words_to_normalize.txt";
+
+    #[test]
+    fn small_root() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        assert_small_root(&t.to_fixed_node());
+    }
+
+    #[test]
+    fn small_root_all_load_methods() {
+        for dataset in [Dataset::TestSmallSorted, Dataset::TestSmallUnsorted].iter() {
+            for load_method in [
+                LoadMethod::ReadVecFill,
+                LoadMethod::VecFill,
+                LoadMethod::Continuous,
+                LoadMethod::ContinuousParallel,
+            ]
+            .iter()
+            {
+                let t =
+                    LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), load_method);
+                assert_small_root(&t.to_fixed_node());
+            }
+        }
+    }
+
+    #[test]
+    fn small_prefix_cross() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        assert_eq!(
+            t.find("cross"),
+            Some(FixedNode {
+                c: 's',
+                prefix: "cross".to_owned(),
+                depth: 5,
+                is_word: true,
+                child_count: 1,
+                node_count: 3,
+                word_count: 2,
+                height: 3,
+                surface_forms: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn normalized_all_load_methods() {
+        for load_method in [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ]
+        .iter()
+        {
+            for (normalizer, word_count) in [
+                (Normalizer::make_default(), 7),
+                (Normalizer::make_exact(), 8),
+                (Normalizer::make_ascii_letters(), 4),
+            ]
+            .iter()
+            {
+                let t = LetterTrieSet::from_file_normalized(
+                    FILENAME_WORDS_TO_NORMALIZE,
+                    false,
+                    load_method,
+                    &DisplayDetailOptions::make_no_display(),
+                    None,
+                    normalizer,
+                );
+                assert_eq!(t.to_fixed_node().word_count, *word_count);
+            }
+        }
+    }
+
+    #[test]
+    fn normalized_lookup() {
+        let mut t: LetterTrieMap<usize> =
+            LetterTrieMap::with_normalizer(Normalizer::make_ascii_letters());
+        assert_eq!(t.insert("Zürich", 1), None);
+        assert_eq!(t.insert("ZURICH", 2), Some(1));
+        assert_eq!(t.insert("r2d2", 3), None);
+        assert_eq!(t.len(), 1);
+        assert_eq!(t.get("zurich"), Some(&2));
+        assert_eq!(t.get("r2d2"), None);
+        assert_eq!(t.iter_prefix("zü").count(), 1);
+        assert_eq!(t.iter_prefix("z2").count(), 0);
+    }
+
+    #[test]
+    fn surface_forms_all_load_methods() {
+        for load_method in [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ]
+        .iter()
+        {
+            let t = LetterTrieSet::from_file_normalized(
+                FILENAME_WORDS_TO_NORMALIZE,
+                false,
+                load_method,
+                &DisplayDetailOptions::make_no_display(),
+                Some(8),
+                &Normalizer::make_folded_preserving(),
+            );
+            assert_eq!(
+                t.find("zurich").unwrap().surface_forms,
+                vec!["Zürich".to_owned(), "Zurich".to_owned()]
+            );
+            assert_eq!(t.surface_forms("CREME"), &["crème".to_owned()]);
+            assert_eq!(t.find("cr").unwrap().surface_forms, Vec::<String>::new());
+            let words: Vec<String> = t
+                .iter_prefix_surface_forms("p")
+                .map(|(word, _)| word)
+                .collect();
+            assert_eq!(words, vec!["Polish".to_owned(), "polish".to_owned()]);
+        }
+    }
+
+    #[test]
+    fn surface_forms_insert_remove() {
+        let mut t: LetterTrieMap<usize> =
+            LetterTrieMap::with_normalizer(Normalizer::make_folded_preserving());
+        t.insert("Zürich", 1);
+        *t.entry("ZÜRICH").or_insert(0) += 1;
+        assert_eq!(t.get("zurich"), Some(&2));
+        assert_eq!(
+            t.surface_forms("Zurich"),
+            &["Zürich".to_owned(), "ZÜRICH".to_owned()]
+        );
+        t.remove("zurich");
+        t.insert("zurich", 3);
+        assert_eq!(t.surface_forms("zurich"), &["zurich".to_owned()]);
+
+        let mut plain: LetterTrieMap<usize> = LetterTrieMap::new();
+        plain.insert("Zürich", 1);
+        assert!(plain.surface_forms("zürich").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_search() {
+        let mut t: LetterTrieMap<usize> =
+            LetterTrieMap::with_normalizer(Normalizer::make_folded_preserving());
+        for (index, word) in ["Zürich", "Zurich", "iPhone", "rich", "Munich"]
+            .iter()
+            .enumerate()
+        {
+            t.insert(*word, index);
+        }
+        assert_eq!(
+            t.fuzzy_search("zuerich", 1),
+            vec![("zurich".to_owned(), 1, &1)]
+        );
+        assert_eq!(
+            t.fuzzy_search_surface_forms("zuerich", 1),
+            vec![("Zürich".to_owned(), 1, &1), ("Zurich".to_owned(), 1, &1)]
+        );
+        let words: Vec<(String, usize)> = t
+            .fuzzy_search("munch", 3)
+            .into_iter()
+            .map(|(word, distance, _)| (word, distance))
+            .collect();
+        assert_eq!(
+            words,
+            vec![
+                ("munich".to_owned(), 1),
+                ("rich".to_owned(), 3),
+                ("zurich".to_owned(), 3)
+            ]
+        );
+        assert_eq!(t.fuzzy_search("iphone", 0).len(), 1);
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut t: LetterTrieMap<u32> = LetterTrieMap::new();
+        assert_eq!(t.insert("an", 1), None);
+        assert_eq!(t.insert("and", 2), None);
+        assert_eq!(t.insert(" AND ", 3), Some(2));
+        assert_eq!(t.insert("", 4), None);
+        assert_eq!(t.len(), 2);
+        assert_eq!(t.get("and"), Some(&3));
+        assert_eq!(t.get("a"), None);
+        assert!(t.contains_word("an"));
+
+        *t.get_mut("an").unwrap() += 10;
+        assert_eq!(t.get("an"), Some(&11));
+
+        assert_eq!(t.remove("an"), Some(11));
+        assert_eq!(t.remove("an"), None);
+        assert_eq!(t.node_count(), 4);
+        assert_eq!(t.remove("and"), Some(3));
+        assert_eq!(t.node_count(), 1);
+        assert!(t.is_empty());
+    }
+
+    #[test]
+    fn entry() {
+        let mut t: LetterTrieMap<usize> = LetterTrieMap::new();
+        for word in "the cat and the hat and the bat".split(' ') {
+            t.entry(word).and_modify(|count| *count += 1).or_insert(1);
+        }
+        *t.entry("cat").or_default() += 5;
+        let counts: Vec<(String, usize)> = t.iter().map(|(word, count)| (word, *count)).collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("and".to_owned(), 2),
+                ("bat".to_owned(), 1),
+                ("cat".to_owned(), 6),
+                ("hat".to_owned(), 1),
+                ("the".to_owned(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn iter_prefix() {
+        let dataset = Dataset::TestSmallSorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let words: Vec<String> = t.iter_prefix("cr").map(|(word, _)| word).collect();
+        let expected: Vec<String> = words_from_file(dataset.filename())
+            .into_iter()
+            .map(|word| word.to_lowercase())
+            .filter(|word| word.starts_with("cr"))
+            .collect();
+        assert_eq!(words, expected);
+        assert_eq!(t.iter_prefix("crx").count(), 0);
+        assert_eq!(t.iter().count(), dataset.word_count());
+    }
+
+    #[test]
+    fn freeze() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let mut t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let fixed_node = t.to_fixed_node();
+        t.freeze();
+        assert_eq!(t.to_fixed_node(), fixed_node);
+        t.insert("zzzzzzzzzzzzzzzzzzzzzz", ());
+        assert_eq!(t.word_count(), dataset.word_count() + 1);
+        assert_eq!(t.height(), 23);
+        t.freeze();
+        t.remove("zzzzzzzzzzzzzzzzzzzzzz");
+        assert_eq!(t.to_fixed_node(), fixed_node);
+    }
+
+    #[test]
+    fn validate() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let mut t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+        );
+        assert_eq!(t.validate(), vec![]);
+        // Check after each change, with the cached counts cleared only on the path to the changed node.
+        t.freeze();
+        for word in ["crease", "creature", "c", "crease"].iter() {
+            t.insert(*word, ());
+            assert_eq!(t.validate(), vec![]);
+            t.freeze();
+        }
+        for word in ["creature", "c", "crease", "not there"].iter() {
+            t.remove(*word);
+            assert_eq!(t.validate(), vec![]);
+        }
+
+        let mut t: LetterTrieMap<usize> = LetterTrieMap::new();
+        t.insert("ab", 1);
+        t.freeze();
+        let a = t.root.children.get_mut(&'a').unwrap();
+        a.word_count = Some(2);
+        let b = a.children.get_mut(&'b').unwrap();
+        b.value = None;
+        b.is_frozen = false;
+        let violations: Vec<(String, TrieViolationKind)> = t
+            .validate()
+            .into_iter()
+            .map(|violation| (violation.prefix, violation.kind))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    "a".to_owned(),
+                    TrieViolationKind::UnfrozenChild { key: 'b' }
+                ),
+                ("ab".to_owned(), TrieViolationKind::DeadEnd),
+                (
+                    "a".to_owned(),
+                    TrieViolationKind::CachedCount {
+                        name: "word_count",
+                        cached: Some(2),
+                        actual: 0
+                    }
+                ),
+                (
+                    "".to_owned(),
+                    TrieViolationKind::CachedCount {
+                        name: "word_count",
+                        cached: Some(1),
+                        actual: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_k_completions() {
+        let mut t = LetterTrieMap::from_frequency_file(FILENAME_SMALL_FREQUENCIES);
+        assert_eq!(t.len(), WORD_COUNT_SMALL);
+        let expected = vec![
+            ("creature".to_owned(), 180),
+            ("creative".to_owned(), 75),
+            ("cross".to_owned(), 75),
+        ];
+        for _ in 0..2 {
+            let top: Vec<(String, usize)> = t
+                .top_k_completions("cr", 3)
+                .into_iter()
+                .map(|(word, count)| (word, *count))
+                .collect();
+            assert_eq!(top, expected);
+            t.freeze_with_frequencies();
+        }
+        assert_eq!(t.max_frequency(), 3_000);
+        assert_eq!(t.top_k_completions("", 100).len(), WORD_COUNT_SMALL);
+        assert_eq!(t.top_k_completions("crx", 3).len(), 0);
+
+        // Changing a value after freezing clears the cached frequencies on the path to the word.
+        *t.get_mut("crossed").unwrap() = 500;
+        assert_eq!(t.top_k_completions("cr", 1)[0].0, "crossed");
+    }
+
+    #[test]
+    fn byte_keys() {
+        let mut t: ByteTrieMap<&str> = ByteTrieMap::new();
+        t.insert(&[10u8, 0][..], "private");
+        t.insert(&[192u8, 168][..], "private");
+        t.insert(&[192u8, 168, 1][..], "home");
+        t.insert("AB", "text");
+        assert_eq!(t.get(&[192u8, 168, 1][..]), Some(&"home"));
+        assert_eq!(t.get("ab"), None);
+        let keys: Vec<Vec<u8>> = t.iter_prefix(&[192u8][..]).map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![vec![192, 168], vec![192, 168, 1]]);
+        assert_eq!(t.iter().next().unwrap().0, vec![10, 0]);
+        assert_eq!(t.len(), 4);
+    }
+
+    #[test]
+    fn token_keys() {
+        let mut t: TokenTrieMap<usize> = TokenTrieMap::new();
+        for phrase in ["New York", "new york city", "New Zealand", "new york"].iter() {
+            *t.entry(*phrase).or_insert(0) += 1;
+        }
+        assert_eq!(t.get("new  YORK"), Some(&2));
+        assert_eq!(t.get("new"), None);
+        let top = t.top_k_completions("new", 1);
+        assert_eq!(top, vec![(vec!["new".to_owned(), "york".to_owned()], &2)]);
+        assert_eq!(t.node_count(), 5);
+    }
+
+    #[test]
+    fn merge() {
+        let mut t_1: LetterTrieMap<&str> = LetterTrieMap::new();
+        t_1.insert("cat", "noun");
+        t_1.insert("run", "verb");
+        let mut t_2: LetterTrieMap<&str> = LetterTrieMap::new();
+        t_2.insert("catch", "verb");
+        t_2.insert("run", "noun");
+        t_1.merge(t_2);
+        let pairs: Vec<(String, &&str)> = t_1.iter().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("cat".to_owned(), &"noun"),
+                ("catch".to_owned(), &"verb"),
+                ("run".to_owned(), &"noun"),
+            ]
+        );
+        let v: Vec<char> = "catch".chars().collect();
+        assert_eq!(t_1.get_node(&v).unwrap().depth, 5);
+    }
+
+    fn words<V>(pairs: Vec<(String, &V)>) -> Vec<String> {
+        pairs.into_iter().map(|(word, _)| word).collect()
+    }
+
+    #[test]
+    fn suffix_index_all_load_methods() {
+        let dataset = Dataset::TestSmallUnsorted;
+        for load_method in [
+            LoadMethod::ReadVecFill,
+            LoadMethod::VecFill,
+            LoadMethod::Continuous,
+            LoadMethod::ContinuousParallel,
+        ]
+        .iter()
+        {
+            let mut t =
+                LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), load_method);
+            assert_eq!(words(t.words_with_suffix("ed")), vec!["crossed"]);
+            t.enable_suffix_index();
+            assert!(t.has_suffix_index());
+            assert_eq!(words(t.words_with_suffix("ed")), vec!["crossed"]);
+            assert_eq!(words(t.words_with_suffix("re")), vec!["azure", "creature"]);
+            assert_eq!(words(t.words_with_prefix_and_suffix("a", "n")), vec!["an"]);
+            assert_eq!(
+                words(t.words_with_prefix_and_suffix("CR", "E")),
+                vec!["crease", "creative", "creature"]
+            );
+            assert_eq!(words(t.words_with_suffix("")).len(), WORD_COUNT_SMALL);
+            assert!(t.words_with_prefix_and_suffix("x", "e").is_empty());
+            assert!(t.words_with_suffix("q").is_empty());
+        }
+    }
+
+    #[test]
+    fn suffix_index_stays_in_sync() {
+        let mut t: LetterTrieMap<usize> = LetterTrieMap::new();
+        t.enable_suffix_index();
+        t.insert("geology", 1);
+        *t.entry("biology").or_insert(0) += 2;
+        t.insert("logo", 3);
+        assert_eq!(
+            t.words_with_suffix("logy"),
+            vec![("biology".to_owned(), &2), ("geology".to_owned(), &1)]
+        );
+        assert_eq!(t.remove("geology"), Some(1));
+        assert_eq!(words(t.words_with_suffix("logy")), vec!["biology"]);
+
+        let mut other: LetterTrieMap<usize> = LetterTrieMap::new();
+        other.insert("ecology", 4);
+        other.insert("aba", 5);
+        t.merge(other);
+        assert_eq!(
+            words(t.words_with_suffix("ology")),
+            vec!["biology", "ecology"]
+        );
+        // The prefix and suffix may overlap.
+        assert_eq!(
+            words(t.words_with_prefix_and_suffix("ab", "ba")),
+            vec!["aba"]
+        );
+        assert_eq!(
+            words(t.words_with_prefix_and_suffix("lo", "go")),
+            vec!["logo"]
+        );
+
+        // Once both tries are frozen the subtree with fewer words is walked.
+        t.freeze();
+        assert!(t.suffix_index.as_ref().unwrap().is_frozen);
+        assert_eq!(
+            words(t.words_with_prefix_and_suffix("b", "logy")),
+            vec!["biology"]
+        );
+        assert_eq!(words(t.words_with_prefix_and_suffix("", "a")), vec!["aba"]);
+
+        t.disable_suffix_index();
+        assert_eq!(
+            words(t.words_with_suffix("ology")),
+            vec!["biology", "ecology"]
+        );
+    }
+
+    #[test]
+    fn ordered_set_operations() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let mut t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::ContinuousParallel,
+        );
+        let sorted: Vec<String> = t.iter().map(|(word, _)| word).collect();
+        for is_frozen in [false, true].iter() {
+            if *is_frozen {
+                t.freeze();
+            }
+            for (n, word) in sorted.iter().enumerate() {
+                assert_eq!(t.rank(word), Some(n));
+                assert_eq!(t.select(n), Some((word.clone(), &())));
+            }
+            assert_eq!(t.select(sorted.len()), None);
+            assert_eq!(t.rank(""), Some(0));
+            assert_eq!(t.rank("cz"), Some(WORD_COUNT_SMALL));
+            assert_eq!(t.rank("crosse"), Some(9));
+            assert_eq!(t.rank("crossing"), Some(10));
+        }
+
+        let word = |pair: Option<(String, &())>| pair.map(|(word, _)| word);
+        assert_eq!(word(t.floor("cross")), Some("cross".to_owned()));
+        assert_eq!(word(t.floor("creatures")), Some("creature".to_owned()));
+        assert_eq!(word(t.floor("0")), None);
+        assert_eq!(word(t.ceiling("cross")), Some("cross".to_owned()));
+        assert_eq!(word(t.ceiling("az")), Some("azure".to_owned()));
+        assert_eq!(word(t.ceiling("d")), None);
+        assert_eq!(word(t.next_word("cross")), Some("crossed".to_owned()));
+        assert_eq!(word(t.next_word("crossed")), None);
+        assert_eq!(word(t.prev_word("Cross")), Some("creature".to_owned()));
+        assert_eq!(word(t.prev_word("a")), None);
+    }
+
+    #[test]
+    fn range() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let words = |range: TrieMapRange<'_, char, ()>| -> Vec<String> {
+            range.map(|(word, _)| word).collect()
+        };
+        assert_eq!(words(t.range("an".."azure")), vec!["an", "and"]);
+        assert_eq!(words(t.range("an"..="azure")), vec!["an", "and", "azure"]);
+        assert_eq!(
+            words(t.range("crea".."creb")),
+            vec!["crease", "creative", "creator", "creature"]
+        );
+        assert_eq!(words(t.range("cross"..)), vec!["cross", "crossed"]);
+        assert_eq!(words(t.range(.."an")), vec!["a"]);
+        assert_eq!(words(t.range::<str, _>(..)).len(), WORD_COUNT_SMALL);
+        assert_eq!(
+            words(t.range::<&str, _>((Bound::Excluded("cross"), Bound::Unbounded))),
+            vec!["crossed"]
+        );
+        assert_eq!(
+            words(t.range::<&str, _>((Bound::Excluded("creat"), Bound::Excluded("creature")))),
+            vec!["creative", "creator"]
+        );
+        assert!(words(t.range("b".."c")).is_empty());
+
+        let mut bytes: ByteTrieMap<usize> = ByteTrieMap::new();
+        for (i, key) in [&[1u8, 2][..], &[1, 3], &[2], &[2, 0, 1]]
+            .iter()
+            .enumerate()
+        {
+            bytes.insert(*key, i);
+        }
+        let values: Vec<usize> = bytes
+            .range(&[1u8, 3][..]..&[2, 0][..])
+            .map(|(_, value)| *value)
+            .collect();
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!(bytes.rank(&[2u8, 0, 1][..]), Some(3));
+    }
+}
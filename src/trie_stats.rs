@@ -0,0 +1,379 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::*;
+
+/// The number of entries kept in `TrieStats::deepest_words` and `TrieStats::widest_nodes`.
+pub const TRIE_STATS_TOP_COUNT: usize = 10;
+// The number of letters shown for each position in the text report. The JSON has all of them.
+const TEXT_LETTER_COUNT: usize = 5;
+
+/// A report on the shape of a trie, from `LetterTrie::stats()`. The counts include the root node as `FixedNode`
+/// does, and depths count from zero at the root so that the depth of a node is the length of its prefix.
+///
+/// The report can be printed as text with `{}` or turned into JSON with `to_json()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use letter_trie::*;
+///
+/// let dataset = Dataset::TestSmallUnsorted;
+/// let trie = BaseLetterTrie::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+///
+/// let stats = trie.stats();
+/// assert_eq!(stats.word_count, 10);
+/// assert_eq!(stats.deepest_words[0], "creative");
+/// // The root has two children, "a" and "c".
+/// assert_eq!(stats.branching_by_depth[0].get(&2), Some(&1));
+/// println!("{}", stats);
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct TrieStats {
+    pub word_count: usize,
+    pub node_count: usize,
+    pub height: usize,
+    /// Nodes with no children. Each one is the end of a word.
+    pub leaf_count: usize,
+    /// Nodes other than the root that aren't words and have exactly one child. These are the links in the chains
+    /// that a radix trie would collapse into a single edge.
+    pub unary_node_count: usize,
+    /// For each depth, the number of nodes with each number of children.
+    pub branching_by_depth: Vec<BTreeMap<usize, usize>>,
+    /// The number of words of each length.
+    pub word_length_counts: BTreeMap<usize, usize>,
+    /// For each position in a word starting from zero, the number of words with each letter in that position.
+    pub letter_counts_by_position: Vec<BTreeMap<char, usize>>,
+    /// The longest words, longest first and then in alphabetical order.
+    pub deepest_words: Vec<String>,
+    /// The prefixes of the nodes with the most children and their child counts, widest first. The root's prefix is
+    /// an empty string.
+    pub widest_nodes: Vec<(String, usize)>,
+}
+
+impl TrieStats {
+    /// The number of nodes the trie would have if each chain of unary nodes were collapsed as in a radix trie.
+    pub fn radix_node_count(&self) -> usize {
+        self.node_count - self.unary_node_count
+    }
+
+    pub fn to_json(&self) -> Value {
+        let branching_by_depth: Vec<Value> = self
+            .branching_by_depth
+            .iter()
+            .enumerate()
+            .map(|(depth, histogram)| {
+                json!({
+                    "depth": depth,
+                    "node_count": histogram.values().sum::<usize>(),
+                    "child_counts": histogram
+                        .iter()
+                        .map(|(children, nodes)| json!({ "children": children, "nodes": nodes }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let word_lengths: Vec<Value> = self
+            .word_length_counts
+            .iter()
+            .map(|(length, count)| json!({ "length": length, "words": count }))
+            .collect();
+        let letters_by_position: Vec<Value> = self
+            .letter_counts_by_position
+            .iter()
+            .map(|counts| {
+                let counts: serde_json::Map<String, Value> = counts
+                    .iter()
+                    .map(|(c, count)| (c.to_string(), json!(count)))
+                    .collect();
+                Value::Object(counts)
+            })
+            .collect();
+        let widest_nodes: Vec<Value> = self
+            .widest_nodes
+            .iter()
+            .map(|(prefix, child_count)| json!({ "prefix": prefix, "children": child_count }))
+            .collect();
+        json!({
+            "word_count": self.word_count,
+            "node_count": self.node_count,
+            "height": self.height,
+            "leaf_count": self.leaf_count,
+            "unary_node_count": self.unary_node_count,
+            "radix_node_count": self.radix_node_count(),
+            "branching_by_depth": branching_by_depth,
+            "word_lengths": word_lengths,
+            "letters_by_position": letters_by_position,
+            "deepest_words": self.deepest_words,
+            "widest_nodes": widest_nodes,
+        })
+    }
+}
+
+impl fmt::Display for TrieStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "words: {}", format_count(self.word_count))?;
+        writeln!(f, "nodes: {}", format_count(self.node_count))?;
+        writeln!(f, "height: {}", self.height)?;
+        writeln!(
+            f,
+            "leaves: {} ({})",
+            format_count(self.leaf_count),
+            format_percent(self.leaf_count, self.node_count)
+        )?;
+        writeln!(
+            f,
+            "unary nodes: {} ({}); a radix trie would have {} nodes",
+            format_count(self.unary_node_count),
+            format_percent(self.unary_node_count, self.node_count),
+            format_count(self.radix_node_count())
+        )?;
+
+        writeln!(f, "\nbranching by depth (children: nodes):")?;
+        for (depth, histogram) in self.branching_by_depth.iter().enumerate() {
+            let node_count: usize = histogram.values().sum();
+            let child_count: usize = histogram
+                .iter()
+                .map(|(child_count, node_count)| child_count * node_count)
+                .sum();
+            let histogram: Vec<String> = histogram
+                .iter()
+                .map(|(child_count, node_count)| {
+                    format!("{}: {}", child_count, format_count(*node_count))
+                })
+                .collect();
+            writeln!(
+                f,
+                "  {:>3}  {:>9} nodes  mean {:.2}  {}",
+                depth,
+                format_count(node_count),
+                child_count as f64 / node_count as f64,
+                histogram.join(", ")
+            )?;
+        }
+
+        writeln!(f, "\nword lengths:")?;
+        for (length, count) in self.word_length_counts.iter() {
+            writeln!(
+                f,
+                "  {:>3}  {:>9} words  {}",
+                length,
+                format_count(*count),
+                format_percent(*count, self.word_count)
+            )?;
+        }
+
+        writeln!(f, "\nmost common letters by position:")?;
+        for (position, counts) in self.letter_counts_by_position.iter().enumerate() {
+            let total: usize = counts.values().sum();
+            let mut counts: Vec<(&char, &usize)> = counts.iter().collect();
+            counts.sort_by_key(|(c, count)| (Reverse(**count), **c));
+            let letters: Vec<String> = counts
+                .iter()
+                .take(TEXT_LETTER_COUNT)
+                .map(|(c, count)| format!("{} {}", c, format_percent(**count, total)))
+                .collect();
+            writeln!(f, "  {:>3}  {}", position + 1, letters.join(", "))?;
+        }
+
+        writeln!(f, "\ndeepest words:")?;
+        for word in self.deepest_words.iter() {
+            writeln!(f, "  {:>3}  {}", word.chars().count(), word)?;
+        }
+
+        writeln!(f, "\nwidest nodes:")?;
+        for (prefix, child_count) in self.widest_nodes.iter() {
+            writeln!(f, "  {:>3}  \"{}\"", child_count, prefix)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_percent(count: usize, total: usize) -> String {
+    if total == 0 {
+        "0.0%".to_owned()
+    } else {
+        format!("{:.1}%", count as f64 * 100.0 / total as f64)
+    }
+}
+
+// Gathers the stats as a trie implementation walks every node once. It doesn't need the counts below each node,
+// only what's at the node itself.
+pub(crate) struct StatsWriter {
+    stats: TrieStats,
+    deepest_words: BTreeSet<(Reverse<usize>, String)>,
+    widest_nodes: BTreeSet<(Reverse<usize>, String)>,
+}
+
+impl StatsWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            stats: TrieStats {
+                word_count: 0,
+                node_count: 0,
+                height: 0,
+                leaf_count: 0,
+                unary_node_count: 0,
+                branching_by_depth: vec![],
+                word_length_counts: BTreeMap::new(),
+                letter_counts_by_position: vec![],
+                deepest_words: vec![],
+                widest_nodes: vec![],
+            },
+            deepest_words: BTreeSet::new(),
+            widest_nodes: BTreeSet::new(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> TrieStats {
+        let mut stats = self.stats;
+        stats.deepest_words = self
+            .deepest_words
+            .into_iter()
+            .map(|(_, word)| word)
+            .collect();
+        stats.widest_nodes = self
+            .widest_nodes
+            .into_iter()
+            .map(|(Reverse(child_count), prefix)| (prefix, child_count))
+            .collect();
+        stats
+    }
+}
+
+// Add an entry to one of the top lists, dropping whichever entry falls off the end.
+fn add_to_top(top: &mut BTreeSet<(Reverse<usize>, String)>, size: usize, label: &str) {
+    top.insert((Reverse(size), label.to_owned()));
+    if top.len() > TRIE_STATS_TOP_COUNT {
+        let last = top.iter().next_back().cloned().unwrap();
+        top.remove(&last);
+    }
+}
+
+impl GraphWriter for StatsWriter {
+    fn children_to_show(&self, _depth: usize, child_count: usize) -> usize {
+        child_count
+    }
+
+    fn add_node(&mut self, prefix: &str, node: &FixedNode, depth: usize) -> Option<usize> {
+        let stats = &mut self.stats;
+        stats.node_count += 1;
+        stats.height = stats.height.max(depth + 1);
+        if node.child_count == 0 {
+            stats.leaf_count += 1;
+        }
+        if depth > 0 && !node.is_word && node.child_count == 1 {
+            stats.unary_node_count += 1;
+        }
+        if stats.branching_by_depth.len() <= depth {
+            stats.branching_by_depth.push(BTreeMap::new());
+        }
+        *stats.branching_by_depth[depth]
+            .entry(node.child_count)
+            .or_insert(0) += 1;
+        if node.child_count > 0 {
+            add_to_top(&mut self.widest_nodes, node.child_count, prefix);
+        }
+
+        if node.is_word {
+            stats.word_count += 1;
+            *stats.word_length_counts.entry(depth).or_insert(0) += 1;
+            for (position, c) in prefix.chars().enumerate() {
+                if stats.letter_counts_by_position.len() <= position {
+                    stats.letter_counts_by_position.push(BTreeMap::new());
+                }
+                *stats.letter_counts_by_position[position]
+                    .entry(c)
+                    .or_insert(0) += 1;
+            }
+            add_to_top(&mut self.deepest_words, depth, prefix);
+        }
+        // Nothing is done with the edges so every node can have the same ID.
+        Some(0)
+    }
+
+    fn add_edge(&mut self, _parent_id: usize, _child_id: usize, _c: char) {}
+
+    fn needs_counts(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small() {
+        let dataset = Dataset::TestSmallUnsorted;
+        let t = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let stats = t.stats();
+        let root = t.to_fixed_node();
+        assert_eq!(stats.word_count, root.word_count);
+        assert_eq!(stats.node_count, root.node_count);
+        assert_eq!(stats.height, root.height);
+        // The leaves are "and", "azure", "crease", "creative", "creator", "creature" and "crossed".
+        assert_eq!(stats.leaf_count, 7);
+        assert_eq!(
+            stats.word_length_counts.values().sum::<usize>(),
+            WORD_COUNT_SMALL
+        );
+        assert_eq!(stats.word_length_counts.get(&1), Some(&1));
+        assert_eq!(stats.letter_counts_by_position[0].get(&'c'), Some(&6));
+        assert_eq!(stats.letter_counts_by_position.len(), stats.height - 1);
+        assert_eq!(
+            stats.deepest_words,
+            vec![
+                "creative", "creature", "creator", "crossed", "crease", "azure", "cross", "and",
+                "an", "a"
+            ]
+        );
+        assert_eq!(stats.widest_nodes[0], ("creat".to_owned(), 3));
+        assert_eq!(
+            stats
+                .branching_by_depth
+                .iter()
+                .map(|h| h.values().sum::<usize>())
+                .sum::<usize>(),
+            stats.node_count
+        );
+        assert_eq!(
+            stats.to_json()["radix_node_count"],
+            stats.radix_node_count()
+        );
+    }
+
+    #[test]
+    fn same_for_each_type() {
+        let dataset = Dataset::TestMediumUnsorted;
+        let base = BaseLetterTrie::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let no_parent = NoParentLetterTrie::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let map = LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        );
+        let stats = map.stats();
+        assert_eq!(base.stats(), stats);
+        assert_eq!(no_parent.stats(), stats);
+        assert_eq!(stats.word_count, WORD_COUNT_MEDIUM);
+        assert_eq!(stats.node_count, map.to_fixed_node().node_count);
+        assert_eq!(stats.deepest_words.len(), TRIE_STATS_TOP_COUNT);
+        assert!(stats.radix_node_count() < stats.node_count);
+    }
+}
@@ -0,0 +1,227 @@
+use std::cmp;
+use std::fmt;
+
+/// A way in which a trie breaks one of the rules that every implementation of `LetterTrie` keeps, found by
+/// `LetterTrie::validate()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrieViolationKind {
+    /// The root's character is not the sentinel `' '`.
+    RootSentinel { c: char },
+    /// The node's depth is not one more than its parent's, or the root's depth is not zero.
+    Depth { expected: usize, found: usize },
+    /// The node is stored in its parent's children under a key other than its own character.
+    KeyMismatch { key: char, c: char },
+    /// The node's link to its parent is missing or points to another node, or the root has a parent link. Only
+    /// `BaseLetterTrie` has parent links.
+    ParentLink,
+    /// The `Rc` holding the node doesn't have exactly one strong reference and one weak reference for each child.
+    /// Only `BaseLetterTrie` has reference counts.
+    RefCounts {
+        strong_count: usize,
+        weak_count: usize,
+        child_count: usize,
+    },
+    /// The node is frozen but one of its cached counts is missing or doesn't match the count worked out again from
+    /// the nodes below it.
+    CachedCount {
+        name: &'static str,
+        cached: Option<usize>,
+        actual: usize,
+    },
+    /// The node is frozen but one of its children isn't, so a change below it may have left its cached counts out
+    /// of date.
+    UnfrozenChild { key: char },
+    /// The node is not a word and has no children, so no word passes through it.
+    DeadEnd,
+}
+
+/// One violation found by `LetterTrie::validate()`, with the prefix of the node where it was found. The prefix is
+/// made from the keys on the path from the root so it's the one that would find the node, and it's empty for the
+/// root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrieViolation {
+    pub prefix: String,
+    pub kind: TrieViolationKind,
+}
+
+impl fmt::Display for TrieViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "root: ")?;
+        } else {
+            write!(f, "\"{}\": ", self.prefix)?;
+        }
+        match &self.kind {
+            TrieViolationKind::RootSentinel { c } => {
+                write!(f, "root character is {:?} instead of ' '", c)
+            }
+            TrieViolationKind::Depth { expected, found } => {
+                write!(f, "depth is {} instead of {}", found, expected)
+            }
+            TrieViolationKind::KeyMismatch { key, c } => {
+                write!(f, "stored under {:?} but its character is {:?}", key, c)
+            }
+            TrieViolationKind::ParentLink => write!(f, "parent link doesn't point to the parent"),
+            TrieViolationKind::RefCounts {
+                strong_count,
+                weak_count,
+                child_count,
+            } => write!(
+                f,
+                "strong count = {}; weak count = {}; children = {}",
+                strong_count, weak_count, child_count
+            ),
+            TrieViolationKind::CachedCount {
+                name,
+                cached,
+                actual,
+            } => write!(f, "cached {} is {:?} instead of {}", name, cached, actual),
+            TrieViolationKind::UnfrozenChild { key } => {
+                write!(f, "frozen but the child {:?} isn't", key)
+            }
+            TrieViolationKind::DeadEnd => write!(f, "not a word and has no children"),
+        }
+    }
+}
+
+// The counts for a subtree worked out from its nodes without using any cached values.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SubtreeCounts {
+    pub(crate) node_count: usize,
+    pub(crate) word_count: usize,
+    pub(crate) height: usize,
+}
+
+impl SubtreeCounts {
+    pub(crate) fn make_node(is_word: bool) -> Self {
+        Self {
+            node_count: 1,
+            word_count: if is_word { 1 } else { 0 },
+            height: 1,
+        }
+    }
+
+    pub(crate) fn add_child(&mut self, child: &SubtreeCounts) {
+        self.node_count += child.node_count;
+        self.word_count += child.word_count;
+        self.height = cmp::max(self.height, child.height + 1);
+    }
+}
+
+// Gathers the violations while one of the implementations walks its nodes, with the checks they have in common.
+#[derive(Default)]
+pub(crate) struct TrieValidator {
+    violations: Vec<TrieViolation>,
+}
+
+impl TrieValidator {
+    pub(crate) fn add(&mut self, prefix: &str, kind: TrieViolationKind) {
+        self.violations.push(TrieViolation {
+            prefix: prefix.to_owned(),
+            kind,
+        });
+    }
+
+    // Check a node's place in the trie. `parent` is the key the node is stored under and the parent's depth, or
+    // None for the root.
+    pub(crate) fn check_node(
+        &mut self,
+        prefix: &str,
+        parent: Option<(char, usize)>,
+        c: char,
+        depth: usize,
+        is_word: bool,
+        child_count: usize,
+    ) {
+        match parent {
+            None => {
+                if c != ' ' {
+                    self.add(prefix, TrieViolationKind::RootSentinel { c });
+                }
+                if depth != 0 {
+                    self.add(
+                        prefix,
+                        TrieViolationKind::Depth {
+                            expected: 0,
+                            found: depth,
+                        },
+                    );
+                }
+            }
+            Some((key, parent_depth)) => {
+                if key != c {
+                    self.add(prefix, TrieViolationKind::KeyMismatch { key, c });
+                }
+                if depth != parent_depth + 1 {
+                    self.add(
+                        prefix,
+                        TrieViolationKind::Depth {
+                            expected: parent_depth + 1,
+                            found: depth,
+                        },
+                    );
+                }
+                if !is_word && child_count == 0 {
+                    self.add(prefix, TrieViolationKind::DeadEnd);
+                }
+            }
+        }
+    }
+
+    // Check the cached counts of a frozen node against the ones worked out from the nodes below it.
+    pub(crate) fn check_cached_counts(
+        &mut self,
+        prefix: &str,
+        node_count: Option<usize>,
+        word_count: Option<usize>,
+        height: Option<usize>,
+        actual: &SubtreeCounts,
+    ) {
+        let counts = [
+            ("node_count", node_count, actual.node_count),
+            ("word_count", word_count, actual.word_count),
+            ("height", height, actual.height),
+        ];
+        for (name, cached, actual) in counts.iter() {
+            if *cached != Some(*actual) {
+                self.add(
+                    prefix,
+                    TrieViolationKind::CachedCount {
+                        name,
+                        cached: *cached,
+                        actual: *actual,
+                    },
+                );
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<TrieViolation> {
+        self.violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let violation = TrieViolation {
+            prefix: "cre".to_owned(),
+            kind: TrieViolationKind::Depth {
+                expected: 3,
+                found: 4,
+            },
+        };
+        assert_eq!(violation.to_string(), "\"cre\": depth is 4 instead of 3");
+        let violation = TrieViolation {
+            prefix: "".to_owned(),
+            kind: TrieViolationKind::RootSentinel { c: 'a' },
+        };
+        assert_eq!(
+            violation.to_string(),
+            "root: root character is 'a' instead of ' '"
+        );
+    }
+}
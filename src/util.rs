@@ -81,7 +81,7 @@ pub fn print_indent(depth: usize, s: &str) {
 
 pub fn print_elapsed<F>(display: bool, case_label: &str, step_label: &str, mut f: F)
 where
-    F: FnMut() -> (),
+    F: FnMut(),
 {
     let start = Instant::now();
     f();
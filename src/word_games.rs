@@ -0,0 +1,286 @@
+use std::collections::BTreeMap;
+use std::iter;
+
+use crate::trie_map::TrieMapNode;
+use crate::*;
+
+/// In a pattern, matches any one letter. In the letters for an anagram, a blank tile that can stand for any letter.
+pub const WILDCARD_ONE: char = '?';
+/// In a pattern, matches any run of letters including none.
+pub const WILDCARD_ANY: char = '*';
+
+// One piece of a parsed pattern.
+#[derive(Clone, Debug, PartialEq)]
+enum PatternToken {
+    Letter(char),
+    AnyOne,
+    AnyRun,
+}
+
+impl<V> LetterTrieMap<V> {
+    /// Find the words that match a crossword-style pattern where "?" stands for any one letter and "*" for any run
+    /// of letters including none, so "c?oss" matches "cross" and "cre*e" matches "crease" and "creative". The
+    /// letters in the pattern are normalized the same way as the words. The results are in alphabetical order.
+    ///
+    /// The search follows only the branches of the trie that can still match, so a pattern that starts with
+    /// letters is much faster than one that starts with "*".
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let dataset = Dataset::TestSmallUnsorted;
+    /// let trie = LetterTrieSet::from_file(dataset.filename(), dataset.is_sorted(), &LoadMethod::Continuous);
+    ///
+    /// let words: Vec<String> = trie.words_matching("CR?A*E").into_iter().map(|(word, _)| word).collect();
+    /// assert_eq!(words, vec!["crease", "creative", "creature"]);
+    /// assert_eq!(trie.words_matching("*d").len(), 2);
+    /// assert_eq!(trie.words_matching("a?").len(), 1);
+    /// assert!(trie.words_matching("z*").is_empty());
+    /// ```
+    pub fn words_matching(&self, pattern: &str) -> Vec<(String, &V)> {
+        let tokens = match self.parse_pattern(pattern) {
+            Some(tokens) => tokens,
+            None => return vec![],
+        };
+        // A pattern with more than one "*" can reach the same word in more than one way, so the map removes
+        // duplicates as well as keeping the words in order.
+        let mut matches = BTreeMap::new();
+        let mut prefix = vec![];
+        self.root()
+            .match_pattern(&tokens, &mut prefix, &mut matches);
+        matches
+            .into_iter()
+            .map(|(key, value)| (key.iter().collect(), value))
+            .collect()
+    }
+
+    // Split the pattern into letters and wildcards, normalizing each run of letters. Returns None if the normalizer
+    // rejects any of the letters, in which case nothing can match.
+    fn parse_pattern(&self, pattern: &str) -> Option<Vec<PatternToken>> {
+        let mut tokens = vec![];
+        let mut run = String::new();
+        for c in pattern.trim().chars().chain(iter::once(WILDCARD_ANY)) {
+            if c != WILDCARD_ONE && c != WILDCARD_ANY {
+                run.push(c);
+                continue;
+            }
+            for c in self.normalizer().to_chars(&run)? {
+                tokens.push(PatternToken::Letter(c));
+            }
+            run.clear();
+            tokens.push(if c == WILDCARD_ONE {
+                PatternToken::AnyOne
+            } else {
+                PatternToken::AnyRun
+            });
+        }
+        // Take off the "*" added to flush the last run of letters.
+        tokens.pop();
+        // Runs of "*" are the same as a single one.
+        tokens.dedup_by(|a, b| *a == PatternToken::AnyRun && *b == PatternToken::AnyRun);
+        Some(tokens)
+    }
+
+    /// Find the words that use exactly the given letters, each as many times as it appears, in alphabetical
+    /// order. A "?" is a blank that can stand for any letter, as in Scrabble. The letters are normalized the same
+    /// way as the words.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use letter_trie::*;
+    ///
+    /// let mut trie = LetterTrieSet::new();
+    /// for word in vec!["stop", "pots", "tops", "spot", "post", "pot", "stoop"] {
+    ///     trie.insert(word, ());
+    /// }
+    ///
+    /// let words: Vec<String> = trie.anagrams("OPTS").into_iter().map(|(word, _)| word).collect();
+    /// assert_eq!(words, vec!["post", "pots", "spot", "stop", "tops"]);
+    /// assert_eq!(trie.anagrams("opt?s").len(), 1);
+    /// assert_eq!(trie.words_from_letters("opts", 3).len(), 6);
+    /// ```
+    pub fn anagrams(&self, letters: &str) -> Vec<(String, &V)> {
+        self.words_from_letters_with_length(letters, 0, true)
+    }
+
+    /// Find the words that can be made from some or all of the given letters, using each letter no more times than
+    /// it appears, as in a word game where each tile can be used once. Only words of at least `min_length` letters
+    /// are returned. A "?" is a blank that can stand for any letter.
+    pub fn words_from_letters(&self, letters: &str, min_length: usize) -> Vec<(String, &V)> {
+        self.words_from_letters_with_length(letters, min_length, false)
+    }
+
+    fn words_from_letters_with_length(
+        &self,
+        letters: &str,
+        min_length: usize,
+        use_all: bool,
+    ) -> Vec<(String, &V)> {
+        let mut blank_count = 0;
+        let mut letter_counts: BTreeMap<char, usize> = BTreeMap::new();
+        for run in letters.trim().split(WILDCARD_ONE) {
+            if !run.is_empty() {
+                let v = match self.normalizer().to_chars(run) {
+                    Some(v) => v,
+                    None => return vec![],
+                };
+                for c in v {
+                    *letter_counts.entry(c).or_insert(0) += 1;
+                }
+            }
+            blank_count += 1;
+        }
+        // Splitting on "?" gives one more run than there are blanks.
+        blank_count -= 1;
+        let tile_count = letter_counts.values().sum::<usize>() + blank_count;
+        let min_length = if use_all { tile_count } else { min_length };
+
+        let mut matches = vec![];
+        let mut prefix = vec![];
+        self.root().match_letters(
+            &mut letter_counts,
+            &mut blank_count,
+            min_length,
+            &mut prefix,
+            &mut matches,
+        );
+        matches
+    }
+}
+
+impl<V> TrieMapNode<char, V> {
+    fn match_pattern<'a>(
+        &'a self,
+        tokens: &[PatternToken],
+        prefix: &mut Vec<char>,
+        matches: &mut BTreeMap<Vec<char>, &'a V>,
+    ) {
+        match tokens.first() {
+            None => {
+                if let Some(value) = self.value() {
+                    matches.insert(prefix.clone(), value);
+                }
+            }
+            Some(PatternToken::Letter(c)) => {
+                if let Some(child_node) = self.children().get(c) {
+                    prefix.push(*c);
+                    child_node.match_pattern(&tokens[1..], prefix, matches);
+                    prefix.pop();
+                }
+            }
+            Some(PatternToken::AnyOne) => {
+                for (c, child_node) in self.children().iter() {
+                    prefix.push(*c);
+                    child_node.match_pattern(&tokens[1..], prefix, matches);
+                    prefix.pop();
+                }
+            }
+            Some(PatternToken::AnyRun) => {
+                // Either the run ends here or it takes one more letter and carries on.
+                self.match_pattern(&tokens[1..], prefix, matches);
+                for (c, child_node) in self.children().iter() {
+                    prefix.push(*c);
+                    child_node.match_pattern(tokens, prefix, matches);
+                    prefix.pop();
+                }
+            }
+        }
+    }
+
+    fn match_letters<'a>(
+        &'a self,
+        letter_counts: &mut BTreeMap<char, usize>,
+        blank_count: &mut usize,
+        min_length: usize,
+        prefix: &mut Vec<char>,
+        matches: &mut Vec<(String, &'a V)>,
+    ) {
+        if let Some(value) = self.value() {
+            if prefix.len() >= min_length {
+                matches.push((prefix.iter().collect(), value));
+            }
+        }
+        for (c, child_node) in self.children().iter() {
+            // Use the real letter if there is one left, otherwise a blank.
+            let count = letter_counts.get_mut(c).filter(|count| **count > 0);
+            let used_letter = match count {
+                Some(count) => {
+                    *count -= 1;
+                    true
+                }
+                None if *blank_count > 0 => {
+                    *blank_count -= 1;
+                    false
+                }
+                None => continue,
+            };
+            prefix.push(*c);
+            child_node.match_letters(letter_counts, blank_count, min_length, prefix, matches);
+            prefix.pop();
+            if used_letter {
+                *letter_counts.get_mut(c).unwrap() += 1;
+            } else {
+                *blank_count += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words<V>(matches: Vec<(String, &V)>) -> Vec<String> {
+        matches.into_iter().map(|(word, _)| word).collect()
+    }
+
+    fn small_trie() -> LetterTrieSet {
+        let dataset = Dataset::TestSmallUnsorted;
+        LetterTrieSet::from_file(
+            dataset.filename(),
+            dataset.is_sorted(),
+            &LoadMethod::Continuous,
+        )
+    }
+
+    #[test]
+    fn pattern() {
+        let t = small_trie();
+        assert_eq!(words(t.words_matching("cross")), vec!["cross"]);
+        assert_eq!(words(t.words_matching("cross*")), vec!["cross", "crossed"]);
+        assert_eq!(words(t.words_matching("??")), vec!["an"]);
+        assert_eq!(
+            words(t.words_matching("*r*e")),
+            vec!["azure", "crease", "creative", "creature"]
+        );
+        assert_eq!(
+            words(t.words_matching("**a**t**")),
+            words(t.words_matching("*a*t*"))
+        );
+        assert_eq!(t.words_matching("*").len(), WORD_COUNT_SMALL);
+        assert_eq!(words(t.words_matching("creat??")), vec!["creator"]);
+        assert!(t.words_matching("").is_empty());
+        assert!(t.words_matching("x*").is_empty());
+    }
+
+    #[test]
+    fn anagrams() {
+        let t = small_trie();
+        assert_eq!(words(t.anagrams("dan")), vec!["and"]);
+        assert_eq!(words(t.anagrams("?n")), vec!["an"]);
+        assert_eq!(words(t.anagrams("ssorc")), vec!["cross"]);
+        assert!(t.anagrams("ssorcx").is_empty());
+        assert_eq!(
+            words(t.words_from_letters("dnaz", 1)),
+            vec!["a", "an", "and"]
+        );
+        assert_eq!(words(t.words_from_letters("dnaz", 3)), vec!["and"]);
+        assert_eq!(
+            words(t.words_from_letters("crossed??", 6)),
+            vec!["crease", "crossed"]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// Checks the heap size estimates against the memory a trie actually allocates. This is its own test binary so
+// that the counting allocator below doesn't become the allocator for the library's unit tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use letter_trie::*;
+
+// Counts the bytes allocated and freed by each thread so that a test can see how much memory a trie actually takes
+// while other tests run at the same time.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED_BYTES.try_with(|bytes| bytes.set(bytes.get() + layout.size() as isize));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = ALLOCATED_BYTES.try_with(|bytes| bytes.set(bytes.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocated_bytes() -> isize {
+    ALLOCATED_BYTES.with(|bytes| bytes.get())
+}
+
+// Build a trie on this thread and compare the estimate with the memory the trie holds on to.
+fn assert_close_to_measured<T: LetterTrie>(dataset: &Dataset) -> HeapSize {
+    // Make sure the dataset file exists before counting, since generating it allocates.
+    let filename = dataset.filename();
+    let before = allocated_bytes();
+    let t = T::from_file(filename, dataset.is_sorted(), &LoadMethod::Continuous);
+    let measured = (allocated_bytes() - before) as f64;
+    let heap_size = t.heap_size();
+    let estimated = heap_size.total_bytes() as f64;
+    assert!(
+        (estimated - measured).abs() / measured < 0.15,
+        "estimated {} bytes, measured {}",
+        estimated,
+        measured
+    );
+    heap_size
+}
+
+#[test]
+fn measured() {
+    let dataset = Dataset::TestMediumUnsorted;
+    let base = assert_close_to_measured::<BaseLetterTrie>(&dataset);
+    let no_parent = assert_close_to_measured::<NoParentLetterTrie>(&dataset);
+    let map = assert_close_to_measured::<LetterTrieSet>(&dataset);
+    for heap_size in [&base, &no_parent, &map].iter() {
+        assert_eq!(heap_size.word_count, dataset.word_count());
+        assert!(heap_size.bytes_per_node() > 0.0);
+    }
+    assert_eq!(base.node_count, map.node_count);
+    assert_eq!(no_parent.node_count, map.node_count);
+    // Storing the nodes inline saves the Rc allocations but leaves whole nodes' worth of empty slots in each
+    // BTreeMap node, which costs more on a trie where most nodes have one or two children.
+    assert!(no_parent.map_bytes > base.map_bytes);
+    assert!(no_parent.total_bytes() > base.total_bytes());
+}